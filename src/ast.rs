@@ -0,0 +1,191 @@
+//! The syntax tree the parser builds and the generator walks, in three
+//! layers: items declare classes, functions and imports; statements run;
+//! expressions compute values inside statements. Names are interned
+//! `Symbol`s; the session's interner spells them out again.
+
+use crate::lexer::Token;
+use crate::session::{Id, Symbol};
+
+/// A class field or function parameter: an untyped 8-byte slot, or a
+/// reference to another class. A class field may give the number it starts
+/// at when the object is made (`hp 100`). A generic class is interned
+/// whole, as `Box<Hero>`.
+#[derive(Debug, Clone)]
+pub struct Field { pub name: Symbol, pub class: Option<Symbol>, pub init: Option<f64> }
+
+/// A single value: a numeric literal or a variable/field path. An array
+/// index is a segment of its own, interned with its brackets (`[i]`).
+#[derive(Debug, Clone)]
+pub enum Operand { Num(f64), Path(Vec<Symbol>) }
+
+/// Arithmetic on numbers and paths with `+ - * /`; parentheses only
+/// survive as the shape of the tree, whose inner nodes live in the
+/// session's arena. With a float in it, the arithmetic is
+/// done in floating point; with a string literal or a text value in it, `+`
+/// joins text instead.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Num(f64),
+    /// A literal written with a fraction or exponent: `2.5`, `1e3`.
+    Float(f64),
+    Str(String),
+    Path(Vec<Symbol>),
+    Binary { op: Token, lhs: ExprId, rhs: ExprId },
+}
+
+/// An `if`/`while` condition: comparisons of a variable or field against
+/// an expression, joined by `and` and `or`. `and` binds tighter, and both
+/// short-circuit. The clauses `and`/`or` join live in the session's arena.
+#[derive(Debug, Clone)]
+pub enum Cond {
+    Cmp { path: Vec<Symbol>, op: Token, rhs: Expr },
+    And(CondId, CondId),
+    Or(CondId, CondId),
+}
+
+/// One piece of a `print` with several operands: literal text, or a value
+/// (a number, or a variable or field printed in decimal; text variables
+/// print their contents).
+#[derive(Debug, Clone)]
+pub enum PrintItem { Str(String), Value(Operand) }
+
+/// What `text append` adds: a string literal or another text variable.
+#[derive(Debug, Clone)]
+pub enum TextSrc { Lit(String), Var(Symbol) }
+
+/// A statement in the session's arena; statement bodies are lists of these.
+pub type StmtId = Id<Stmt>;
+
+/// A declaration in the session's arena.
+pub type ItemId = Id<Item>;
+
+/// An operand of a binary expression in the session's arena.
+pub type ExprId = Id<Expr>;
+
+/// A clause of an `and`/`or` condition in the session's arena.
+pub type CondId = Id<Cond>;
+
+/// Declarations: they define names for the rest of the program rather
+/// than run. Each one sits in its body as a `Stmt::Item`, in source order.
+#[derive(Debug, Clone)]
+pub enum Item {
+    Class { name: Symbol, params: Vec<Symbol>, fields: Vec<Field> },
+    /// `public` (`pub func`) also exports it under its own name for C callers.
+    Func { name: Symbol, params: Vec<Field>, body: Vec<StmtId>, public: bool },
+    /// `Get lib` pulls in `lib.hmr` from the importing file's directory.
+    /// The resolver fills in `file` and, at the first `Get` of each file,
+    /// the statements parsed from it; later ones keep an empty body.
+    Import { name: String, file: String, body: Vec<StmtId> },
+    /// `@python emit is ... done`: the resolver runs the script and parses
+    /// what it prints into `body`, which stands in for the block. `file`
+    /// names that output in diagnostics, as `main.hmr:3:@python`.
+    Emit { script: String, file: String, body: Vec<StmtId> },
+}
+
+/// Something that runs, in the order it appears.
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum Stmt {
+    /// A declaration where it appeared in the body.
+    Item(ItemId),
+    LocalAssign { name: Symbol, value: Expr },
+    /// `local p = new Player 100 50`: the values go to the first fields in
+    /// order; the rest start at their `init`, or 0.
+    HeapAlloc { var_name: Symbol, class_name: Symbol, args: Vec<Operand> },
+    ArrayAlloc { var_name: Symbol, class_name: Symbol, len: usize },
+    /// `local xs = array 10`: ten numbers, all 0 to start with.
+    NumArrayAlloc { var_name: Symbol, len: usize },
+    CopyAlloc { var_name: Symbol, src: Symbol },
+    MapAlloc { var_name: Symbol },
+    TextAlloc { var_name: Symbol },
+    /// `local answer = input` reads one line from stdin into a new text value.
+    Input { var_name: Symbol },
+    TextAppend { text: Vec<Symbol>, src: TextSrc },
+    MapPut { map: Vec<Symbol>, key: String, value: Operand },
+    MapGet { map: Vec<Symbol>, key: String, dest: Symbol },
+    /// `path = expr` on a variable or field.
+    FieldAssign { path: Vec<Symbol>, value: Expr },
+    /// `path = other.path` copies the value as is, usually an object pointer.
+    RefAssign { path: Vec<Symbol>, src: Vec<Symbol> },
+    PrintVar(Symbol),
+    PrintString(String),
+    /// `print "hp: " hero.hp " of " max`, written out in one pass.
+    PrintList(Vec<PrintItem>),
+    /// `eprint ...`: the print statement it holds, written to stderr.
+    Eprint(StmtId),
+    PrintFormat { fmt: String, args: Vec<Operand> },
+    /// `print hex v` (4 bits per digit) or `print bin v` (1 bit per digit).
+    PrintBits { bits: u32, value: Operand },
+    /// `cls`: clears the terminal and homes the cursor.
+    Cls,
+    /// `cursor x y` moves the cursor to column `x`, row `y` (1-based, as ANSI counts).
+    Cursor { x: Operand, y: Operand },
+    /// `color n` picks colour `n` of the 256-colour palette; a bare `color` resets.
+    Color(Option<Operand>),
+    /// `else_body` runs when the condition fails; an `elif` is an `if`
+    /// nested as the whole of it.
+    IfStmt { cond: Cond, body: Vec<StmtId>, else_body: Vec<StmtId> },
+    IfSame { lhs: Vec<Symbol>, rhs: Vec<Symbol>, body: Vec<StmtId>, else_body: Vec<StmtId> },
+    ProbIf { chance: Operand, body: Vec<StmtId>, else_body: Vec<StmtId> },
+    WhileStmt { cond: Cond, body: Vec<StmtId> },
+    /// `every 16 ms do ... done` runs its body forever, once per period.
+    Every { period: Operand, body: Vec<StmtId> },
+    /// `after 100 ms do ... done` runs its body once, that many
+    /// milliseconds later, from the main program's timer queue.
+    After { delay: Operand, body: Vec<StmtId> },
+    ForEach { var: Symbol, array: Symbol, body: Vec<StmtId> },
+    /// `sort wave by hp` orders an object array by one field, smallest
+    /// first; without `by`, by the first field.
+    Sort { array: Symbol, field: Option<Symbol> },
+    /// `local i = find wave 42 by hp`: the index of the first element whose
+    /// field equals the value, or -1.
+    Find { array: Symbol, value: Operand, field: Option<Symbol>, dest: Symbol },
+    Match { subject: Symbol, arms: Vec<(String, Vec<StmtId>)>, default: Vec<StmtId> },
+    /// One roll picks an arm; each arm's odds are its weight over the total.
+    Choose { arms: Vec<(u32, Vec<StmtId>)> },
+    /// `machine guard is state idle ... state chase ... done` runs the body
+    /// of its current state, starting with the first; the state is kept
+    /// between runs.
+    Machine { name: Symbol, states: Vec<(Symbol, Vec<StmtId>)> },
+    /// `go chase` switches the innermost machine to a state and runs it.
+    Go(Symbol),
+    /// `delete p` hands the object back; the next `new` of its class reuses
+    /// it, and `p` becomes 0.
+    Delete(Symbol),
+    Label(Symbol),
+    Goto(Symbol),
+    /// Checked only in `--checked` builds.
+    Assert(Cond),
+    Handler { kind: Symbol, body: Vec<StmtId> },
+    Spawn(Vec<StmtId>),
+    Join,
+    AtomicAdd { path: Vec<Symbol>, value: Operand },
+    Lock(Vec<Symbol>),
+    OnInterrupt(Vec<StmtId>),
+    Run { argv: Vec<String>, dest: Option<Symbol> },
+    Try { body: Vec<StmtId>, err: Option<Symbol>, rescue: Vec<StmtId> },
+    Unlock(Vec<Symbol>),
+    /// In a pipeline (`hero -> heal 5 -> show`) each call is nested as the
+    /// `from` of the next, and its result goes first in that call's arguments.
+    Call { name: Symbol, args: Vec<Operand>, dest: Option<Symbol>, from: Option<StmtId> },
+    Return(Option<Operand>),
+    AsmBlock(String),
+    IntelBlock(String),
+    PythonBlock { script: String, line: usize },
+}
+
+impl Stmt {
+    /// The blocks of statements nested directly in this one.
+    pub fn bodies(&self) -> Vec<&[StmtId]> {
+        match self {
+            Stmt::IfStmt { body, else_body, .. } | Stmt::IfSame { body, else_body, .. } | Stmt::ProbIf { body, else_body, .. } => vec![body, else_body],
+            Stmt::WhileStmt { body, .. } | Stmt::Every { body, .. } | Stmt::After { body, .. } | Stmt::ForEach { body, .. }
+            | Stmt::Handler { body, .. } | Stmt::Spawn(body) | Stmt::OnInterrupt(body) => vec![body],
+            Stmt::Try { body, rescue, .. } => vec![body, rescue],
+            Stmt::Match { arms, default, .. } => arms.iter().map(|(_, b)| b.as_slice()).chain([default.as_slice()]).collect(),
+            Stmt::Choose { arms } => arms.iter().map(|(_, b)| b.as_slice()).collect(),
+            Stmt::Machine { states, .. } => states.iter().map(|(_, b)| b.as_slice()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+use crate::source::FileLoader;
+
+/// Project settings read from `hamer.toml` in the working directory.
+/// Only flat `key = value` pairs are understood; sections and comments
+/// are skipped.
+pub struct Config {
+    /// Seconds a compile-time subprocess (e.g. @python) may run.
+    pub exec_timeout: Duration,
+    /// `strict_case = false` lets keywords be written in any case.
+    pub strict_case: bool,
+}
+
+impl Config {
+    pub fn load(loader: &dyn FileLoader) -> Self {
+        let mut config = Config { exec_timeout: Duration::from_secs(30), strict_case: true };
+        let Ok(text) = loader.read("hamer.toml") else { return config };
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "exec_timeout" => if let Ok(secs) = value.parse::<f64>() {
+                    config.exec_timeout = Duration::from_secs_f64(secs.max(0.0));
+                },
+                "strict_case" => if let Ok(strict) = value.parse() {
+                    config.strict_case = strict;
+                },
+                _ => {}
+            }
+        }
+        config
+    }
+}
@@ -0,0 +1,75 @@
+//! `hamer disasm`: lines up each H@mer statement with the assembly it
+//! turned into and the bytes the assembler encoded that as.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::generator::Generator;
+
+/// Merges `objdump -d -l` output for an object assembled with line info
+/// from `asm` with the generator's source map. Each source line gets a
+/// header, followed by its instructions as `address  bytes  assembly`.
+pub fn annotate(objdump: &str, asm: &str, generator: &Generator) -> String {
+    let asm_lines: Vec<&str> = asm.lines().collect();
+    let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+    let mut out = String::new();
+    // Line of `asm` the next instructions came from, and the last one shown
+    let mut asm_line = 0;
+    let mut shown_asm = 0;
+    let mut shown_src: Option<(String, usize)> = None;
+    // Alignment padding repeats one instruction; runs are shown once plus a count
+    let mut last_bytes = String::new();
+    let mut repeats = 0;
+    for line in objdump.lines() {
+        // llvm-objdump puts "; " before line info, GNU objdump doesn't
+        let line = line.trim_start_matches("; ");
+        if let Some((file, n)) = line.rsplit_once(':') && file.ends_with(".s") && let Ok(n) = n.trim().parse() {
+            asm_line = n;
+            continue;
+        }
+        if let Some(sym) = line.strip_suffix(">:").and_then(|l| l.split_once(" <")).map(|(_, s)| s) {
+            flush_repeats(&mut out, &mut repeats);
+            let _ = writeln!(out, "\n{}:", sym);
+            continue;
+        }
+        let Some((addr, rest)) = line.split_once(':') else { continue };
+        let addr = addr.trim();
+        if addr.is_empty() || !addr.chars().all(|c| c.is_ascii_hexdigit()) { continue; }
+        let bytes = rest.trim_start().split('\t').next().unwrap_or("").trim();
+        if asm_line == shown_asm && bytes == last_bytes {
+            repeats += 1;
+            continue;
+        }
+        flush_repeats(&mut out, &mut repeats);
+        last_bytes = bytes.to_string();
+
+        let loc = generator.source_location(asm_line);
+        if loc != shown_src.as_ref().map(|(f, l)| (f.as_str(), *l)) {
+            match loc {
+                Some((file, src_line)) => {
+                    let text = sources.entry(file.to_string())
+                        .or_insert_with(|| generator.session.sources.get(file).unwrap_or_default().lines().map(String::from).collect())
+                        .get(src_line.wrapping_sub(1)).map_or("", |t| t.trim());
+                    let _ = writeln!(out, "\n{}:{} | {}", file, src_line, text);
+                }
+                None => out.push_str("\n(no source line)\n"),
+            }
+            shown_src = loc.map(|(f, l)| (f.to_string(), l));
+        }
+        if asm_line != shown_asm {
+            let text = asm_lines.get(asm_line.wrapping_sub(1)).map_or("", |t| t.trim());
+            let _ = writeln!(out, "{:>8}  {:<12}  {}", addr, bytes, text);
+            shown_asm = asm_line;
+        } else {
+            let _ = writeln!(out, "{:>8}  {}", addr, bytes);
+        }
+    }
+    flush_repeats(&mut out, &mut repeats);
+    out
+}
+
+fn flush_repeats(out: &mut String, repeats: &mut usize) {
+    if *repeats > 0 {
+        let _ = writeln!(out, "{:>8}  (repeated {} more times)", "...", repeats);
+        *repeats = 0;
+    }
+}
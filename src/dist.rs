@@ -0,0 +1,47 @@
+//! `hamer dist`: the finished, stripped binary and what it asks of the
+//! kernel, for people shipping small demos.
+
+use std::collections::BTreeSet;
+
+/// Linux ARM64 numbers of the system calls codegen and the runtime make.
+const SYSCALL_NAMES: &[(u32, &str)] = &[
+    (63, "read"),
+    (64, "write"),
+    (93, "exit"),
+    (94, "exit_group"),
+    (98, "futex"),
+    (113, "clock_gettime"),
+    (115, "clock_nanosleep"),
+    (132, "sigaltstack"),
+    (134, "rt_sigaction"),
+    (220, "clone"),
+    (221, "execve"),
+    (222, "mmap"),
+    (260, "wait4"),
+];
+
+/// Numbers of the system calls `asm` makes: the last `mov x8, #n` before
+/// each `svc #0`. That covers `@asm` blocks written the usual way too.
+pub fn syscalls(asm: &str) -> BTreeSet<u32> {
+    let mut found = BTreeSet::new();
+    let mut number = None;
+    for line in asm.lines().map(str::trim) {
+        if let Some(n) = line.strip_prefix("mov x8, #") {
+            number = n.parse().ok();
+        } else if line.starts_with("svc") && let Some(n) = number {
+            found.insert(n);
+        }
+    }
+    found
+}
+
+/// `write (64), exit (93)` style list of `numbers`.
+pub fn describe(numbers: &BTreeSet<u32>) -> String {
+    let names: Vec<String> = numbers.iter().map(|n| {
+        match SYSCALL_NAMES.iter().find(|(k, _)| k == n) {
+            Some((_, name)) => format!("{} ({})", name, n),
+            None => format!("#{}", n),
+        }
+    }).collect();
+    if names.is_empty() { "none".to_string() } else { names.join(", ") }
+}
@@ -1,193 +1,3276 @@
-use std::collections::HashMap;
-use std::process::Command;
-use crate::lexer::{Lexer, Token};
-use crate::parser::{Parser, Stmt};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::io::{self, Read};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::lexer::Token;
+use crate::ast::{Cond, Expr, Field, Item, ItemId, Operand, PrintItem, Stmt, StmtId, TextSrc};
+use crate::runtime;
+use crate::session::{kw, Interner, Session, Symbol};
+use crate::target::Target;
+
+/// Formats assembly straight into the generator's output buffer.
+macro_rules! emit {
+    ($g:expr, $($arg:tt)*) => {{ let _ = write!($g.output, $($arg)*); }};
+}
+
+/// Built-in value kinds other than integers and user class objects. Maps
+/// and text live on the heap; a float is kept as its IEEE 754 bits in the
+/// variable's own register or slot.
+#[derive(Clone, Copy, PartialEq)]
+enum Builtin { Map, Text, Float }
+
+/// Where a variable lives: its own register, or once those run out, an
+/// 8-byte slot in the stack frame of the code body that defined it.
+#[derive(Clone)]
+enum Loc {
+    Reg(String),
+    /// Slot `n` of the frame set up for body `frame` (see `fn_scope`).
+    Slot { frame: usize, n: usize },
+}
+
+/// Signature of a user function. The first pass works out the return class
+/// where it can; generating the body fills in the rest.
+#[derive(Clone)]
+struct FuncInfo {
+    params: Vec<Field>,
+    ret_class: Option<Symbol>,
+}
 
 pub struct Generator {
     pub output: String,
-    symbols: HashMap<String, String>,
-    class_map: HashMap<String, Vec<String>>,
-    obj_types: HashMap<String, String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Skip @python blocks instead of failing when python3 can't be started.
+    pub allow_missing_python: bool,
+    /// Let files pulled in with `Get` execute code at compile time.
+    pub allow_run: bool,
+    /// Check divisors at run time and exit with a message instead of
+    /// letting sdiv quietly produce 0.
+    pub check_div: bool,
+    /// Describe stack frames with .cfi directives so debuggers can unwind.
+    pub cfi: bool,
+    /// Record which source line each assembly line came from; see `source_map_json`.
+    pub sourcemap: bool,
+    /// Trap on crashes, division by zero, bad indices and failed `assert`s,
+    /// reporting the source line from a table embedded in the program.
+    pub checked: bool,
+    /// Check the stack pointer against a per-thread limit on entry to
+    /// every function, reporting runaway recursion by name.
+    pub stack_guard: bool,
+    /// Symbol the top-level code starts at.
+    pub entry: String,
+    /// Compile the top-level code as a subroutine for a larger program
+    /// instead of a process entry point; see `gen_subroutine_entry`.
+    pub no_runtime: bool,
+    /// How long a compile-time subprocess may run before it is killed.
+    pub exec_timeout: Duration,
+    /// Source file currently being generated, for diagnostics.
+    pub file: String,
+    /// The arenas the parser and resolver filled, included files and all.
+    pub session: Session,
+    pub target: Target,
+    include_depth: usize,
+    symbols: HashMap<Symbol, Loc>,
+    class_map: HashMap<Symbol, Vec<Field>>,
+    generics: HashMap<Symbol, (Vec<Symbol>, Vec<Field>)>,
+    obj_types: HashMap<Symbol, Symbol>,
+    /// Element counts of arrays. `obj_types` holds the element class of an
+    /// object array; number arrays have none.
+    arrays: HashMap<Symbol, usize>,
+    /// Loop variables pointing into the object array they walk, by array.
+    elements: HashMap<Symbol, Symbol>,
+    builtins: HashMap<Symbol, Builtin>,
     reg_count: usize,
+    /// Stack slots handed out in the frame of the body being generated.
+    slots: usize,
     label_count: usize,
+    deferred: String,
+    /// Zero-initialized slots for .bss, as (label, size, alignment) in bytes.
+    bss: Vec<(String, usize, usize)>,
+    vectors: [Option<String>; 4],
+    funcs: HashMap<Symbol, FuncInfo>,
+    /// The definition each function name was declared from in the first pass.
+    func_items: HashMap<Symbol, ItemId>,
+    current_fn: Option<Symbol>,
+    runtime: Vec<&'static str>,
+    /// Descriptor print statements write to: 1, or 2 inside `eprint`.
+    out_fd: u32,
+    /// Symbols of `pub func` wrappers, as C sees them.
+    exports: Vec<String>,
+    /// Label, state names and scope of each machine being generated,
+    /// innermost last, for `go`.
+    machines: Vec<(String, Vec<Symbol>, usize)>,
+    /// Body label of each `after`, in order; slot `k` of the timer queue
+    /// holds the deadline of the `k`th.
+    timers: Vec<String>,
+    /// Scopes of `after` bodies, which run on the main program's frame.
+    timer_scopes: Vec<usize>,
+    /// Map keys in order of first use; the `k`th one's record is `key{k}`.
+    map_keys: Vec<String>,
+    /// Classes with a free list, in order; the `k`th list is `free{k}`.
+    free_lists: Vec<Symbol>,
+    /// The program has a `delete`, so `new` looks at the free lists first.
+    deletes: bool,
+    /// The program has a `spawn`, so each thread reaches its own heap end
+    /// and stack limit through its block (see `THREAD_BLOCK`).
+    spawns: bool,
+    /// Some allocation checks for room with `__hamer_heap_reserve`.
+    heap_reserve: bool,
+    rescues: Vec<String>,
+    /// Code body being generated: 0 is the main program, and each function,
+    /// thread, handler and interrupt body gets its own number. A `goto`
+    /// only reaches labels in its own body.
+    scope: usize,
+    scope_count: usize,
+    /// Scope of the innermost function body being generated.
+    fn_scope: usize,
+    /// First variable register of the innermost function being generated.
+    fn_regs: usize,
+    /// Label a top-level `return` jumps to, with the exit status in x0.
+    exit_label: Option<String>,
+    /// Self-recursive calls in tail position, lowered to a jump.
+    tail_calls: HashSet<StmtId>,
+    /// `label`s seen so far, as (scope, name).
+    labels: HashSet<(usize, Symbol)>,
+    /// Every `goto`, as (scope, name), checked once all labels are known.
+    gotos: Vec<(usize, Symbol)>,
+    /// File and line of the statement being generated, for the source map.
+    loc: Option<(String, usize)>,
+    /// Assembly line (1-based) where each run of lines from one source
+    /// location starts; `None` for lines no statement produced.
+    source_map: Vec<(usize, Option<(String, usize)>)>,
+    /// Label at the start of each run of code from one location, for the
+    /// `--checked` location table.
+    loc_labels: Vec<(String, Option<(String, usize)>)>,
+    /// Line of the statement being generated, for warnings.
+    line: usize,
 }
 
+/// Register use in generated code. Hand-written `@asm` must leave the
+/// heap pointer and the variable registers it doesn't own alone.
+/// - x0-x7: arguments, return values and runtime routine inputs; any call
+///   or runtime routine may clobber them
+/// - x8: system call number
+/// - x9-x11: scratch, only live within one statement
+/// - x12-x27: variables, one register per name for the whole program;
+///   later ones get stack slots addressed from x29, see `slot_addr`
+/// - x28: heap pointer, the next free byte of the bump heap
+/// - x29, x30: frame pointer and link register
+/// - d0-d7: float arithmetic, only live within one statement
+const FIRST_VAR_REG: usize = 12;
+const LAST_VAR_REG: usize = 27;
+const HEAP_REG: &str = "x28";
+
+/// Stack slots one frame can address with a scaled `ldr` offset.
+const MAX_SLOTS: usize = (32760 - 16) / 8;
+
+/// Starts a line recording `loc` in the output while building a source
+/// map. The markers are taken out again before the assembly is returned.
+const LOC_MARK: &str = "//@loc ";
+
+/// Lines standing in for the register saves around a recursive call until
+/// the function's body is done; see `fill_saves`.
+const SAVE_MARK: &str = "//@save";
+const RESTORE_MARK: &str = "//@restore";
+
+/// Bytes mapped for the bump heap at startup, and the least added when it fills.
+const HEAP_SIZE: usize = 1 << 20;
+
+/// Stack carved from the heap for each `spawn`ed thread.
+const THREAD_STACK: usize = 16 * 1024;
+
+/// Bytes at the bottom of a spawned thread's stack for its block: the end
+/// of its heap chunk, its stack limit, its tid (cleared when it exits) and
+/// the next block in the list `join` walks. tpidr_el0 points at the
+/// running thread's block.
+const THREAD_BLOCK: usize = 32;
+
+/// Stack the main thread may use under `--stack-guard`; below the usual
+/// 8 MiB limit, so the check fires before the kernel's guard page does.
+const MAIN_STACK_BUDGET: usize = 7 << 20;
+
+/// Stack left below a spawned thread's limit for reporting the overflow.
+const STACK_RESERVE: usize = 1024;
+
+/// clone() flags for a thread sharing memory, files and signal handlers;
+/// the kernel sets its tpidr_el0 to its block, writes the tid for `join`
+/// and clears it (with a futex wake) when the thread exits.
+const CLONE_THREAD_FLAGS: u64 = 0x100 | 0x200 | 0x400 | 0x800 | 0x10000 | 0x40000 | 0x80000 | 0x100000 | 0x200000;
+
+/// Error codes handed to `rescue err` when a runtime operation fails.
+const ERR_SYSCALL: u32 = 1;
+const ERR_DIV_ZERO: u32 = 2;
+const ERR_BOUNDS: u32 = 3;
+
+/// Exit status of a `--checked` program stopped by a trap, and of one that
+/// crashed (128 + SIGSEGV, as if the signal had killed it).
+const TRAP_STATUS: u32 = 134;
+const CRASH_STATUS: u32 = 139;
+
+/// Offset of the saved pc in the ucontext handed to an SA_SIGINFO handler.
+const UCONTEXT_PC: usize = 440;
+
+/// Alternate signal stack for the crash handler, so a stack overflow can
+/// still be reported.
+const SIGNAL_STACK: usize = 16 * 1024;
+
+/// Exception kinds in the order of an AArch64 vector table group.
+pub(crate) const VECTOR_KINDS: [&str; 4] = ["sync", "irq", "fiq", "serror"];
+
 impl Generator {
     pub fn new() -> Self {
         Self {
-            output: ".global _start\n.section .text\n\n_start:\n    mov x11, #10\n    mov x0, #0\n    mov x1, #4096\n    mov x2, #3\n    mov x3, #34\n    mov x4, #-1\n    mov x5, #0\n    mov x8, #222\n    svc #0\n    mov x20, x0\n".to_string(),
+            output: String::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            allow_missing_python: false,
+            allow_run: false,
+            check_div: false,
+            cfi: false,
+            sourcemap: false,
+            checked: false,
+            out_fd: 1,
+            exports: Vec::new(),
+            stack_guard: false,
+            entry: "_start".to_string(),
+            no_runtime: false,
+            exec_timeout: Duration::from_secs(30),
+            file: String::new(),
+            session: Session::default(),
+            target: Target::default(),
+            include_depth: 0,
             symbols: HashMap::new(),
             class_map: HashMap::new(),
+            generics: HashMap::new(),
             obj_types: HashMap::new(),
-            reg_count: 12,
+            arrays: HashMap::new(),
+            elements: HashMap::new(),
+            builtins: HashMap::new(),
+            reg_count: FIRST_VAR_REG,
+            slots: 0,
             label_count: 0,
+            deferred: String::new(),
+            bss: Vec::new(),
+            vectors: Default::default(),
+            funcs: HashMap::new(),
+            func_items: HashMap::new(),
+            current_fn: None,
+            runtime: Vec::new(),
+            machines: Vec::new(),
+            timers: Vec::new(),
+            timer_scopes: Vec::new(),
+            map_keys: Vec::new(),
+            free_lists: Vec::new(),
+            deletes: false,
+            spawns: false,
+            heap_reserve: false,
+            rescues: Vec::new(),
+            scope: 0,
+            scope_count: 0,
+            fn_scope: 0,
+            fn_regs: FIRST_VAR_REG,
+            exit_label: None,
+            tail_calls: HashSet::new(),
+            labels: HashSet::new(),
+            gotos: Vec::new(),
+            loc: None,
+            source_map: Vec::new(),
+            loc_labels: Vec::new(),
+            line: 0,
         }
     }
 
-    fn get_path_info(&self, path: &Vec<String>) -> (String, usize) {
-        let base_var = &path[0];
-        let reg = self.symbols.get(base_var).cloned().unwrap_or("x0".to_string());
+    fn name(&self, sym: Symbol) -> &str {
+        self.session.interner.resolve(sym)
+    }
+
+    /// `path` as written, e.g. `hero.hp`.
+    fn path_name(&self, path: &[Symbol]) -> String {
+        self.session.interner.join(path, ".")
+    }
+
+    fn is_index(&self, segment: Symbol) -> bool {
+        self.name(segment).starts_with('[')
+    }
+
+    /// The variable or number inside index segment `segment` (`[i]`), if it is one.
+    fn index_of(&self, segment: Symbol) -> Option<String> {
+        let name = self.name(segment);
+        name.strip_prefix('[').and_then(|n| n.strip_suffix(']')).map(str::to_string)
+    }
+
+    /// Resolves a path to `[reg, #offset]`. Paths through object-typed fields
+    /// (`hero.target.hp`) load each intermediate pointer into x9 first, as
+    /// does a base variable kept on the stack.
+    fn get_path_info(&mut self, path: &[Symbol]) -> (String, usize) {
+        let base_var = path[0];
+        let mut reg = match self.symbols.get(&base_var).cloned() {
+            Some(loc) => self.var_in(base_var, &loc, "x9"),
+            None => "x0".to_string(),
+        };
         let mut offset = 0;
-        if path.len() > 1 {
-            if let Some(c) = self.obj_types.get(base_var) {
-                if let Some(fields) = self.class_map.get(c) {
-                    offset = fields.iter().position(|f| f == &path[1]).unwrap_or(0) * 8;
-                }
+        let mut class = self.obj_types.get(&base_var).copied();
+        // True once `reg + offset` addresses a field rather than `reg` holding the value.
+        let mut in_mem = false;
+        for (i, &name) in path.iter().enumerate().skip(1) {
+            if in_mem {
+                emit!(self, "    ldr x9, [{}, #{}]\n", reg, offset);
+                reg = "x9".to_string();
+                offset = 0;
+            }
+            if let Some(index) = self.index_of(name) {
+                self.gen_index(&path[..i], &reg, &index, class);
+                reg = "x9".to_string();
+                in_mem = false;
+                continue;
             }
+            if i == 1 && let Some(len) = self.arrays.get(&base_var) {
+                let base = self.name(base_var);
+                self.errors.push(format!("`{}` is an array of {} objects; index it like `{}[0].{}`", base, len, base, self.name(name)));
+            }
+            let fields = class.and_then(|c| self.class_map.get(&c));
+            let Some(idx) = fields.and_then(|f| f.iter().position(|f| f.name == name)) else {
+                match class {
+                    Some(c) => self.errors.push(format!("class `{}` has no field `{}` (in `{}`)", self.name(c), self.name(name), self.path_name(path))),
+                    None => self.errors.push(format!("`{}` is not an object of a known class, so `{}` can't be found", self.path_name(&path[..i]), self.path_name(path))),
+                }
+                return (reg, 0);
+            };
+            offset = idx * 8;
+            class = fields.and_then(|f| f.get(idx)).and_then(|f| f.class);
+            in_mem = true;
         }
         (reg, offset)
     }
 
-    pub fn generate(&mut self, ast: Vec<Stmt>) -> String {
-        for s in ast { self.gen_stmt(s); }
-        self.output.push_str("\n    mov x0, #0\n    mov x8, #93\n    svc #0\n");
-        self.output.clone()
+    /// True if a path names a word in memory (a field, or an element of a
+    /// number array) rather than a value held in a register (a plain
+    /// variable or an object array element's address).
+    fn in_memory(&self, path: &[Symbol]) -> bool {
+        match *path {
+            [array, index] if self.is_index(index) => self.is_number_array(array),
+            _ => path.len() > 1 && !path.last().is_some_and(|&s| self.is_index(s)),
+        }
     }
 
-    fn gen_stmt(&mut self, stmt: Stmt) {
-        match stmt {
-            Stmt::MergeBlock(content) => {
-                let mut lexer = Lexer::new(content);
-                let mut tokens = Vec::new();
-                loop {
-                    let t = lexer.next_token();
-                    if t == Token::EOF { break; }
-                    tokens.push(t);
-                }
-                let mut parser = Parser::new(tokens);
-                let sub_ast = parser.parse_program();
-                for s in sub_ast { self.gen_stmt(s); }
+    fn is_number_array(&self, var: Symbol) -> bool {
+        self.arrays.contains_key(&var) && !self.obj_types.contains_key(&var)
+    }
+
+    /// Puts the address of element `index` of the array in `base` into x9.
+    /// Constant indices are checked at compile time, variable ones inside `try`.
+    fn gen_index(&mut self, array: &[Symbol], base: &str, index: &str, class: Option<Symbol>) {
+        let size = class.and_then(|c| self.class_map.get(&c)).map_or(8, |f| f.len() * 8);
+        let len = if array.len() == 1 { self.arrays.get(&array[0]).copied() } else { None };
+        if let Ok(n) = index.parse::<usize>() {
+            if let Some(len) = len && n >= len {
+                self.errors.push(format!("index {} is out of bounds for `{}` ({} elements)", n, self.path_name(array), len));
             }
-            Stmt::PythonBlock(script) => {
-                let out = Command::new("python3").arg("-c").arg(&script).output().expect("Python failed");
-                let res = String::from_utf8_lossy(&out.stdout).to_string();
-                self.output.push_str(&format!("\n    // Python Output: {}\n", res.trim()));
+            if n * size < 4096 {
+                emit!(self, "    add x9, {}, #{}\n", base, n * size);
+            } else {
+                emit!(self, "    ldr x10, ={}\n    add x9, {}, x10\n", n * size, base);
             }
-            Stmt::IntelBlock(code) => {
-                self.output.push_str("\n    .intel_syntax noprefix\n");
-                self.output.push_str(&format!("    {}\n", code));
-                self.output.push_str("    .att_syntax\n");
+            return;
+        }
+        let var = self.session.interner.intern(index);
+        let Some(loc) = self.symbols.get(&var).cloned() else {
+            self.errors.push(format!("unknown index variable `{}` in `{}[{}]`", index, self.path_name(array), index));
+            return;
+        };
+        let ireg = self.var_in(var, &loc, "x11");
+        if !self.rescues.is_empty() {
+            emit!(self, "    ldur x10, [{}, #-8]\n    cmp {}, x10\n", base, ireg);
+            self.gen_check("hs", ERR_BOUNDS);
+        } else if self.checked {
+            let l = self.new_label("index");
+            emit!(self, "    ldur x10, [{}, #-8]\n    cmp {}, x10\n    b.lo {}\n", base, ireg, l);
+            self.gen_trap(&format!("index out of bounds in `{}[{}]`", self.path_name(array), index), TRAP_STATUS, false);
+            emit!(self, "{}:\n", l);
+        }
+        self.gen_mov_imm("x10", size as i64);
+        emit!(self, "    madd x9, {}, x10, {}\n", ireg, base);
+    }
+
+    pub fn generate(&mut self, ast: Vec<StmtId>) -> String {
+        // Most statements lower to a handful of instructions; reserving up
+        // front keeps large programs from repeatedly regrowing the buffer.
+        self.output.reserve(ast.len() * 160);
+        self.deletes = self.session.stmts.iter().any(|s| matches!(s, Stmt::Delete(_)));
+        self.spawns = self.session.stmts.iter().any(|s| matches!(s, Stmt::Spawn(_)));
+        emit!(self, ".global {}\n{}{}\n\n{}:\n", self.entry, self.target.function_type(&self.entry), self.target.text_section(), self.entry);
+        // Where the end of the first heap chunk gets recorded, if anything checks it
+        let mut heap_at = None;
+        if self.no_runtime {
+            self.gen_subroutine_entry();
+        } else {
+            if self.cfi {
+                // Nothing called the entry point, so unwinding stops here
+                self.output.push_str("    .cfi_startproc\n    .cfi_undefined x30\n");
             }
-            Stmt::AsmBlock(code) => { self.output.push_str(&format!("    {}\n", code)); }
-            Stmt::ProbIf { chance, body } => {
-                let id = self.label_count; self.label_count += 1;
-                let math_reg = self.symbols.get("math").cloned().unwrap_or("x12".into());
-                self.output.push_str(&format!("\n    // Chaos Roll {}%\n    ldr x1, [{}, #8]\n", chance, math_reg));
-                self.output.push_str(&format!("    cmp x1, #0\n    b.ne .Lskp{}\n    mrs x1, cntvct_el0\n.Lskp{}:\n", id, id));
-                self.output.push_str("    ldr x2, =0x9E3779B97F4A7C15\n    mul x1, x1, x2\n    eor x1, x1, x1, lsr #33\n");
-                self.output.push_str(&format!("    str x1, [{}, #8]\n", math_reg));
-                self.output.push_str("    and x1, x1, #0x7FFFFFFF\n    mov x2, #100\n    udiv x3, x1, x2\n    msub x1, x3, x2, x1\n");
-                self.output.push_str(&format!("    cmp x1, #{}\n    b.hs .Lif{}\n", chance as i64, id));
-                for s in body { self.gen_stmt(s); }
-                self.output.push_str(&format!(".Lif{}:\n", id));
-            }
-            Stmt::IfStmt { path, op, rhs_val, body } => {
-                let id = self.label_count; self.label_count += 1;
-                let (reg, offset) = self.get_path_info(&path);
-                if path.len() > 1 {
-                    self.output.push_str(&format!("    ldr x1, [{}, #{}]\n", reg, offset));
+            emit!(self, "    mov x0, #0\n    mov x1, #{}\n    mov x2, #3\n    mov x3, #34\n    mov x4, #-1\n    mov x5, #0\n    mov x8, #222\n    svc #0\n    mov x28, x0\n", HEAP_SIZE);
+            heap_at = Some(self.output.len());
+            if self.spawns {
+                // The main thread's block is the heap end word and the one after it
+                let block = self.heap_end();
+                self.gen_addr("x10", &block);
+                self.output.push_str("    msr tpidr_el0, x10\n");
+            }
+            if self.stack_guard {
+                emit!(self, "    mov x9, sp\n    ldr x10, ={:#x}\n    sub x9, x9, x10\n", MAIN_STACK_BUDGET);
+                if self.spawns {
+                    self.output.push_str("    mrs x10, tpidr_el0\n    str x9, [x10, #8]\n");
                 } else {
-                    self.output.push_str(&format!("    mov x1, {}\n", reg));
+                    // Nothing else uses the thread pointer, so it holds the stack limit
+                    self.output.push_str("    msr tpidr_el0, x9\n");
                 }
-                let cond = match op {
-                    Token::Equal => "ne",
-                    Token::Greater => "le",
-                    Token::Less => "ge",
-                    _ => "eq",
-                };
-                self.output.push_str(&format!("    cmp x1, #{}\n    b.{} .Lif{}\n", rhs_val as i64, cond, id));
-                for s in body { self.gen_stmt(s); }
-                self.output.push_str(&format!(".Lif{}:\n", id));
             }
-            Stmt::WhileStmt { path, op, rhs_val, body } => {
-                let id = self.label_count; self.label_count += 1;
-                self.output.push_str(&format!(".Lw_start{}:\n", id));
-                let (reg, offset) = self.get_path_info(&path);
-                if path.len() > 1 {
-                    self.output.push_str(&format!("    ldr x1, [{}, #{}]\n", reg, offset));
-                } else {
-                    self.output.push_str(&format!("    mov x1, {}\n", reg));
+        }
+        let frame_at = self.output.len();
+        if self.checked { self.gen_crash_handler(); }
+        self.declare(&ast);
+        self.gen_block(ast);
+        if !self.timers.is_empty() { self.gen_timer_wait(); }
+        self.check_gotos();
+        let frame = self.slots.div_ceil(2) * 16;
+        if frame > 0 {
+            let mut setup = format!("{}    stp x29, x30, [sp, #-16]!\n    mov x29, sp\n", adjust_sp("sub", frame));
+            if self.cfi {
+                let above = if self.no_runtime { 96 } else { 0 };
+                let _ = writeln!(setup, "    .cfi_def_cfa x29, {}", above + frame + 16);
+            }
+            self.output.insert_str(frame_at, &setup);
+        }
+        if self.no_runtime {
+            self.gen_subroutine_exit(frame);
+        } else {
+            self.output.push_str("\n    mov x0, #0\n");
+            self.gen_exit_label();
+            self.output.push_str("    mov x8, #93\n    svc #0\n");
+        }
+        if self.cfi { self.output.push_str("    .cfi_endproc\n"); }
+        let size = self.target.symbol_size(&self.entry);
+        self.output.push_str(&size);
+        self.output.push_str(&self.deferred);
+        self.loc = None;
+        self.mark_loc();
+        if !self.timers.is_empty() { self.gen_timer_queue(); }
+        if self.checked {
+            let segv = format!("{}segv", self.target.local_prefix());
+            emit!(self, "\n{}:\n    ldr x2, [x2, #{}]\n", segv, UCONTEXT_PC);
+            self.gen_trap("segmentation fault", CRASH_STATUS, true);
+        }
+        // Growing a text or a map moves it to a fresh buffer, which may need a fresh chunk
+        if self.runtime.iter().any(|r| ["__hamer_text_append", "__hamer_map_put"].contains(r)) { self.heap_reserve = true; }
+        if self.heap_reserve { self.gen_heap_reserve(heap_at); }
+        debug!("runtime routines: {}", if self.runtime.is_empty() { "none".to_string() } else { self.runtime.join(", ") });
+        for name in &self.runtime {
+            self.output.push_str(&self.target.function_type(name));
+            self.output.push_str(&self.target.localize(runtime::source(name)));
+            self.output.push_str(&self.target.symbol_size(name));
+        }
+        if self.vectors.iter().any(|v| v.is_some()) {
+            self.gen_vector_table();
+        }
+        if self.checked { self.gen_loc_table(); }
+        self.gen_bss();
+        if self.sourcemap { self.take_loc_marks(); }
+        std::mem::take(&mut self.output)
+    }
+
+    /// With `no_runtime`, the entry is an AAPCS64 function the host program
+    /// calls: it takes memory for the heap in x0 (0 if the program allocates
+    /// nothing) and saves the callee-saved registers it uses for variables.
+    fn gen_subroutine_entry(&mut self) {
+        if self.cfi { self.output.push_str("    .cfi_startproc\n"); }
+        self.gen_aapcs_prologue();
+        self.output.push_str("    mov x28, x0\n");
+    }
+
+    /// Returns 0, or the value of a top-level `return`, to the host program,
+    /// dropping the `frame` bytes of variable slots and restoring what
+    /// `gen_subroutine_entry` saved.
+    fn gen_subroutine_exit(&mut self, frame: usize) {
+        self.output.push_str("\n    mov x0, #0\n");
+        self.gen_exit_label();
+        if frame > 0 {
+            self.output.push_str(&frame_epilogue(frame));
+            if self.cfi { self.output.push_str("    .cfi_def_cfa x29, 96\n"); }
+        }
+        self.gen_aapcs_epilogue();
+    }
+
+    /// Places the label top-level `return`s jump to, if there were any.
+    fn gen_exit_label(&mut self) {
+        if let Some(l) = self.exit_label.clone() { emit!(self, "{}:\n", l); }
+    }
+
+    /// Pushes a frame record and x19-x28, which AAPCS64 callers expect to
+    /// survive the call but H@mer code uses for variables and the heap.
+    fn gen_aapcs_prologue(&mut self) {
+        self.output.push_str("    stp x29, x30, [sp, #-96]!\n");
+        if self.cfi { self.output.push_str("    .cfi_def_cfa_offset 96\n    .cfi_offset x29, -96\n    .cfi_offset x30, -88\n"); }
+        self.output.push_str("    mov x29, sp\n");
+        if self.cfi { self.output.push_str("    .cfi_def_cfa x29, 96\n"); }
+        for r in (19..29).step_by(2) {
+            emit!(self, "    stp x{}, x{}, [sp, #{}]\n", r, r + 1, (r - 17) * 8);
+            if self.cfi { emit!(self, "    .cfi_offset x{}, -{}\n    .cfi_offset x{}, -{}\n", r, 96 - (r - 17) * 8, r + 1, 88 - (r - 17) * 8); }
+        }
+    }
+
+    /// Pushes a frame record with `frame` bytes of variable slots above it
+    /// and points x29 at the record.
+    fn frame_prologue(&self, frame: usize) -> String {
+        let mut out = String::new();
+        if frame > 0 {
+            out.push_str(&adjust_sp("sub", frame));
+            if self.cfi { let _ = writeln!(out, "    .cfi_def_cfa_offset {}", frame); }
+        }
+        out.push_str("    stp x29, x30, [sp, #-16]!\n");
+        if self.cfi {
+            // The frame record sits at the CFA - 16 - frame; once x29 is set it tracks the CFA
+            let _ = writeln!(out, "    .cfi_def_cfa_offset {}\n    .cfi_offset x29, -{}\n    .cfi_offset x30, -{}", frame + 16, frame + 16, frame + 8);
+        }
+        out.push_str("    mov x29, sp\n");
+        if self.cfi { let _ = writeln!(out, "    .cfi_def_cfa x29, {}", frame + 16); }
+        out
+    }
+
+    /// Restores what `gen_aapcs_prologue` saved and returns.
+    fn gen_aapcs_epilogue(&mut self) {
+        for r in (19..29).step_by(2).rev() {
+            emit!(self, "    ldp x{}, x{}, [sp, #{}]\n", r, r + 1, (r - 17) * 8);
+        }
+        self.output.push_str("    ldp x29, x30, [sp], #96\n    ret\n");
+    }
+
+    /// Catches SIGSEGV and SIGBUS on a stack carved from the heap, so a
+    /// crash is reported with its source line instead of killing the program.
+    fn gen_crash_handler(&mut self) {
+        // stack_t { ss_sp, ss_flags, ss_size } for sigaltstack
+        emit!(self, "    sub sp, sp, #32\n    stp x28, xzr, [sp]\n    mov x9, #{}\n    str x9, [sp, #16]\n    add x28, x28, x9\n", SIGNAL_STACK);
+        self.output.push_str("    mov x0, sp\n    mov x1, #0\n    mov x8, #132\n    svc #0\n");
+        // struct sigaction { handler, SA_SIGINFO | SA_ONSTACK, restorer, mask }
+        emit!(self, "    adr x9, {}segv\n    ldr x10, =0x08000004\n    stp x9, x10, [sp]\n    stp xzr, xzr, [sp, #16]\n", self.target.local_prefix());
+        for sig in [11, 7] {
+            emit!(self, "    mov x0, #{}\n    mov x1, sp\n    mov x2, #0\n    mov x3, #8\n    mov x8, #134\n    svc #0\n", sig);
+        }
+        self.output.push_str("    add sp, sp, #32\n");
+    }
+
+    /// Reports `msg` with the current source line and exits with `status`.
+    /// Unless `pc_in_x2`, the address of the trap itself locates it.
+    fn gen_trap(&mut self, msg: &str, status: u32, pc_in_x2: bool) {
+        let text = self.data_str(msg);
+        self.gen_addr("x0", &text);
+        self.gen_mov_imm("x1", msg.len() as i64);
+        if !pc_in_x2 { self.output.push_str("    adr x2, .\n"); }
+        let table = format!("{}loc_table", self.target.local_prefix());
+        self.gen_addr("x3", &table);
+        emit!(self, "    mov x4, #{}\n", status);
+        self.call_runtime("__hamer_trap");
+    }
+
+    /// Emits the table `__hamer_trap` searches: every location label with
+    /// the file and line it stands for (0 where no statement applies).
+    /// Addresses are offsets from the table, so it works at any load address.
+    fn gen_loc_table(&mut self) {
+        let mut files: HashMap<String, String> = HashMap::new();
+        let base = format!("{}loc_table", self.target.local_prefix());
+        let mut table = format!(".balign 8\n{}:\n    .quad {}\n", base, self.loc_labels.len());
+        for (label, loc) in std::mem::take(&mut self.loc_labels) {
+            match loc {
+                Some((file, line)) => {
+                    let name = match files.get(&file) {
+                        Some(name) => name.clone(),
+                        None => {
+                            let name = self.data_str(&file);
+                            files.insert(file.clone(), name.clone());
+                            name
+                        }
+                    };
+                    let _ = writeln!(table, "    .quad {} - {}, {} - {}, {}, {}", label, base, name, base, file.len(), line);
                 }
-                let cond = match op {
-                    Token::Equal => "ne",
-                    Token::Greater => "le",
-                    Token::Less => "ge",
-                    _ => "eq",
-                };
-                self.output.push_str(&format!("    cmp x1, #{}\n    b.{} .Lw_end{}\n", rhs_val as i64, cond, id));
-                for s in body { self.gen_stmt(s); }
-                self.output.push_str(&format!("    b .Lw_start{}\n.Lw_end{}:\n", id, id));
+                None => { let _ = writeln!(table, "    .quad {} - {}, 0, 0, 0", label, base); }
             }
-            Stmt::LocalAssign { name, value } => {
-                let reg = self.symbols.entry(name.clone()).or_insert_with(|| {
-                    let r = format!("x{}", self.reg_count); self.reg_count += 1; r
-                }).clone();
-                self.output.push_str(&format!("    mov {}, #{}\n", reg, value as i64));
+        }
+        self.gen_data(table.trim_end());
+    }
+
+    /// Notes in the output that the lines that follow come from `self.loc`.
+    fn mark_loc(&mut self) {
+        if self.checked {
+            let label = self.new_label("loc");
+            emit!(self, "{}:\n", label);
+            self.loc_labels.push((label, self.loc.clone()));
+        }
+        if !self.sourcemap { return; }
+        match &self.loc {
+            Some((file, line)) => emit!(self, "{}{}:{}\n", LOC_MARK, file, line),
+            None => emit!(self, "{}-\n", LOC_MARK),
+        }
+    }
+
+    /// Strips the location markers from the output, turning them into
+    /// `source_map` entries for the lines around them.
+    fn take_loc_marks(&mut self) {
+        let text = std::mem::take(&mut self.output);
+        let mut loc = None;
+        let mut line = 0;
+        for l in text.lines() {
+            if let Some(mark) = l.strip_prefix(LOC_MARK) {
+                loc = mark.rsplit_once(':').and_then(|(f, n)| Some((f.to_string(), n.parse().ok()?)));
+                continue;
             }
-            Stmt::FieldAssign { path, value } => {
-                let (reg, offset) = self.get_path_info(&path);
-                if path.len() > 1 {
-                    self.output.push_str(&format!("    mov x1, #{}\n    str x1, [{}, #{}]\n", value as i64, reg, offset));
-                } else {
-                    self.output.push_str(&format!("    mov {}, #{}\n", reg, value as i64));
+            line += 1;
+            if self.source_map.last().is_none_or(|(_, last)| *last != loc) {
+                self.source_map.push((line, loc.clone()));
+            }
+            self.output.push_str(l);
+            self.output.push('\n');
+        }
+    }
+
+    /// The source file and line that produced line `asm_line` (1-based) of
+    /// the output, when it was generated with `sourcemap` set.
+    pub fn source_location(&self, asm_line: usize) -> Option<(&str, usize)> {
+        let run = self.source_map.partition_point(|(start, _)| *start <= asm_line).checked_sub(1)?;
+        self.source_map[run].1.as_ref().map(|(file, line)| (file.as_str(), *line))
+    }
+
+    /// The source map as JSON. Each mapping covers assembly lines from
+    /// `asm` up to the next mapping; `source` indexes `sources`, and both it
+    /// and `line` are null for generated code that has no single origin.
+    pub fn source_map_json(&self, asm_file: &str) -> String {
+        let mut sources: Vec<&str> = Vec::new();
+        let mut json = format!("{{\n  \"version\": 1,\n  \"file\": {},\n  \"mappings\": [", json_str(asm_file));
+        for (k, (asm, loc)) in self.source_map.iter().enumerate() {
+            json.push_str(if k == 0 { "\n" } else { ",\n" });
+            match loc {
+                Some((file, line)) => {
+                    let idx = sources.iter().position(|s| s == file).unwrap_or_else(|| { sources.push(file); sources.len() - 1 });
+                    let _ = write!(json, "    {{\"asm\": {}, \"source\": {}, \"line\": {}}}", asm, idx, line);
                 }
+                None => { let _ = write!(json, "    {{\"asm\": {}, \"source\": null, \"line\": null}}", asm); }
             }
-            Stmt::FieldMath { path, op, rhs_val } => {
-                let (reg, offset) = self.get_path_info(&path);
+        }
+        let sources: Vec<String> = sources.iter().map(|s| json_str(s)).collect();
+        let _ = write!(json, "\n  ],\n  \"sources\": [{}]\n}}\n", sources.join(", "));
+        json
+    }
+
+    /// Starts a new code body for labels, returning the one to restore.
+    fn enter_scope(&mut self) -> usize {
+        self.scope_count += 1;
+        std::mem::replace(&mut self.scope, self.scope_count)
+    }
+
+    /// Local label `part` of function `name`, e.g. its `ret` or `body`.
+    fn fn_label(&self, name: Symbol, part: &str) -> String {
+        asm_symbol(format!("{}fn_{}_{}", self.target.local_prefix(), self.name(name), part))
+    }
+
+    fn user_label(&self, scope: usize, name: Symbol) -> String {
+        asm_symbol(format!("{}label{}_{}", self.target.local_prefix(), scope, self.name(name)))
+    }
+
+    fn gen_label(&mut self, name: Symbol) {
+        if !self.labels.insert((self.scope, name)) {
+            self.errors.push(format!("label `{}` is defined twice", self.name(name)));
+            return;
+        }
+        emit!(self, "{}:\n", self.user_label(self.scope, name));
+    }
+
+    fn check_gotos(&mut self) {
+        for (scope, name) in std::mem::take(&mut self.gotos) {
+            if self.labels.contains(&(scope, name)) { continue; }
+            let label = self.name(name);
+            if self.labels.iter().any(|&(_, n)| n == name) {
+                self.errors.push(format!("`goto {}` leaves its body: label `{}` is in a different function, thread or handler", label, label));
+            } else {
+                self.errors.push(format!("`goto {}` has no matching `label {}`", label, label));
+            }
+        }
+    }
+
+    /// Returns the class of the object a path evaluates to, if known.
+    fn path_class(&self, path: &[Symbol]) -> Option<Symbol> {
+        let mut class = self.obj_types.get(&path[0]).copied();
+        for &name in path[1..].iter().filter(|&&s| !self.is_index(s)) {
+            let fields = self.class_map.get(&class?)?;
+            class = fields.iter().find(|f| f.name == name)?.class;
+        }
+        class
+    }
+
+    /// Loads the value a path evaluates to into `dst`.
+    fn load_path(&mut self, path: &[Symbol], dst: &str) {
+        if let [var] = *path && let Some(loc) = self.symbols.get(&var).cloned() {
+            self.copy_var(var, &loc, dst);
+            return;
+        }
+        let (reg, offset) = self.get_path_info(path);
+        if self.in_memory(path) {
+            emit!(self, "    ldr {}, [{}, #{}]\n", dst, reg, offset);
+        } else {
+            emit!(self, "    mov {}, {}\n", dst, reg);
+        }
+    }
+
+    /// Loads an operand where an integer is needed; a float variable or
+    /// field is narrowed.
+    fn load_operand(&mut self, op: &Operand, dst: &str) {
+        match op {
+            Operand::Num(n) => self.gen_mov_imm(dst, *n as i64),
+            Operand::Path(p) => {
+                self.load_path(p, dst);
+                if self.is_float_path(p) { self.narrow_float(dst, &self.path_name(p)); }
+            }
+        }
+    }
+
+    /// Generates a function out of line. Parameters arrive in x0-x7 and are
+    /// moved into fresh registers (or stack slots); object parameters are
+    /// pointers. The frame is only sized once the body is generated, so the
+    /// prologue is put in front of it afterwards.
+    fn gen_func(&mut self, name: Symbol, params: Vec<Field>, body: Vec<StmtId>, public: bool) {
+        if params.len() > 8 {
+            self.errors.push(format!("function `{}` takes {} parameters, at most 8 are supported", self.name(name), params.len()));
+            return;
+        }
+        let main = std::mem::take(&mut self.output);
+        self.mark_loc();
+        let saved_symbols = self.symbols.clone();
+        let saved_types = self.obj_types.clone();
+        let symbol = asm_symbol(format!("fn_{}", self.name(name)));
+        emit!(self, "\n{}{}:\n", self.target.function_type(&symbol), symbol);
+        if self.cfi { self.output.push_str("    .cfi_startproc\n"); }
+        let prologue_at = self.output.len();
+        let outer_slots = std::mem::take(&mut self.slots);
+        let outer = self.current_fn.replace(name);
+        let rescues = std::mem::take(&mut self.rescues);
+        let scope = self.enter_scope();
+        let outer_scope = std::mem::replace(&mut self.fn_scope, self.scope);
+        let outer_regs = std::mem::replace(&mut self.fn_regs, self.reg_count);
+        let overflow = self.fn_label(name, "overflow");
+        if self.stack_guard {
+            self.output.push_str("    mrs x9, tpidr_el0\n");
+            if self.spawns { self.output.push_str("    ldr x9, [x9, #8]\n"); }
+            emit!(self, "    cmp x29, x9\n    b.lo {}\n", overflow);
+        }
+        // Tail calls come back here, with the new arguments in x0-x7
+        emit!(self, "{}:\n", self.fn_label(name, "body"));
+        for (i, p) in params.into_iter().enumerate() {
+            if let Some(c) = p.class { self.ensure_class(c); }
+            let loc = self.alloc_var(p.name);
+            self.store_var(p.name, &loc, &format!("x{}", i));
+            self.symbols.insert(p.name, loc);
+            match p.class {
+                Some(c) => { self.obj_types.insert(p.name, c); }
+                None => { self.obj_types.remove(&p.name); }
+            }
+        }
+        // A call to itself with nothing after it is a tail call too
+        if let Some(&last) = body.last() && self.self_call(last) == Some(None) {
+            self.tail_calls.insert(last);
+        }
+        self.gen_block(body);
+        let live: Vec<usize> = (self.fn_regs..self.reg_count.min(LAST_VAR_REG + 1)).collect();
+        self.fill_saves(&live);
+        self.fn_scope = outer_scope;
+        self.fn_regs = outer_regs;
+        self.scope = scope;
+        self.rescues = rescues;
+        self.current_fn = outer;
+        let frame = std::mem::replace(&mut self.slots, outer_slots).div_ceil(2) * 16;
+        let prologue = self.frame_prologue(frame);
+        self.output.insert_str(prologue_at, &prologue);
+        emit!(self, "{}:\n{}    ret\n", self.fn_label(name, "ret"), frame_epilogue(frame));
+        if self.stack_guard {
+            emit!(self, "{}:\n", overflow);
+            let msg = format!("stack overflow in `{}`", self.name(name));
+            if self.checked {
+                // Blame the function's own line rather than whatever was last generated
+                emit!(self, "    adr x2, {}\n", symbol);
+                self.gen_trap(&msg, TRAP_STATUS, true);
+            } else {
+                self.gen_write_fd(2, &format!("error: {}\n", msg));
+                self.output.push_str("    mov x0, #1\n    mov x8, #93\n    svc #0\n");
+            }
+        }
+        if self.cfi { self.output.push_str("    .cfi_endproc\n"); }
+        let size = self.target.symbol_size(&symbol);
+        self.output.push_str(&size);
+        if public { self.gen_export(name); }
+        self.symbols = saved_symbols;
+        self.obj_types = saved_types;
+        let func = std::mem::replace(&mut self.output, main);
+        self.deferred.push_str(&func);
+    }
+
+    /// Exports `pub func name` to C as `name`: an AAPCS64 wrapper that keeps
+    /// the caller's x19-x28 and runs the function on the heap the exported
+    /// `hamer_heap` pointer gives, storing the advanced pointer back after.
+    /// Calls from H@mer code go straight to `fn_name` instead.
+    fn gen_export(&mut self, name: Symbol) {
+        let name = self.name(name).to_string();
+        let symbol = asm_symbol(self.target.c_symbol(&name));
+        if symbol == self.entry || self.exports.contains(&symbol) {
+            self.errors.push(format!("`pub func {}` clashes with another exported symbol `{}`", name, symbol));
+            return;
+        }
+        if self.spawns {
+            self.errors.push(format!("`pub func {}` can't be called from C in a program with `spawn`, whose threads keep their heap ends in tpidr_el0", name));
+            return;
+        }
+        let heap = self.target.c_symbol("hamer_heap");
+        if self.exports.is_empty() {
+            let size = self.target.symbol_size(&heap);
+            self.gen_data(&format!(".balign 8\n.global {}\n{}: .quad 0\n{}", heap, heap, size.trim_end()));
+        }
+        self.exports.push(symbol.clone());
+        emit!(self, "\n.global {}\n{}{}:\n", symbol, self.target.function_type(&symbol), symbol);
+        if self.cfi { self.output.push_str("    .cfi_startproc\n"); }
+        self.gen_aapcs_prologue();
+        self.gen_addr("x9", &heap);
+        emit!(self, "    ldr x28, [x9]\n    bl {}\n", asm_symbol(format!("fn_{}", name)));
+        self.gen_addr("x9", &heap);
+        self.output.push_str("    str x28, [x9]\n");
+        self.gen_aapcs_epilogue();
+        if self.cfi { self.output.push_str("    .cfi_endproc\n"); }
+        let size = self.target.symbol_size(&symbol);
+        self.output.push_str(&size);
+    }
+
+    /// Generates a statement list. In a function, a call to the function
+    /// itself directly followed by `return` of its result is a tail call: it
+    /// jumps back to the top with the new arguments instead of growing the stack.
+    fn gen_block(&mut self, body: Vec<StmtId>) {
+        let mut stmts = body.into_iter().peekable();
+        while let Some(s) = stmts.next() {
+            if let Some(dest) = self.self_call(s)
+                && let Some(&next) = stmts.peek()
+                && self.returns_result(next, dest) {
+                self.tail_calls.insert(s);
+                // The jump never comes back, so the `return` would be dead code
+                stmts.next();
+            }
+            self.gen_stmt(s);
+        }
+    }
+
+    /// If `id` calls the function being generated from its own body (not a
+    /// thread or handler inside it), the variable receiving the result.
+    fn self_call(&self, id: StmtId) -> Option<Option<Symbol>> {
+        let Stmt::Call { name, dest, .. } = self.session.stmts[id] else { return None };
+        (self.current_fn == Some(name) && self.scope == self.fn_scope).then_some(dest)
+    }
+
+    /// True if `id` is `return dest`, or a bare `return` when there is no `dest`.
+    fn returns_result(&self, id: StmtId, dest: Option<Symbol>) -> bool {
+        match (&self.session.stmts[id], dest) {
+            (Stmt::Return(None), None) => true,
+            (Stmt::Return(Some(Operand::Path(p))), Some(d)) => p.len() == 1 && p[0] == d,
+            _ => false,
+        }
+    }
+
+    /// Calls `name`. With `from`, the pipeline stage before it is called
+    /// first and its result, left in x0, is the first argument.
+    fn gen_call(&mut self, name: Symbol, args: Vec<Operand>, dest: Option<Symbol>, from: Option<StmtId>, tail: bool) {
+        let Some(info) = self.funcs.get(&name).cloned() else {
+            self.errors.push(format!("call to undefined function `{}`", self.name(name)));
+            return;
+        };
+        let piped = usize::from(from.is_some());
+        if args.len() + piped != info.params.len() {
+            self.errors.push(format!("`{}` expects {} arguments, got {}", self.name(name), info.params.len(), args.len() + piped));
+            return;
+        }
+        let piped_class = from.map(|id| match &self.session.stmts[id] {
+            Stmt::Call { name, .. } => self.funcs.get(name).and_then(|f| f.ret_class),
+            _ => None,
+        });
+        let classes: Vec<Option<Symbol>> = piped_class.into_iter().chain(args.iter().map(|arg| match arg {
+            Operand::Path(p) => self.path_class(p),
+            Operand::Num(_) => None,
+        })).collect();
+        for (actual, param) in classes.into_iter().zip(&info.params) {
+            let Some(expected) = param.class else { continue };
+            if actual != Some(expected) {
+                let found = actual.map_or_else(|| "a number".into(), |c| format!("a {}", self.name(c)));
+                self.errors.push(format!("parameter `{}` of `{}` expects an object of class {}, found {}", self.name(param.name), self.name(name), self.name(expected), found));
+            }
+        }
+        if let Some(id) = from {
+            let Stmt::Call { name, args, from, .. } = self.session.stmts[id].clone() else { unreachable!() };
+            self.gen_call(name, args, None, from, false);
+        }
+        for (i, arg) in args.iter().enumerate() {
+            self.load_operand(arg, &format!("x{}", i + piped));
+        }
+        if tail {
+            emit!(self, "    b {}\n", self.fn_label(name, "body"));
+            return;
+        }
+        let symbol = asm_symbol(format!("fn_{}", self.name(name)));
+        // Each variable has a register of its own, so only a call that can
+        // come back into this function overwrites the ones it uses; which
+        // those are is known once its body is generated (see `fill_saves`)
+        if self.current_fn.is_some_and(|f| self.reaches(name, f)) {
+            emit!(self, "{}\n    bl {}\n{}\n", SAVE_MARK, symbol, RESTORE_MARK);
+        } else {
+            emit!(self, "    bl {}\n", symbol);
+        }
+        if let Some(var) = dest {
+            self.set_var(var, "x0");
+            self.elements.remove(&var);
+            match info.ret_class {
+                Some(c) => { self.obj_types.insert(var, c); }
+                None => { self.obj_types.remove(&var); }
+            }
+        }
+    }
+
+    /// Puts the saving and restoring of the registers `live` in place of
+    /// the marks around the recursive calls of the function just generated.
+    fn fill_saves(&mut self, live: &[usize]) {
+        if !self.output.contains(SAVE_MARK) { return; }
+        let body = std::mem::take(&mut self.output);
+        let frame = live.len().div_ceil(2) * 16;
+        if frame > 0 { emit!(self, "    sub sp, sp, #{}\n", frame); }
+        self.save_regs(live, "stp", "str");
+        let save = std::mem::take(&mut self.output);
+        self.save_regs(live, "ldp", "ldr");
+        if frame > 0 { emit!(self, "    add sp, sp, #{}\n", frame); }
+        let restore = std::mem::take(&mut self.output);
+        self.output = body.replace(&format!("{}\n", SAVE_MARK), &save).replace(&format!("{}\n", RESTORE_MARK), &restore);
+    }
+
+    /// True if calling `from` can end up calling `to`, directly or through
+    /// other functions. A function the first pass didn't see might.
+    fn reaches(&self, from: Symbol, to: Symbol) -> bool {
+        let mut seen = HashSet::new();
+        let mut todo = vec![from];
+        while let Some(f) = todo.pop() {
+            if f == to { return true; }
+            if !seen.insert(f) { continue; }
+            let Some(&id) = self.func_items.get(&f) else { return true };
+            if let Item::Func { body, .. } = &self.session.items[id] { self.calls_in(body, &mut todo); }
+        }
+        false
+    }
+
+    /// Adds the functions `body` calls, at any depth, to `out`.
+    fn calls_in(&self, body: &[StmtId], out: &mut Vec<Symbol>) {
+        for &id in body {
+            let mut stage = Some(id);
+            while let Some(Stmt::Call { name, from, .. }) = stage.map(|s| &self.session.stmts[s]) {
+                out.push(*name);
+                stage = *from;
+            }
+            for b in self.session.stmts[id].bodies() { self.calls_in(b, out); }
+        }
+    }
+
+    fn gen_return(&mut self, value: Option<Operand>) {
+        let Some(func) = self.current_fn else {
+            self.gen_exit(value);
+            return;
+        };
+        if let Some(v) = value {
+            self.load_operand(&v, "x0");
+            // The first pass knows most return classes; this catches the rest
+            if let Operand::Path(p) = &v
+                && let Some(c) = self.path_class(p)
+                && let Some(info) = self.funcs.get_mut(&func) {
+                info.ret_class.get_or_insert(c);
+            }
+        }
+        emit!(self, "    b {}\n", self.fn_label(func, "ret"));
+    }
+
+    /// Lowers a `return` in the main program: it ends the program with the
+    /// value as exit status (0 without one), or hands the value back to the
+    /// host program under `--no-runtime`.
+    fn gen_exit(&mut self, value: Option<Operand>) {
+        if self.scope != 0 {
+            self.errors.push("`return` outside of a function".into());
+            return;
+        }
+        self.load_operand(&value.unwrap_or(Operand::Num(0.0)), "x0");
+        let l = match self.exit_label.clone() {
+            Some(l) => l,
+            None => {
+                let l = self.new_label("exit");
+                self.exit_label = Some(l.clone());
+                l
+            }
+        };
+        emit!(self, "    b {}\n", l);
+    }
+
+    /// Makes sure `class` has a layout, stamping out generic instances such
+    /// as `Box<Hero>` from their template on first use.
+    fn ensure_class(&mut self, class: Symbol) {
+        if self.class_map.contains_key(&class) { return; }
+        let (base, args) = generic_parts(&self.session.interner, class);
+        if args.is_empty() { return; }
+        let Some((params, template)) = self.generics.get(&base).cloned() else { return };
+        let fields: Vec<Field> = template.into_iter().map(|f| {
+            let class = f.class.map(|c| {
+                let name = substitute(&self.session.interner, c, &params, &args);
+                self.intern_class(&name)
+            });
+            Field { name: f.name, class, init: f.init }
+        }).collect();
+        self.class_map.insert(class, fields.clone());
+        for f in fields {
+            if let Some(c) = f.class { self.ensure_class(c); }
+        }
+    }
+
+    /// Interns a class name and, for a generic instance, each level of its
+    /// arguments, as `generic_parts` expects.
+    fn intern_class(&mut self, name: &str) -> Symbol {
+        if let Some((base, rest)) = name.split_once('<') {
+            self.session.interner.intern(base);
+            for arg in split_generic_args(rest.strip_suffix('>').unwrap_or(rest)) { self.intern_class(&arg); }
+        }
+        self.session.interner.intern(name)
+    }
+
+    /// Copies a verbatim assembly block line by line, re-indented.
+    fn gen_raw_lines(&mut self, code: &str) {
+        for line in code.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            emit!(self, "    {}\n", line);
+        }
+    }
+
+    /// Warns when an `@asm` block names the heap pointer, which the rest
+    /// of the program relies on staying intact.
+    fn check_asm_registers(&mut self, code: &str, id: StmtId) {
+        let heap_w = HEAP_REG.replacen('x', "w", 1);
+        let mentions = code.split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| word == HEAP_REG || word == heap_w);
+        if mentions {
+            self.warnings.push(format!("{}:{}: @asm block uses {}, the heap pointer; changing it corrupts later allocations",
+                self.file, self.session.stmt_line(id), HEAP_REG));
+        }
+    }
+
+    /// Runs `body` on a new thread via clone(). The child gets a fresh stack
+    /// from the heap and a copy of every register, so only heap data is
+    /// shared; it allocates from a heap chunk of its own.
+    fn gen_spawn(&mut self, body: Vec<StmtId>) {
+        if self.no_runtime {
+            self.errors.push("`spawn` keeps each thread's heap end in tpidr_el0, which belongs to the host program under --no-runtime".into());
+            return;
+        }
+        let l = self.new_label("spawn");
+        let threads = self.threads();
+        self.reserve_heap(THREAD_STACK);
+        // The block goes at the bottom of the new stack. Its heap end is the
+        // stack top, where the parent carries on, so the thread's first
+        // allocation maps a chunk of its own.
+        emit!(self, "    mov x3, x28\n    add x28, x28, #{}\n    stp x28, xzr, [x3]\n", THREAD_STACK);
+        if self.stack_guard {
+            emit!(self, "    add x9, x3, #{}\n    str x9, [x3, #8]\n", THREAD_BLOCK + STACK_RESERVE);
+        }
+        // Threads may spawn their own, so the block is pushed atomically
+        self.gen_addr("x10", &threads);
+        emit!(self, "{l}_push:\n    ldaxr x9, [x10]\n    stp xzr, x9, [x3, #16]\n    stlxr w11, x3, [x10]\n    cbnz w11, {l}_push\n", l = l);
+        emit!(self, "    ldr x0, ={:#x}\n    mov x1, x28\n    add x2, x3, #16\n    mov x4, x2\n    mov x8, #220\n    svc #0\n", CLONE_THREAD_FLAGS);
+        if !self.rescues.is_empty() {
+            self.output.push_str("    cmp x0, #0\n");
+            self.gen_check("lt", ERR_SYSCALL);
+        }
+        emit!(self, "    cbnz x0, {}\n", l);
+        let rescues = std::mem::take(&mut self.rescues);
+        let scope = self.enter_scope();
+        self.gen_block(body);
+        self.scope = scope;
+        self.rescues = rescues;
+        emit!(self, "    mov x0, #0\n    mov x8, #93\n    svc #0\n{}:\n", l);
+    }
+
+    /// Waits for every spawned thread: walks the list of their blocks,
+    /// waiting on each one's tid word until the kernel clears it.
+    fn gen_join(&mut self) {
+        if !self.spawns { return; }
+        let l = self.new_label("join");
+        let threads = self.threads();
+        self.gen_addr("x9", &threads);
+        emit!(self, "    ldar x9, [x9]\n{l}:\n    cbz x9, {l}_done\n{l}_wait:\n    ldr w2, [x9, #16]\n    cbz w2, {l}_next\n", l = l);
+        emit!(self, "    add x0, x9, #16\n    mov x1, #0\n    mov x3, #0\n    mov x8, #98\n    svc #0\n    b {l}_wait\n{l}_next:\n    ldr x9, [x9, #24]\n    b {l}\n{l}_done:\n", l = l);
+    }
+
+    /// The .bss word heading the list of spawned threads' blocks.
+    fn threads(&mut self) -> String {
+        let label = format!("{}threads", self.target.local_prefix());
+        self.bss_slot(&label, 8, 8);
+        label
+    }
+
+    /// The .bss word holding the end of the main thread's heap chunk; in a
+    /// program that spawns, the main thread's block.
+    fn heap_end(&mut self) -> String {
+        let label = format!("{}heap_end", self.target.local_prefix());
+        let size = if self.spawns { 16 } else { 8 };
+        self.bss_slot(&label, size, 8);
+        label
+    }
+
+    /// Installs a SIGINT handler with rt_sigaction. The handler runs `body`
+    /// with the interrupted registers still live, then exits with 130.
+    fn gen_on_interrupt(&mut self, body: Vec<StmtId>) {
+        let l = self.new_label("sigint");
+        // struct sigaction { handler, flags, restorer, mask } built on the stack
+        emit!(self, "    adr x9, {}\n    sub sp, sp, #32\n    stp x9, xzr, [sp]\n    stp xzr, xzr, [sp, #16]\n", l);
+        self.output.push_str("    mov x0, #2\n    mov x1, sp\n    mov x2, #0\n    mov x3, #8\n    mov x8, #134\n    svc #0\n    add sp, sp, #32\n");
+        let main = std::mem::take(&mut self.output);
+        self.mark_loc();
+        emit!(self, "\n{}:\n", l);
+        let rescues = std::mem::take(&mut self.rescues);
+        let scope = self.enter_scope();
+        self.gen_block(body);
+        self.scope = scope;
+        self.rescues = rescues;
+        self.output.push_str("    mov x0, #130\n    mov x8, #94\n    svc #0\n");
+        let handler = std::mem::replace(&mut self.output, main);
+        self.deferred.push_str(&handler);
+    }
+
+    /// Runs an external program: fork (clone with SIGCHLD), execve in the
+    /// child, wait4 in the parent. The exit code (or -1 if the fork failed)
+    /// ends up in `dest`; a program that can't be executed exits with 127.
+    fn gen_run(&mut self, argv: Vec<String>, dest: Option<Symbol>) {
+        let Some(prog) = argv.first().cloned() else {
+            self.errors.push("`run` needs a program name".into());
+            return;
+        };
+        let l = self.new_label("run");
+        let labels: Vec<String> = argv.iter().map(|a| self.data_cstr(a)).collect();
+        // Bare names are looked up in the usual system directories
+        let candidates: Vec<String> = if prog.contains('/') {
+            vec![labels[0].clone()]
+        } else {
+            ["/usr/bin/", "/bin/"].iter().map(|dir| self.data_cstr(&format!("{}{}", dir, prog))).collect()
+        };
+        // argv array plus NULL terminator and the wait status word, 16-byte aligned
+        let status = (argv.len() + 1) * 8;
+        let frame = (status + 8).div_ceil(16) * 16;
+        emit!(self, "    sub sp, sp, #{}\n", frame);
+        for (i, label) in labels.iter().enumerate() {
+            self.gen_addr("x9", label);
+            emit!(self, "    str x9, [sp, #{}]\n", i * 8);
+        }
+        emit!(self, "    str xzr, [sp, #{}]\n", argv.len() * 8);
+        self.output.push_str("    mov x0, #17\n    mov x1, #0\n    mov x2, #0\n    mov x3, #0\n    mov x4, #0\n    mov x8, #220\n    svc #0\n");
+        emit!(self, "    cbnz x0, {}_parent\n", l);
+        for path in candidates {
+            self.gen_addr("x0", &path);
+            emit!(self, "    mov x1, sp\n    mov x2, #0\n    mov x8, #221\n    svc #0\n");
+        }
+        self.output.push_str("    mov x0, #127\n    mov x8, #94\n    svc #0\n");
+        emit!(self, "{}_parent:\n    tbnz x0, #63, {}_fail\n", l, l);
+        emit!(self, "    add x1, sp, #{}\n    mov x2, #0\n    mov x3, #0\n    mov x8, #260\n    svc #0\n", status);
+        emit!(self, "    ldr w1, [sp, #{}]\n    ubfx x1, x1, #8, #8\n    b {}_done\n", status, l);
+        emit!(self, "{}_fail:\n    mov x1, #-1\n{}_done:\n    add sp, sp, #{}\n", l, l, frame);
+        if !self.rescues.is_empty() {
+            // exit codes are 0..=255, so -1 only ever means the fork failed
+            self.output.push_str("    cmn x1, #1\n");
+            self.gen_check("eq", ERR_SYSCALL);
+        }
+        if let Some(var) = dest {
+            self.set_var(var, "x1");
+            self.obj_types.remove(&var);
+        }
+    }
+
+    /// Guards a division by the run-time value in `divisor`: inside `try` a
+    /// zero goes to the rescue, otherwise `--checked` or `--check-div`
+    /// decide whether it stops the program with a message naming `name`.
+    fn gen_div_check(&mut self, divisor: &str, name: &str) {
+        if !self.rescues.is_empty() {
+            emit!(self, "    cmp {}, #0\n", divisor);
+            self.gen_check("eq", ERR_DIV_ZERO);
+        } else if self.checked {
+            let l = self.new_label("div");
+            emit!(self, "    cbnz {}, {}\n", divisor, l);
+            self.gen_trap(&format!("division by zero in `{}`", name), TRAP_STATUS, false);
+            emit!(self, "{}:\n", l);
+        } else if self.check_div {
+            let l = self.new_label("div");
+            emit!(self, "    cbnz {}, {}\n", divisor, l);
+            self.gen_write_fd(2, &format!("error: division by zero in `{}`\n", name));
+            emit!(self, "    mov x0, #1\n    mov x8, #93\n    svc #0\n{}:\n", l);
+        }
+    }
+
+    /// Evaluates `expr` into `dst`. Partial results live in x`depth` and up,
+    /// which any call or runtime routine is free to clobber anyway; `name`
+    /// is what a division by zero is reported against.
+    fn gen_expr(&mut self, expr: &Expr, dst: &str, depth: usize, name: &str) {
+        match expr {
+            Expr::Num(n) | Expr::Float(n) => self.gen_mov_imm(dst, *n as i64),
+            Expr::Str(_) => self.errors.push(format!("text can't be used as a number in `{}`", name)),
+            Expr::Path(path) => {
+                if !self.symbols.contains_key(&path[0]) {
+                    self.errors.push(format!("unknown variable `{}` in `{}`", self.path_name(path), name));
+                    return;
+                }
+                self.load_path(path, dst);
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                if depth >= 7 {
+                    self.errors.push(format!("the expression for `{}` is nested too deeply", name));
+                    return;
+                }
+                let (lhs, rhs) = (self.session.exprs[*lhs].clone(), self.session.exprs[*rhs].clone());
+                let lhs_reg = match self.var_in_reg(&lhs) {
+                    Some(reg) => reg,
+                    None => {
+                        let reg = format!("x{}", depth);
+                        self.gen_expr(&lhs, &reg, depth, name);
+                        reg
+                    }
+                };
                 let instr = match op {
-                    Token::Plus => "add",
                     Token::Minus => "sub",
+                    Token::Star => "mul",
+                    Token::Slash => "sdiv",
                     _ => "add",
                 };
-                if path.len() > 1 {
-                    self.output.push_str(&format!("    ldr x1, [{}, #{}]\n    {} x1, x1, #{}\n    str x1, [{}, #{}]\n", reg, offset, instr, rhs_val as i64, reg, offset));
-                } else {
-                    self.output.push_str(&format!("    {} {}, {}, #{}\n", instr, reg, reg, rhs_val as i64));
+                match rhs {
+                    Expr::Num(n) if matches!(op, Token::Plus | Token::Minus) && (0.0..4096.0).contains(&n) => {
+                        emit!(self, "    {} {}, {}, #{}\n", instr, dst, lhs_reg, n as i64);
+                    }
+                    Expr::Num(n) if *op == Token::Slash && n as i64 == 0 => {
+                        self.errors.push(format!("`{}` is divided by zero", name));
+                    }
+                    _ => {
+                        let rhs_reg = match self.var_in_reg(&rhs) {
+                            Some(reg) => reg,
+                            None => {
+                                let reg = format!("x{}", depth + 1);
+                                self.gen_expr(&rhs, &reg, depth + 1, name);
+                                reg
+                            }
+                        };
+                        if *op == Token::Slash && !matches!(rhs, Expr::Num(_)) {
+                            self.gen_div_check(&rhs_reg, name);
+                        }
+                        emit!(self, "    {} {}, {}, {}\n", instr, dst, lhs_reg, rhs_reg);
+                    }
                 }
             }
-            Stmt::PrintVar(name) => {
-                if let Some(reg) = self.symbols.get(&name).cloned() {
-                    let id = self.output.len();
-                    self.output.push_str(&format!("
-    stp x0, x1, [sp, #-16]!
-    mov x0, {}
-    sub sp, sp, #32
-    mov x1, sp
-    add x1, x1, #31
-    mov w2, #10
-    strb w2, [x1]
-.Lp{}:
-    sub x1, x1, #1
-    udiv x2, x0, x11
-    msub x3, x2, x11, x0
-    add x3, x3, #48
-    strb w3, [x1]
-    mov x0, x2
-    cbnz x0, .Lp{}
-    mov x0, #1
-    mov x2, sp
-    add x2, x2, #32
-    sub x2, x2, x1
-    mov x8, #64
-    svc #0
-    add sp, sp, #32
-    ldp x0, x1, [sp], #16\n", reg, id, id));
-                }
-            }
-            Stmt::PrintString(s) => {
-                let id = self.label_count; self.label_count += 1;
-                self.output.push_str(&format!("\n.section .data\n.Lstr{}: .ascii \"{}\\n\"\n.section .text\n", id, s));
-                self.output.push_str(&format!("    mov x0, #1\n    adr x1, .Lstr{}\n    mov x2, #{}\n    mov x8, #64\n    svc #0\n", id, s.len() + 1));
-            }
-            Stmt::ClassDef { name, fields } => { self.class_map.insert(name, fields); }
-            Stmt::HeapAlloc { var_name, class_name } => {
-                let reg = format!("x{}", self.reg_count); self.reg_count += 1;
-                self.symbols.insert(var_name.clone(), reg.clone());
-                self.obj_types.insert(var_name, class_name.clone());
-                if let Some(f) = self.class_map.get(&class_name) {
-                    self.output.push_str(&format!("    mov {}, x20\n    add x20, x20, #{}\n", reg, f.len() * 8));
-                }
-            }
-        }
-    }
-}
\ No newline at end of file
+        }
+    }
+
+    /// The register holding `expr` when it is a plain variable kept in one,
+    /// so it can be used in place instead of copied.
+    fn var_in_reg(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Path(p) if p.len() == 1 => match self.symbols.get(&p[0]) {
+                Some(Loc::Reg(reg)) => Some(reg.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Inside `try`, jumps to the innermost `rescue` with `code` in x9 when
+    /// condition `cond` holds. Outside `try` the failure is left to the caller.
+    fn gen_check(&mut self, cond: &str, code: u32) {
+        let Some(rescue) = self.rescues.last().cloned() else { return };
+        let l = self.new_label("ok");
+        let inverse = match cond {
+            "eq" => "ne", "ne" => "eq", "lt" => "ge", "ge" => "lt",
+            "mi" => "pl", "pl" => "mi", "hi" => "ls", "ls" => "hi",
+            "hs" => "lo", "lo" => "hs",
+            _ => "al",
+        };
+        emit!(self, "    b.{} {}\n    mov x9, #{}\n    b {}\n{}:\n", inverse, l, code, rescue, l);
+    }
+
+    fn gen_try(&mut self, body: Vec<StmtId>, err: Option<Symbol>, rescue: Vec<StmtId>) {
+        let l = self.new_label("try");
+        self.rescues.push(format!("{}_rescue", l));
+        self.gen_block(body);
+        self.rescues.pop();
+        emit!(self, "    b {}_end\n{}_rescue:\n", l, l);
+        if let Some(var) = err {
+            self.set_var(var, "x9");
+            self.obj_types.remove(&var);
+        }
+        self.gen_block(rescue);
+        emit!(self, "{}_end:\n", l);
+    }
+
+    /// Puts the address of a field into x10 for exclusive load/store, which
+    /// take no offset. Plain variables live in registers and can't be shared.
+    fn field_address(&mut self, path: &[Symbol], what: &str) -> bool {
+        if path.len() < 2 {
+            self.errors.push(format!("`{}` needs an object field, but `{}` lives in a register", what, self.path_name(path)));
+            return false;
+        }
+        let (reg, offset) = self.get_path_info(path);
+        emit!(self, "    add x10, {}, #{}\n", reg, offset);
+        true
+    }
+
+    /// Emits a `bl` to a runtime routine, pulling its body into the output.
+    /// Variable registers the allocator has handed out and the routine
+    /// overwrites are saved on the stack around the call.
+    fn call_runtime(&mut self, name: &'static str) {
+        if !self.runtime.contains(&name) { self.runtime.push(name); }
+        let clobbered = runtime::clobbers(name);
+        let live: Vec<usize> = (FIRST_VAR_REG..self.reg_count.min(LAST_VAR_REG + 1))
+            .filter(|&r| clobbered & 1 << r != 0)
+            .collect();
+        if live.is_empty() || !runtime::returns(name) {
+            emit!(self, "    bl {}\n", name);
+            return;
+        }
+        let frame = live.len().div_ceil(2) * 16;
+        trace!("saving {} around `bl {}`", live.iter().map(|r| format!("x{}", r)).collect::<Vec<_>>().join(", "), name);
+        emit!(self, "    sub sp, sp, #{}\n", frame);
+        self.save_regs(&live, "stp", "str");
+        emit!(self, "    bl {}\n", name);
+        self.save_regs(&live, "ldp", "ldr");
+        emit!(self, "    add sp, sp, #{}\n", frame);
+    }
+
+    /// Stores (or loads) `regs` in pairs at the bottom of the stack.
+    fn save_regs(&mut self, regs: &[usize], pair: &str, single: &str) {
+        for (i, chunk) in regs.chunks(2).enumerate() {
+            match chunk {
+                [a, b] => emit!(self, "    {} x{}, x{}, [sp, #{}]\n", pair, a, b, i * 16),
+                [a] => emit!(self, "    {} x{}, [sp, #{}]\n", single, a, i * 16),
+                _ => {}
+            }
+        }
+    }
+
+    /// Writes `text` where `print` output goes: stdout, or stderr in `eprint`.
+    fn gen_write_str(&mut self, text: &str) {
+        self.gen_write_fd(self.out_fd, text);
+    }
+
+    /// Calls a number-printing runtime routine, aimed where `gen_write_str` writes.
+    fn gen_print_call(&mut self, routine: &'static str) {
+        emit!(self, "    mov x8, #{}\n", self.out_fd);
+        self.call_runtime(routine);
+    }
+
+    fn gen_write_fd(&mut self, fd: u32, text: &str) {
+        let label = self.data_str(text);
+        emit!(self, "    mov x0, #{}\n", fd);
+        self.gen_addr("x1", &label);
+        self.gen_mov_imm("x2", text.len() as i64);
+        self.output.push_str("    mov x8, #64\n    svc #0\n");
+    }
+
+    /// Emits `def` into the data section and switches back to text.
+    fn gen_data(&mut self, def: &str) {
+        emit!(self, "\n{}\n{}\n{}\n", self.target.data_section(), def, self.target.text_section());
+    }
+
+    /// Reserves `size` zeroed bytes in .bss under `label`, aligned to
+    /// `align`. Reserving a label again returns the slot already there, so
+    /// runtime state such as the RNG is shared by everything using it.
+    fn bss_slot(&mut self, label: &str, size: usize, align: usize) {
+        if self.bss.iter().any(|(l, ..)| l == label) { return; }
+        self.bss.push((label.to_string(), size, align));
+    }
+
+    /// Emits every reserved .bss slot, once all code has been generated.
+    fn gen_bss(&mut self) {
+        if self.bss.is_empty() { return; }
+        emit!(self, "
+{}
+", self.target.bss_section());
+        for (label, size, align) in &self.bss {
+            let _ = writeln!(self.output, ".balign {}\n{}: .zero {}", align, label, size);
+        }
+    }
+
+    /// Puts the constant `value` into `reg`: one `mov` when it can encode
+    /// the value, otherwise a load from the literal pool.
+    fn gen_mov_imm(&mut self, reg: &str, value: i64) {
+        if mov_encodable(value) {
+            emit!(self, "    mov {}, #{}\n", reg, value);
+        } else {
+            emit!(self, "    ldr {}, ={}\n", reg, value);
+        }
+    }
+
+    /// Compares `reg` with the constant `value`. Only 12-bit immediates fit
+    /// `cmp` (and `cmn`, for negative ones); others go through `scratch`.
+    fn gen_cmp_imm(&mut self, reg: &str, value: i64, scratch: &str) {
+        match value {
+            0..4096 => emit!(self, "    cmp {}, #{}\n", reg, value),
+            -4095..0 => emit!(self, "    cmn {}, #{}\n", reg, -value),
+            _ => {
+                self.gen_mov_imm(scratch, value);
+                emit!(self, "    cmp {}, {}\n", reg, scratch);
+            }
+        }
+    }
+
+    /// Puts the address of a label, usually in .data, into `reg`.
+    fn gen_addr(&mut self, reg: &str, label: &str) {
+        let code = self.target.load_address(reg, label);
+        self.output.push_str(&code);
+    }
+
+    /// Returns a fresh local label naming the construct, e.g. `.Lwhile7`.
+    /// Every label comes from here, so numbers never repeat, even across
+    /// included files; related labels add a suffix such as `.Lwhile7_end`.
+    fn new_label(&mut self, kind: &str) -> String {
+        let label = format!("{}{}{}", self.target.local_prefix(), kind, self.label_count);
+        self.label_count += 1;
+        label
+    }
+
+    /// Places `text` in .data as a NUL-terminated C string and returns its label.
+    fn data_cstr(&mut self, text: &str) -> String {
+        let l = self.new_label("str");
+        self.gen_data(&format!("{}: .asciz \"{}\"", l, asm_escape(text)));
+        l
+    }
+
+    /// Places `text` in .data and returns its label. The string takes
+    /// `text.len()` bytes: UTF-8 is stored as is.
+    fn data_str(&mut self, text: &str) -> String {
+        let l = self.new_label("str");
+        self.gen_data(&format!("{}: .ascii \"{}\"", l, asm_escape(text)));
+        l
+    }
+
+    /// Lowers `every N ms`. The deadline lives in a timespec of the loop's
+    /// own and advances by the period after each pass; sleeping until it
+    /// with an absolute clock_nanosleep keeps the cadence from drifting by
+    /// however long the body took.
+    fn gen_every(&mut self, period: Operand, body: Vec<StmtId>) {
+        let l = self.new_label("every");
+        let deadline = format!("{}_deadline", l);
+        self.bss_slot(&deadline, 16, 8);
+        // clock_gettime(CLOCK_MONOTONIC, &deadline)
+        self.output.push_str("    mov x0, #1\n");
+        self.output.push_str(&self.target.load_address("x1", &deadline));
+        emit!(self, "    mov x8, #113\n    svc #0\n{}:\n", l);
+        self.gen_block(body);
+        if self.in_main_body() && !self.timers.is_empty() {
+            emit!(self, "    bl {}\n", self.timer_label("run"));
+        }
+        self.load_operand(&period, "x0");
+        // 1_000_000 ns per ms, and 1_000_000_000 ns carried into the seconds
+        self.output.push_str("    mov x9, #0x4240\n    movk x9, #0xf, lsl #16\n    mul x0, x0, x9\n");
+        self.output.push_str(&self.target.load_address("x2", &deadline));
+        emit!(self, "    ldp x9, x10, [x2]\n    add x10, x10, x0\n    mov x11, #0xca00\n    movk x11, #0x3b9a, lsl #16\n");
+        emit!(self, "{l}_carry:\n    cmp x10, x11\n    b.lo {l}_sleep\n    sub x10, x10, x11\n    add x9, x9, #1\n    b {l}_carry\n", l = l);
+        // clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &deadline, NULL)
+        emit!(self, "{}_sleep:\n    stp x9, x10, [x2]\n    mov x0, #1\n    mov x1, #1\n    mov x3, #0\n    mov x8, #115\n    svc #0\n    b {}\n", l, l);
+    }
+
+    /// True while generating the main program or an `after` body, which
+    /// share its frame and its timers.
+    fn in_main_body(&self) -> bool {
+        self.scope == 0 || self.timer_scopes.contains(&self.scope)
+    }
+
+    /// Label `part` of the timer queue, e.g. its `run` routine.
+    fn timer_label(&self, part: &str) -> String {
+        format!("{}timers_{}", self.target.local_prefix(), part)
+    }
+
+    /// Lowers `after N ms`: sets the timer's deadline N ms of the cycle
+    /// counter ahead, replacing any it still had, and moves the body out of
+    /// line into a subroutine the queue calls once the deadline passes.
+    fn gen_after(&mut self, delay: Operand, body: Vec<StmtId>) {
+        if !self.in_main_body() {
+            self.errors.push("`after` only works in the main program, whose loops run the timers".to_string());
+            return;
+        }
+        let l = self.new_label("after");
+        let slot = self.timers.len() * 8;
+        self.timers.push(l.clone());
+        self.load_operand(&delay, "x0");
+        self.output.push_str("    mrs x9, cntfrq_el0\n    mul x0, x0, x9\n    mov x9, #1000\n    sdiv x0, x0, x9\n    mrs x9, cntvct_el0\n    add x0, x0, x9\n");
+        self.gen_addr("x9", &self.timer_label("at"));
+        emit!(self, "    str x0, [x9, #{}]\n", slot);
+        let main = std::mem::take(&mut self.output);
+        self.mark_loc();
+        emit!(self, "\n{}:\n    str x30, [sp, #-16]!\n", l);
+        let rescues = std::mem::take(&mut self.rescues);
+        let scope = self.enter_scope();
+        self.timer_scopes.push(self.scope);
+        self.gen_block(body);
+        self.scope = scope;
+        self.rescues = rescues;
+        self.output.push_str("    ldr x30, [sp], #16\n    ret\n");
+        let code = std::mem::replace(&mut self.output, main);
+        self.deferred.push_str(&code);
+    }
+
+    /// Ends the main program by running the timers still pending, sleeping
+    /// until each next deadline rather than spinning on the counter.
+    fn gen_timer_wait(&mut self) {
+        let l = self.new_label("wait");
+        let sleep = self.timer_label("sleep");
+        self.bss_slot(&sleep, 16, 8);
+        emit!(self, "{l}:\n    bl {}\n    cbz x0, {l}_done\n    mrs x9, cntvct_el0\n    subs x0, x0, x9\n    b.ls {l}\n", self.timer_label("run"), l = l);
+        // The ticks left as a timespec: whole seconds, then the rest in ns
+        self.output.push_str("    mrs x9, cntfrq_el0\n    udiv x10, x0, x9\n    msub x0, x10, x9, x0\n    mov x11, #0xca00\n    movk x11, #0x3b9a, lsl #16\n    mul x0, x0, x11\n    udiv x11, x0, x9\n");
+        self.gen_addr("x0", &sleep);
+        // nanosleep(&sleep, NULL)
+        emit!(self, "    stp x10, x11, [x0]\n    mov x1, #0\n    mov x8, #101\n    svc #0\n    b {}\n{}_done:\n", l, l);
+    }
+
+    /// Emits the timer queue's `run` routine. Each pass scans the deadlines
+    /// for the earliest pending one and, if it has passed, clears it and
+    /// calls that timer's body, so due timers fire in deadline order. It
+    /// returns the earliest deadline still pending in x0, or 0 if none is.
+    fn gen_timer_queue(&mut self) {
+        let run = self.timer_label("run");
+        let at = self.timer_label("at");
+        let timers = self.timers.clone();
+        self.bss_slot(&at, timers.len() * 8, 8);
+        emit!(self, "\n{run}:\n    str x30, [sp, #-16]!\n{run}_scan:\n    mov x10, #0\n", run = run);
+        self.gen_addr("x9", &at);
+        for k in 0..timers.len() {
+            emit!(self, "    ldr x0, [x9, #{}]\n    cbz x0, {r}_{k}\n    cbz x10, {r}_{k}_take\n    cmp x0, x10\n    b.hs {r}_{k}\n", k * 8, r = run, k = k);
+            emit!(self, "{r}_{k}_take:\n    mov x10, x0\n    mov x11, #{k}\n{r}_{k}:\n", r = run, k = k);
+        }
+        emit!(self, "    mov x0, x10\n    cbz x0, {r}_done\n    mrs x1, cntvct_el0\n    cmp x1, x0\n    b.lo {r}_done\n    str xzr, [x9, x11, lsl #3]\n", r = run);
+        // The first timer's call comes right after, so it needs no compare
+        for k in 1..timers.len() {
+            self.gen_cmp_imm("x11", k as i64, "x10");
+            emit!(self, "    b.eq {}_{}_fire\n", run, k);
+        }
+        for (k, body) in timers.iter().enumerate() {
+            emit!(self, "{}_{}_fire:\n    bl {}\n    b {}_scan\n", run, k, body, run);
+        }
+        emit!(self, "{}_done:\n    ldr x30, [sp], #16\n    ret\n", run);
+    }
+
+    /// Emits `__hamer_heap_reserve`, which makes sure the x28 heap has x9
+    /// more bytes: when the current chunk is short, it maps a fresh one of
+    /// at least `HEAP_SIZE` bytes and carries on there. Only x9-x11 and the
+    /// flags change besides x28. A heap end of 0 (`--no-runtime`, where the
+    /// host supplies the heap) is never checked. In a program that spawns,
+    /// the end is the first word of the running thread's block. `heap_at` is
+    /// where startup records the end of the first chunk.
+    fn gen_heap_reserve(&mut self, heap_at: Option<usize>) {
+        let end = self.heap_end();
+        if let Some(at) = heap_at {
+            let record = format!("    mov x9, #{}\n    add x9, x28, x9\n{}    str x9, [x10]\n", HEAP_SIZE, self.target.load_address("x10", &end));
+            self.output.insert_str(at, &record);
+        }
+        let name = "__hamer_heap_reserve";
+        let grow = format!("{}heap_grow", self.target.local_prefix());
+        emit!(self, "{}{}:\n", self.target.function_type(name), name);
+        if self.spawns {
+            self.output.push_str("    mrs x10, tpidr_el0\n");
+        } else {
+            self.gen_addr("x10", &end);
+        }
+        emit!(self, "    ldr x11, [x10]\n    cbz x11, {g}_done\n    sub x11, x11, x28\n    cmp x11, x9\n    b.lo {g}\n{g}_done:\n    ret\n", g = grow);
+        emit!(self, "{}:\n    stp x0, x1, [sp, #-64]!\n    stp x2, x3, [sp, #16]\n    stp x4, x5, [sp, #32]\n    str x8, [sp, #48]\n", grow);
+        // Round up to keep the heap 16-byte aligned; small requests get a whole chunk
+        emit!(self, "    add x9, x9, #15\n    and x9, x9, #-16\n    mov x1, #{}\n    cmp x9, x1\n    csel x11, x9, x1, hi\n", HEAP_SIZE);
+        emit!(self, "    mov x0, #0\n    mov x1, x11\n    mov x2, #3\n    mov x3, #34\n    mov x4, #-1\n    mov x5, #0\n    mov x8, #222\n    svc #0\n    cmn x0, #4095\n    b.hs {}_full\n", grow);
+        emit!(self, "    mov x28, x0\n    add x11, x0, x11\n    str x11, [x10]\n    ldp x2, x3, [sp, #16]\n    ldp x4, x5, [sp, #32]\n    ldr x8, [sp, #48]\n    ldp x0, x1, [sp], #64\n    ret\n{}_full:\n", grow);
+        self.gen_write_fd(2, "error: out of memory\n");
+        self.output.push_str("    mov x0, #1\n    mov x8, #94\n    svc #0\n");
+        let size = self.target.symbol_size(name);
+        self.output.push_str(&size);
+    }
+
+    /// Lowers `print "a" x "b" 5`: neighbouring strings and literal numbers
+    /// are merged into one write, and `end` (the newline) joins the last of them.
+    fn gen_print_list(&mut self, items: Vec<PrintItem>, end: &str) {
+        let mut literal = String::new();
+        for item in items {
+            let path = match item {
+                PrintItem::Str(s) => { literal.push_str(&s); continue; }
+                PrintItem::Value(Operand::Num(n)) if n.fract() != 0.0 => { literal.push_str(&float_text(n)); continue; }
+                PrintItem::Value(Operand::Num(n)) => { let _ = write!(literal, "{}", n as i64); continue; }
+                PrintItem::Value(Operand::Path(p)) => p,
+            };
+            if !self.symbols.contains_key(&path[0]) {
+                self.errors.push(format!("unknown variable `{}` in print", self.path_name(&path)));
+                continue;
+            }
+            if !literal.is_empty() { self.gen_write_str(&std::mem::take(&mut literal)); }
+            if self.is_text_path(&path) {
+                self.load_text(&path);
+                emit!(self, "    mov x0, #{}\n    mov x8, #64\n    svc #0\n", self.out_fd);
+            } else if self.is_float_path(&path) {
+                self.load_path(&path, "x0");
+                self.gen_print_call("__hamer_print_float");
+            } else {
+                self.load_path(&path, "x0");
+                self.output.push_str("    mov x1, #10\n    mov x2, #0\n    mov x3, #32\n    mov x4, #1\n");
+                self.gen_print_call("__hamer_print_num");
+            }
+        }
+        literal.push_str(end);
+        if !literal.is_empty() { self.gen_write_str(&literal); }
+    }
+
+    /// Lowers `print format "..." args` to literal writes and calls to the
+    /// number formatting routine. Supports `%d` (signed), `%x`, `%b` with an optional
+    /// `0` pad flag and width, plus `%%`.
+    fn gen_print_format(&mut self, fmt: String, args: Vec<Operand>) {
+        let mut args = args.into_iter();
+        let mut literal = String::new();
+        let mut chars = fmt.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' { literal.push(c); continue; }
+            if chars.peek() == Some(&'%') { chars.next(); literal.push('%'); continue; }
+            let pad = if chars.peek() == Some(&'0') { chars.next(); '0' } else { ' ' };
+            let mut width = 0;
+            while let Some(d) = chars.peek().and_then(|d| d.to_digit(10)) {
+                width = (width * 10 + d).min(64);
+                chars.next();
+            }
+            let base = match chars.next() {
+                Some('d') => 10,
+                Some('x') => 16,
+                Some('b') => 2,
+                other => {
+                    self.errors.push(format!("unsupported format specifier `%{}` in \"{}\"", other.map(String::from).unwrap_or_default(), fmt));
+                    return;
+                }
+            };
+            let Some(arg) = args.next() else {
+                self.errors.push(format!("format string \"{}\" needs more values", fmt));
+                return;
+            };
+            if !literal.is_empty() { self.gen_write_str(&std::mem::take(&mut literal)); }
+            self.load_operand(&arg, "x0");
+            emit!(self, "    mov x1, #{}\n    mov x2, #{}\n    mov x3, #{}\n    mov x4, #{}\n", base, width, pad as u32, (base == 10) as u32);
+            self.gen_print_call("__hamer_print_num");
+        }
+        if args.next().is_some() {
+            self.errors.push(format!("too many values for format string \"{}\"", fmt));
+        }
+        literal.push('\n');
+        self.gen_write_str(&literal);
+    }
+
+    /// Takes the next free variable register for `owner`, or once x27 is
+    /// taken, the next stack slot in the frame of the body being generated.
+    fn alloc_var(&mut self, owner: Symbol) -> Loc {
+        if self.reg_count <= LAST_VAR_REG {
+            let reg = format!("x{}", self.reg_count);
+            debug!("{} holds `{}`", reg, self.name(owner));
+            self.reg_count += 1;
+            return Loc::Reg(reg);
+        }
+        if self.slots == MAX_SLOTS {
+            self.errors.push(format!("too many variables: {} fit in registers and {} more on the stack",
+                LAST_VAR_REG + 1 - FIRST_VAR_REG, MAX_SLOTS));
+        }
+        let n = self.slots.min(MAX_SLOTS - 1);
+        debug!("stack slot {} holds `{}`", n, self.name(owner));
+        self.slots += 1;
+        Loc::Slot { frame: self.fn_scope, n }
+    }
+
+    /// Returns where a scalar variable lives, allocating a place on first use.
+    fn var_loc(&mut self, name: Symbol) -> Loc {
+        if let Some(loc) = self.symbols.get(&name) { return loc.clone(); }
+        let loc = self.alloc_var(name);
+        self.symbols.insert(name, loc.clone());
+        loc
+    }
+
+    /// Address of a stack slot: slots sit above the frame record x29 points
+    /// at. Only the body that owns the frame can reach them, as x29 moves
+    /// with every call.
+    fn slot_addr(&mut self, name: Symbol, frame: usize, n: usize) -> String {
+        if frame != self.fn_scope {
+            let owner = if frame == 0 { "the main program's" } else { "another function's" };
+            self.errors.push(format!("`{}` is kept on {} stack and can't be used here", self.name(name), owner));
+        }
+        format!("[x29, #{}]", 16 + n * 8)
+    }
+
+    /// The register holding `name`, loading it into `scratch` first if it
+    /// lives on the stack.
+    fn var_in(&mut self, name: Symbol, loc: &Loc, scratch: &str) -> String {
+        match *loc {
+            Loc::Reg(ref reg) => reg.clone(),
+            Loc::Slot { frame, n } => {
+                let addr = self.slot_addr(name, frame, n);
+                emit!(self, "    ldr {}, {}\n", scratch, addr);
+                scratch.to_string()
+            }
+        }
+    }
+
+    /// Copies the value of `name` into `dst`.
+    fn copy_var(&mut self, name: Symbol, loc: &Loc, dst: &str) {
+        match *loc {
+            Loc::Reg(ref reg) => emit!(self, "    mov {}, {}\n", dst, reg),
+            Loc::Slot { .. } => { self.var_in(name, loc, dst); }
+        }
+    }
+
+    /// The register to compute a new value of a variable in: its own, or
+    /// `scratch` for `store_var` to write back.
+    fn var_out(loc: &Loc, scratch: &str) -> String {
+        match loc {
+            Loc::Reg(reg) => reg.clone(),
+            Loc::Slot { .. } => scratch.to_string(),
+        }
+    }
+
+    /// Makes `src` the value of `name`.
+    fn store_var(&mut self, name: Symbol, loc: &Loc, src: &str) {
+        match *loc {
+            Loc::Reg(ref reg) if reg == src => {}
+            Loc::Reg(ref reg) => emit!(self, "    mov {}, {}\n", reg, src),
+            Loc::Slot { frame, n } => {
+                let addr = self.slot_addr(name, frame, n);
+                emit!(self, "    str {}, {}\n", src, addr);
+            }
+        }
+    }
+
+    /// Assigns `src` to `name`, giving the variable a place if it is new.
+    fn set_var(&mut self, name: Symbol, src: &str) {
+        let loc = self.var_loc(name);
+        self.store_var(name, &loc, src);
+    }
+
+    /// Loads a map variable into x0, reporting an error if it isn't one.
+    fn load_map(&mut self, map: &[Symbol]) -> bool {
+        if map.len() != 1 || self.builtins.get(&map[0]) != Some(&Builtin::Map) {
+            self.errors.push(format!("`{}` is not a map", self.path_name(map)));
+            return false;
+        }
+        self.load_path(map, "x0");
+        true
+    }
+
+    /// The record standing for map key `key`: one per distinct key, holding
+    /// its hash, so the runtime compares keys by address.
+    fn map_key(&mut self, key: &str) -> String {
+        let prefix = self.target.local_prefix();
+        if let Some(k) = self.map_keys.iter().position(|k| k == key) {
+            return format!("{}key{}", prefix, k);
+        }
+        let label = format!("{}key{}", prefix, self.map_keys.len());
+        self.map_keys.push(key.to_string());
+        self.gen_data(&format!(".balign 8\n{}: .quad {:#x}", label, runtime::key_hash(key)));
+        label
+    }
+
+    /// Bump-allocates an instance of `class_name` and binds it to `var_name`.
+    /// Returns the register left holding the object's address.
+    fn alloc_object(&mut self, var_name: Symbol, class_name: Symbol) -> String {
+        self.ensure_class(class_name);
+        let loc = self.alloc_var(var_name);
+        let reg = Self::var_out(&loc, "x9");
+        match self.class_map.get(&class_name).map(|f| f.len() * 8) {
+            Some(size) => {
+                let reused = (self.deletes && size > 0).then(|| self.pop_free(class_name, &reg, size));
+                self.reserve_heap(size);
+                if size < 4096 {
+                    emit!(self, "    mov {}, x28\n    add x28, x28, #{}\n", reg, size);
+                } else {
+                    emit!(self, "    mov {}, x28\n", reg);
+                    self.gen_mov_imm("x10", size as i64);
+                    self.output.push_str("    add x28, x28, x10\n");
+                }
+                if let Some(done) = reused { emit!(self, "{}:\n", done); }
+            }
+            None => self.errors.push(format!("unknown class `{}` in `new {}`", self.name(class_name), self.name(class_name))),
+        }
+        self.store_var(var_name, &loc, &reg);
+        self.symbols.insert(var_name, loc);
+        self.elements.remove(&var_name);
+        self.obj_types.insert(var_name, class_name);
+        reg
+    }
+
+    /// Gives the object `var` (a `class` held in `reg`) its starting
+    /// values: `args` for the first fields, then each other field's `init`.
+    fn init_object(&mut self, var: Symbol, class: Symbol, reg: &str, args: Vec<Operand>) {
+        let Some(fields) = self.class_map.get(&class).cloned() else { return };
+        for (i, f) in fields.iter().enumerate().skip(args.len()) {
+            let Some(n) = f.init else { continue };
+            let src = self.init_word(f, n, "x10");
+            emit!(self, "    str {}, [{}, #{}]\n", src, reg, i * 8);
+        }
+        for (f, arg) in fields.iter().zip(args) {
+            let value = match arg {
+                Operand::Num(n) => Expr::Num(n),
+                Operand::Path(p) => Expr::Path(p),
+            };
+            self.assign_field(&[var, f.name], &value);
+        }
+    }
+
+    /// Puts the word field `f` starts at, `n`, in `scratch` and returns
+    /// the register holding it (`xzr` for 0).
+    fn init_word(&mut self, f: &Field, n: f64, scratch: &str) -> String {
+        let word = if f.class.is_some_and(|c| self.name(c) == "float") { n.to_bits() as i64 } else { n as i64 };
+        if word == 0 { return "xzr".to_string(); }
+        self.gen_mov_imm(scratch, word);
+        scratch.to_string()
+    }
+
+    /// Takes an object of `class` (`size` bytes) off its free list into
+    /// `reg`, zeroed like a fresh one, and returns the label to go on at.
+    /// Falls through to the code after it when the list is empty.
+    fn pop_free(&mut self, class: Symbol, reg: &str, size: usize) -> String {
+        let list = self.free_list(class);
+        let l = self.new_label("reuse");
+        self.gen_addr("x10", &list);
+        emit!(self, "    ldr {r}, [x10]\n    cbz {r}, {l}\n    ldr x11, [{r}]\n    str x11, [x10]\n", r = reg, l = l);
+        for offset in (0..size).step_by(8) {
+            emit!(self, "    str xzr, [{}, #{}]\n", reg, offset);
+        }
+        emit!(self, "    b {l}_done\n{l}:\n", l = l);
+        format!("{}_done", l)
+    }
+
+    /// The .bss word heading the free list of `class`: deleted objects,
+    /// each linked to the next through its first word.
+    fn free_list(&mut self, class: Symbol) -> String {
+        let k = match self.free_lists.iter().position(|&c| c == class) {
+            Some(k) => k,
+            None => {
+                self.free_lists.push(class);
+                self.free_lists.len() - 1
+            }
+        };
+        let label = format!("{}free{}", self.target.local_prefix(), k);
+        self.bss_slot(&label, 8, 8);
+        label
+    }
+
+    /// Lowers `delete var`: pushes the object onto its class's free list
+    /// and sets `var` to 0, so deleting it again does nothing.
+    fn gen_delete(&mut self, var: Symbol) {
+        let name = self.name(var);
+        let class = match self.obj_types.get(&var) {
+            Some(_) if self.arrays.contains_key(&var) => {
+                self.errors.push(format!("`delete {}`: `{}` is an array; only objects made with `new` can be deleted", name, name));
+                return;
+            }
+            Some(_) if let Some(&array) = self.elements.get(&var) => {
+                self.errors.push(format!("`delete {}`: `{}` is an element of the array `{}`; only objects made with `new` can be deleted", name, name, self.name(array)));
+                return;
+            }
+            Some(&class) => class,
+            None => {
+                self.errors.push(format!("`delete {}`: `{}` is not an object made with `new`", name, name));
+                return;
+            }
+        };
+        // An object with no fields takes no memory, so there is nothing to hand back
+        if self.class_map.get(&class).is_some_and(|f| !f.is_empty()) {
+            let loc = self.var_loc(var);
+            let reg = self.var_in(var, &loc, "x9");
+            let list = self.free_list(class);
+            let l = self.new_label("delete");
+            emit!(self, "    cbz {}, {}\n", reg, l);
+            self.gen_addr("x10", &list);
+            emit!(self, "    ldr x11, [x10]\n    str x11, [{r}]\n    str {r}, [x10]\n{l}:\n", r = reg, l = l);
+        }
+        self.set_var(var, "xzr");
+    }
+
+    /// Makes sure the heap has `size` more bytes before they are bumped
+    /// off x28; see `gen_heap_reserve`.
+    fn reserve_heap(&mut self, size: usize) {
+        if size == 0 { return; }
+        self.gen_mov_imm("x9", size as i64);
+        self.output.push_str("    bl __hamer_heap_reserve\n");
+        self.heap_reserve = true;
+    }
+
+    /// Allocates `len` contiguous objects after a word holding the count;
+    /// the variable points at the first element. Fields with an `init`
+    /// are set in every element.
+    fn alloc_array(&mut self, var_name: Symbol, class_name: Symbol, len: usize) {
+        self.ensure_class(class_name);
+        let Some(fields) = self.class_map.get(&class_name).cloned() else {
+            self.errors.push(format!("unknown class `{}` in `new {}[{}]`", self.name(class_name), self.name(class_name), len));
+            return;
+        };
+        let size = fields.len() * 8;
+        self.gen_array(var_name, len, size);
+        if len > 0 && fields.iter().any(|f| f.init.is_some()) {
+            let loc = self.var_loc(var_name);
+            let base = self.var_in(var_name, &loc, "x9");
+            if base != "x9" { emit!(self, "    mov x9, {}\n", base); }
+            emit!(self, "    ldr x10, ={}\n    add x10, x9, x10\n", len * size);
+            let l = self.new_label("init");
+            emit!(self, "{}:\n", l);
+            for (i, f) in fields.iter().enumerate() {
+                let Some(n) = f.init else { continue };
+                let src = self.init_word(f, n, "x11");
+                emit!(self, "    str {}, [x9, #{}]\n", src, i * 8);
+            }
+            if size < 4096 {
+                emit!(self, "    add x9, x9, #{}\n", size);
+            } else {
+                emit!(self, "    ldr x11, ={}\n    add x9, x9, x11\n", size);
+            }
+            emit!(self, "    cmp x9, x10\n    b.lo {}\n", l);
+        }
+        self.obj_types.insert(var_name, class_name);
+        self.arrays.insert(var_name, len);
+    }
+
+    /// Bump-allocates `len` elements of `size` bytes each for `var_name`,
+    /// after a word holding the length.
+    fn gen_array(&mut self, var_name: Symbol, len: usize, size: usize) {
+        let loc = self.var_loc(var_name);
+        let reg = Self::var_out(&loc, "x10");
+        self.reserve_heap(8 + len * size);
+        self.gen_mov_imm("x9", len as i64);
+        emit!(self, "    str x9, [x28]\n    add {}, x28, #8\n    ldr x9, ={}\n    add x28, x28, x9\n", reg, 8 + len * size);
+        self.store_var(var_name, &loc, &reg);
+    }
+
+    /// Walks a number array with `var` holding each element in turn; a
+    /// hidden cursor steps through them.
+    fn gen_for_each_number(&mut self, var: Symbol, array: Symbol, len: usize, body: Vec<StmtId>) {
+        let array_loc = self.var_loc(array);
+        let base = self.var_in(array, &array_loc, "x10");
+        let cursor_name = self.hidden_var(array, "cursor");
+        let cursor_loc = self.alloc_var(cursor_name);
+        let end_name = self.hidden_var(array, "end");
+        let end_loc = self.alloc_var(end_name);
+        self.store_var(cursor_name, &cursor_loc, &base);
+        let end = Self::var_out(&end_loc, "x9");
+        emit!(self, "    ldr x9, ={}\n    add {}, {}, x9\n", len * 8, end, base);
+        self.store_var(end_name, &end_loc, &end);
+        let loc = self.var_loc(var);
+        self.obj_types.remove(&var);
+        self.arrays.remove(&var);
+        self.builtins.remove(&var);
+        let l = self.new_label("for");
+        emit!(self, "{}:\n", l);
+        let cursor = self.var_in(cursor_name, &cursor_loc, "x10");
+        let end = self.var_in(end_name, &end_loc, "x11");
+        emit!(self, "    cmp {}, {}\n    b.hs {}_end\n", cursor, end, l);
+        let value = Self::var_out(&loc, "x9");
+        emit!(self, "    ldr {}, [{}], #8\n", value, cursor);
+        self.store_var(cursor_name, &cursor_loc, &cursor);
+        self.store_var(var, &loc, &value);
+        self.gen_block(body);
+        emit!(self, "    b {}\n{}_end:\n", l, l);
+    }
+
+    /// Walks an object array with `var` pointing at each element in turn.
+    /// The end address is computed once, before the first iteration.
+    fn gen_for_each(&mut self, var: Symbol, array: Symbol, body: Vec<StmtId>) {
+        if let Some(&len) = self.arrays.get(&array) && self.is_number_array(array) {
+            self.gen_for_each_number(var, array, len, body);
+            return;
+        }
+        let (Some(len), Some(class)) = (self.arrays.get(&array).copied(), self.obj_types.get(&array).copied()) else {
+            self.errors.push(format!("`for {} in {}` needs an object array", self.name(var), self.name(array)));
+            return;
+        };
+        let size = self.class_map.get(&class).map_or(8, |f| f.len() * 8);
+        let array_loc = self.var_loc(array);
+        let base = self.var_in(array, &array_loc, "x10");
+        let loc = self.var_loc(var);
+        let end_name = self.hidden_var(array, "end");
+        let end_loc = self.alloc_var(end_name);
+        self.obj_types.insert(var, class);
+        self.arrays.remove(&var);
+        self.elements.insert(var, array);
+        let l = self.new_label("for");
+        self.store_var(var, &loc, &base);
+        let end = Self::var_out(&end_loc, "x9");
+        emit!(self, "    ldr x9, ={}\n    add {}, {}, x9\n", len * size, end, base);
+        self.store_var(end_name, &end_loc, &end);
+        emit!(self, "{}:\n", l);
+        let reg = self.var_in(var, &loc, "x10");
+        let end = self.var_in(end_name, &end_loc, "x11");
+        emit!(self, "    cmp {}, {}\n    b.hs {}_end\n", reg, end, l);
+        self.gen_block(body);
+        let reg = self.var_in(var, &loc, "x10");
+        emit!(self, "    add {}, {}, #{}\n", reg, reg, size);
+        self.store_var(var, &loc, &reg);
+        emit!(self, "    b {}\n{}_end:\n", l, l);
+    }
+
+    /// The loop's own variable `what` for walking `array`, such as its
+    /// cursor. Its name has a space, so no program can spell it.
+    fn hidden_var(&mut self, array: Symbol, what: &str) -> Symbol {
+        let name = format!("{}'s {}", self.name(array), what);
+        self.session.interner.intern(&name)
+    }
+
+    /// Loads what the sort and find routines take into x0-x3: an array, its
+    /// length, the size of an element and the offset of the field compared
+    /// (`by`, or else the first). Number arrays compare the numbers.
+    fn load_array_key(&mut self, what: &str, array: Symbol, field: Option<Symbol>) -> bool {
+        let Some(len) = self.arrays.get(&array).copied() else {
+            self.errors.push(format!("`{} {}` needs an array", what, self.name(array)));
+            return false;
+        };
+        let Some(class) = self.obj_types.get(&array).copied() else {
+            if let Some(name) = field {
+                self.errors.push(format!("`{}` holds numbers, which have no field `{}` to {} by", self.name(array), self.name(name), what));
+                return false;
+            }
+            let loc = self.var_loc(array);
+            self.copy_var(array, &loc, "x0");
+            self.gen_mov_imm("x1", len as i64);
+            self.output.push_str("    mov x2, #8\n    mov x3, #0\n");
+            return true;
+        };
+        let fields = self.class_map.get(&class).cloned().unwrap_or_default();
+        let key = match field {
+            Some(name) => match fields.iter().position(|f| f.name == name) {
+                Some(i) => i,
+                None => {
+                    self.errors.push(format!("class `{}` has no field `{}` to {} `{}` by", self.name(class), self.name(name), what, self.name(array)));
+                    return false;
+                }
+            },
+            None if fields.is_empty() => {
+                self.errors.push(format!("class `{}` has no fields to {} `{}` by", self.name(class), what, self.name(array)));
+                return false;
+            }
+            None => 0,
+        };
+        let loc = self.var_loc(array);
+        self.copy_var(array, &loc, "x0");
+        self.gen_mov_imm("x1", len as i64);
+        self.gen_mov_imm("x2", fields.len() as i64 * 8);
+        self.gen_mov_imm("x3", key as i64 * 8);
+        true
+    }
+
+    /// Allocates an empty text value with a `cap`-byte buffer.
+    fn alloc_text(&mut self, var_name: Symbol, cap: usize) -> Loc {
+        let loc = self.alloc_var(var_name);
+        let reg = Self::var_out(&loc, "x9");
+        self.gen_new_text(&reg, cap);
+        self.store_var(var_name, &loc, &reg);
+        self.symbols.insert(var_name, loc.clone());
+        self.obj_types.remove(&var_name);
+        self.builtins.insert(var_name, Builtin::Text);
+        loc
+    }
+
+    /// Puts a new text value in `reg`: the header [data ptr, len, capacity]
+    /// followed by a `cap`-byte buffer. Uses x1.
+    fn gen_new_text(&mut self, reg: &str, cap: usize) {
+        self.reserve_heap(32 + cap);
+        emit!(self, "    mov {}, x28\n    add x1, x28, #32\n    str x1, [{}]\n", reg, reg);
+        self.gen_mov_imm("x1", cap as i64);
+        emit!(self, "    str x1, [{}, #16]\n", reg);
+        if 32 + cap < 4096 {
+            emit!(self, "    add x28, x28, #{}\n", 32 + cap);
+        } else {
+            self.gen_mov_imm("x1", 32 + cap as i64);
+            self.output.push_str("    add x28, x28, x1\n");
+        }
+    }
+
+    /// True if `path` holds a float: a float variable, or a field declared
+    /// `name: float`.
+    fn is_float_path(&self, path: &[Symbol]) -> bool {
+        match path {
+            [var] => self.builtins.get(var) == Some(&Builtin::Float),
+            _ => self.path_class(path).is_some_and(|c| self.name(c) == "float"),
+        }
+    }
+
+    /// True if `expr` has a float in it, making its arithmetic floating point.
+    fn is_float(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Float(_) => true,
+            Expr::Num(_) | Expr::Str(_) => false,
+            Expr::Path(p) => self.is_float_path(p),
+            Expr::Binary { lhs, rhs, .. } => self.is_float(&self.session.exprs[*lhs]) || self.is_float(&self.session.exprs[*rhs]),
+        }
+    }
+
+    /// Evaluates `expr` in floating point into d`depth`, converting integer
+    /// values on the way in. Partial results live in the d registers above
+    /// it; nothing else uses d0-d7 between statements.
+    fn gen_float(&mut self, expr: &Expr, depth: usize, name: &str) {
+        match expr {
+            Expr::Num(n) | Expr::Float(n) => {
+                self.gen_mov_imm("x9", n.to_bits() as i64);
+                emit!(self, "    fmov d{}, x9\n", depth);
+            }
+            Expr::Str(_) => self.errors.push(format!("text can't be used as a number in `{}`", name)),
+            Expr::Path(path) => {
+                if !self.symbols.contains_key(&path[0]) {
+                    self.errors.push(format!("unknown variable `{}` in `{}`", self.path_name(path), name));
+                    return;
+                }
+                let reg = match self.var_in_reg(expr) {
+                    Some(reg) => reg,
+                    None => {
+                        self.load_path(path, "x9");
+                        "x9".to_string()
+                    }
+                };
+                let instr = if self.is_float_path(path) { "fmov" } else { "scvtf" };
+                emit!(self, "    {} d{}, {}\n", instr, depth, reg);
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                if depth >= 7 {
+                    self.errors.push(format!("the expression for `{}` is nested too deeply", name));
+                    return;
+                }
+                let (lhs, rhs) = (self.session.exprs[*lhs].clone(), self.session.exprs[*rhs].clone());
+                self.gen_float(&lhs, depth, name);
+                self.gen_float(&rhs, depth + 1, name);
+                let instr = match op {
+                    Token::Minus => "fsub",
+                    Token::Star => "fmul",
+                    Token::Slash => "fdiv",
+                    _ => "fadd",
+                };
+                emit!(self, "    {} d{d}, d{d}, d{}\n", instr, depth + 1, d = depth);
+            }
+        }
+    }
+
+    /// Turns the float bits in `reg` into an integer, rounding toward zero,
+    /// and warns that `name` lost its fraction.
+    fn narrow_float(&mut self, reg: &str, name: &str) {
+        emit!(self, "    fmov d0, {}\n    fcvtzs {}, d0\n", reg, reg);
+        self.warnings.push(format!("{}:{}: the float `{}` is narrowed to an integer here, losing its fraction", self.file, self.line, name));
+    }
+
+    /// Stores `value` in the variable or field `path`, as text, a float or
+    /// an integer depending on what either side holds.
+    fn assign_field(&mut self, path: &[Symbol], value: &Expr) {
+        if self.is_text(value) {
+            self.assign_text(path, value);
+            return;
+        }
+        if self.is_float(value) || self.is_float_path(path) {
+            self.assign_float(path, value, false);
+            return;
+        }
+        let name = self.path_name(path);
+        if self.in_memory(path) {
+            // Addressing the field only uses x9 and x10, so the value survives it
+            self.gen_expr(value, "x1", 0, &name);
+            let (reg, offset) = self.get_path_info(path);
+            emit!(self, "    str x1, [{}, #{}]\n", reg, offset);
+        } else if let [var] = *path && let Some(loc) = self.symbols.get(&var).cloned() {
+            let reg = Self::var_out(&loc, "x9");
+            self.gen_expr(value, &reg, 0, &name);
+            self.store_var(var, &loc, &reg);
+            self.builtins.remove(&var);
+        } else {
+            let (reg, _) = self.get_path_info(path);
+            self.gen_expr(value, &reg, 0, &name);
+        }
+    }
+
+    /// Stores `value`, worked out in floating point, in `path`. A new local
+    /// or a float variable or field keeps the float; an integer one gets it
+    /// narrowed toward zero, with a warning.
+    fn assign_float(&mut self, path: &[Symbol], value: &Expr, local: bool) {
+        let name = self.path_name(path);
+        self.gen_float(value, 0, &name);
+        let convert = if local || self.is_float_path(path) {
+            "fmov"
+        } else {
+            self.warnings.push(format!("{}:{}: `{}` holds integers, so the float stored in it loses its fraction", self.file, self.line, name));
+            "fcvtzs"
+        };
+        if let [var] = *path && (local || self.symbols.contains_key(&var)) {
+            let loc = self.var_loc(var);
+            let reg = Self::var_out(&loc, "x1");
+            emit!(self, "    {} {}, d0\n", convert, reg);
+            self.store_var(var, &loc, &reg);
+            if local {
+                self.obj_types.remove(&var);
+                self.arrays.remove(&var);
+                self.builtins.insert(var, Builtin::Float);
+            }
+            return;
+        }
+        emit!(self, "    {} x1, d0\n", convert);
+        // Addressing the field only uses x9 and x10, so the value survives it
+        let (reg, offset) = self.get_path_info(path);
+        if self.in_memory(path) {
+            emit!(self, "    str x1, [{}, #{}]\n", reg, offset);
+        } else {
+            emit!(self, "    mov {}, x1\n", reg);
+        }
+    }
+
+    /// True if `path` holds text: a text variable, or a field declared
+    /// `name: text`.
+    fn is_text_path(&self, path: &[Symbol]) -> bool {
+        match path {
+            [var] => self.builtins.get(var) == Some(&Builtin::Text),
+            _ => self.path_class(path) == Some(kw::TEXT),
+        }
+    }
+
+    /// True if `expr` has text in it, making its `+` a join.
+    fn is_text(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Str(_) => true,
+            Expr::Num(_) | Expr::Float(_) => false,
+            Expr::Path(p) => self.is_text_path(p),
+            Expr::Binary { lhs, rhs, .. } => self.is_text(&self.session.exprs[*lhs]) || self.is_text(&self.session.exprs[*rhs]),
+        }
+    }
+
+    /// Collects the literals and text values `+` joins in `expr`, in order,
+    /// reporting anything else in it against `name`.
+    fn text_parts(&mut self, expr: &Expr, name: &str, parts: &mut Vec<Expr>) -> bool {
+        match expr {
+            Expr::Binary { op: Token::Plus, lhs, rhs } => {
+                let (lhs, rhs) = (self.session.exprs[*lhs].clone(), self.session.exprs[*rhs].clone());
+                self.text_parts(&lhs, name, parts) && self.text_parts(&rhs, name, parts)
+            }
+            Expr::Binary { .. } => {
+                self.errors.push(format!("text can only be joined with `+` in `{}`", name));
+                false
+            }
+            Expr::Path(p) if !self.is_text_path(p) => {
+                self.errors.push(format!("`{}` is a number, not text, so it can't be joined in `{}`; print the two side by side instead", self.path_name(p), name));
+                false
+            }
+            Expr::Num(n) => {
+                self.errors.push(format!("the number {} can't be joined to text in `{}`; print the two side by side instead", n, name));
+                false
+            }
+            _ => {
+                parts.push(expr.clone());
+                true
+            }
+        }
+    }
+
+    /// Loads the bytes and length of the text at `path` into x1 and x2. A
+    /// text field that was never set reads as empty.
+    fn load_text(&mut self, path: &[Symbol]) {
+        if let [var] = *path && let Some(loc) = self.symbols.get(&var).cloned() {
+            let reg = self.var_in(var, &loc, "x1");
+            emit!(self, "    ldp x1, x2, [{}]\n", reg);
+            return;
+        }
+        self.load_path(path, "x1");
+        let l = self.new_label("text");
+        emit!(self, "    mov x2, #0\n    cbz x1, {}\n    ldp x1, x2, [x1]\n{}:\n", l, l);
+    }
+
+    /// Builds a new text value in x0 holding `parts` one after another. A
+    /// lone literal stays where it is in .data, with its capacity full so
+    /// the first append copies it out; anything else is appended piece by
+    /// piece to a fresh buffer.
+    fn gen_text(&mut self, parts: &[Expr]) {
+        if let [Expr::Str(s)] = parts && !s.is_empty() {
+            let label = self.data_str(s);
+            self.reserve_heap(32);
+            self.output.push_str("    mov x0, x28\n    add x28, x28, #32\n");
+            self.gen_addr("x1", &label);
+            self.gen_mov_imm("x2", s.len() as i64);
+            self.output.push_str("    stp x1, x2, [x0]\n    str x2, [x0, #16]\n");
+            return;
+        }
+        let known: usize = parts.iter().map(|p| if let Expr::Str(s) = p { s.len() } else { 0 }).sum();
+        self.gen_new_text("x0", known.max(runtime::TEXT_INITIAL_CAP).next_multiple_of(16));
+        for part in parts {
+            match part {
+                Expr::Str(s) if s.is_empty() => continue,
+                Expr::Str(s) => {
+                    let label = self.data_str(s);
+                    self.gen_addr("x1", &label);
+                    self.gen_mov_imm("x2", s.len() as i64);
+                }
+                Expr::Path(p) => self.load_text(p),
+                _ => continue,
+            }
+            // The routine leaves x0 alone, so the header is still there for the next piece
+            self.call_runtime("__hamer_text_append");
+        }
+    }
+
+    /// Stores a new text value built from `value` in the variable or text
+    /// field `path`.
+    fn assign_text(&mut self, path: &[Symbol], value: &Expr) {
+        let name = self.path_name(path);
+        let mut parts = Vec::new();
+        if !self.text_parts(value, &name, &mut parts) { return; }
+        if let [var] = *path {
+            self.gen_text(&parts);
+            self.set_var(var, "x0");
+            self.obj_types.remove(&var);
+            self.builtins.insert(var, Builtin::Text);
+            return;
+        }
+        if !self.in_memory(path) || self.path_class(path) != Some(kw::TEXT) {
+            let field = path.last().map_or("", |&f| self.name(f));
+            self.errors.push(format!("`{}` can't hold text; declare the field as `{}: text`", name, field));
+            return;
+        }
+        self.gen_text(&parts);
+        // Addressing the field only uses x9 and x10, so the value survives it
+        let (reg, offset) = self.get_path_info(path);
+        emit!(self, "    str x0, [{}, #{}]\n", reg, offset);
+    }
+
+    /// Lowers `match text is "a" then ... done` to a chain of string
+    /// comparisons, falling through to the `else` arm if none match.
+    fn gen_match(&mut self, subject: Symbol, arms: Vec<(String, Vec<StmtId>)>, default: Vec<StmtId>) {
+        if self.builtins.get(&subject) != Some(&Builtin::Text) {
+            self.errors.push(format!("`match {}` needs a text value", self.name(subject)));
+            return;
+        }
+        let loc = self.var_loc(subject);
+        let l = self.new_label("match");
+        for (k, (pattern, body)) in arms.into_iter().enumerate() {
+            let label = self.data_str(&pattern);
+            self.copy_var(subject, &loc, "x0");
+            self.gen_addr("x1", &label);
+            self.gen_mov_imm("x2", pattern.len() as i64);
+            self.call_runtime("__hamer_text_eq");
+            emit!(self, "    cbz x0, {}_{}\n", l, k);
+            self.gen_block(body);
+            emit!(self, "    b {}_end\n{}_{}:\n", l, l, k);
+        }
+        self.gen_block(default);
+        emit!(self, "{}_end:\n", l);
+    }
+
+    /// Where a failed `if` labelled `l` jumps: its else branch, or past it
+    /// when there is none.
+    fn miss_label(l: &str, else_body: &[StmtId]) -> String {
+        if else_body.is_empty() { format!("{}_end", l) } else { format!("{}_else", l) }
+    }
+
+    /// Emits an `if`'s body and else branch, after the test that jumps to
+    /// `miss_label` when the condition fails.
+    fn gen_if_branches(&mut self, l: &str, body: Vec<StmtId>, else_body: Vec<StmtId>) {
+        self.gen_block(body);
+        if !else_body.is_empty() {
+            emit!(self, "    b {}_end\n{}_else:\n", l, l);
+            self.gen_block(else_body);
+        }
+        emit!(self, "{}_end:\n", l);
+    }
+
+    /// Jumps to `target` when `cond` evaluates to `when`, falling through
+    /// otherwise. `and`/`or` stop at the first comparison that decides them.
+    fn gen_branch(&mut self, cond: &Cond, target: &str, when: bool) {
+        match cond {
+            Cond::Cmp { path, op, rhs } => {
+                let (holds, fails) = match op {
+                    Token::Equal => ("eq", "ne"),
+                    Token::Greater => ("gt", "le"),
+                    Token::Less => ("lt", "ge"),
+                    _ => ("ne", "eq"),
+                };
+                if self.is_float_path(path) || self.is_float(rhs) {
+                    let name = self.path_name(path);
+                    self.gen_float(&Expr::Path(path.clone()), 0, &name);
+                    self.gen_float(rhs, 1, &name);
+                    emit!(self, "    fcmp d0, d1\n    b.{} {}\n", if when { holds } else { fails }, target);
+                    return;
+                }
+                self.load_path(path, "x1");
+                match rhs {
+                    Expr::Num(n) => self.gen_cmp_imm("x1", *n as i64, "x2"),
+                    _ => {
+                        let rhs_reg = match self.var_in_reg(rhs) {
+                            Some(reg) => reg,
+                            None => {
+                                self.gen_expr(rhs, "x2", 2, &self.path_name(path));
+                                "x2".to_string()
+                            }
+                        };
+                        emit!(self, "    cmp x1, {}\n", rhs_reg);
+                    }
+                }
+                emit!(self, "    b.{} {}\n", if when { holds } else { fails }, target);
+            }
+            // Both sides must hold: the first failing one settles it
+            Cond::And(a, b) if !when => {
+                let (a, b) = (self.session.conds[*a].clone(), self.session.conds[*b].clone());
+                self.gen_branch(&a, target, false);
+                self.gen_branch(&b, target, false);
+            }
+            Cond::Or(a, b) if when => {
+                let (a, b) = (self.session.conds[*a].clone(), self.session.conds[*b].clone());
+                self.gen_branch(&a, target, true);
+                self.gen_branch(&b, target, true);
+            }
+            // The first side alone can only decide the opposite outcome
+            Cond::And(a, b) | Cond::Or(a, b) => {
+                let (a, b) = (self.session.conds[*a].clone(), self.session.conds[*b].clone());
+                let skip = self.new_label("cond");
+                let is_and = matches!(cond, Cond::And(..));
+                self.gen_branch(&a, &skip, !is_and);
+                self.gen_branch(&b, target, when);
+                emit!(self, "{}:\n", skip);
+            }
+        }
+    }
+
+    /// Advances the RNG and leaves a roll in 0..`modulus` in x1, using x2
+    /// and x3. The state lives in .bss and is seeded from the cycle counter
+    /// on first use.
+    fn gen_roll(&mut self, l: &str, modulus: u32) {
+        let state = format!("{}rng_state", self.target.local_prefix());
+        self.bss_slot(&state, 8, 8);
+        self.gen_addr("x3", &state);
+        emit!(self, "    ldr x1, [x3]\n    cmp x1, #0\n    b.ne {}_skip\n    mrs x1, cntvct_el0\n{}_skip:\n", l, l);
+        self.output.push_str("    ldr x2, =0x9E3779B97F4A7C15\n    mul x1, x1, x2\n    eor x1, x1, x1, lsr #33\n    str x1, [x3]\n");
+        self.output.push_str("    and x1, x1, #0x7FFFFFFF\n");
+        self.gen_mov_imm("x2", modulus as i64);
+        self.output.push_str("    udiv x3, x1, x2\n    msub x1, x3, x2, x1\n");
+    }
+
+    /// Lowers a `machine`: its state number lives in a .bss word, so it
+    /// starts at 0 (the first state) and is kept between runs. Dispatch
+    /// jumps to the current state's body; `go` stores a new number and
+    /// dispatches again, and a body that ends without one leaves the machine.
+    fn gen_machine(&mut self, states: Vec<(Symbol, Vec<StmtId>)>) {
+        let l = self.new_label("machine");
+        let state = format!("{}_state", l);
+        self.bss_slot(&state, 8, 8);
+        emit!(self, "{}:\n", l);
+        self.gen_addr("x9", &state);
+        self.output.push_str("    ldr x9, [x9]\n");
+        // The first state needs no compare: anything else falls through to it
+        for k in 1..states.len() {
+            self.gen_cmp_imm("x9", k as i64, "x10");
+            emit!(self, "    b.eq {}_{}\n", l, k);
+        }
+        let names = states.iter().map(|&(name, _)| name).collect();
+        self.machines.push((l.clone(), names, self.scope));
+        let last = states.len().saturating_sub(1);
+        for (k, (_, body)) in states.into_iter().enumerate() {
+            emit!(self, "{}_{}:\n", l, k);
+            self.gen_block(body);
+            if k != last { emit!(self, "    b {}_end\n", l); }
+        }
+        self.machines.pop();
+        emit!(self, "{}_end:\n", l);
+    }
+
+    /// Lowers `go state`: switches the innermost machine and dispatches.
+    fn gen_go(&mut self, state: Symbol) {
+        let Some((l, states, scope)) = self.machines.last().cloned() else {
+            self.errors.push(format!("`go {}` is outside any `machine`", self.name(state)));
+            return;
+        };
+        let Some(k) = states.iter().position(|&s| s == state) else {
+            self.errors.push(format!("the machine has no state `{}`", self.name(state)));
+            return;
+        };
+        if scope != self.scope {
+            self.errors.push(format!("`go {}` leaves its body: the machine is in a different function, thread or handler", self.name(state)));
+            return;
+        }
+        self.gen_mov_imm("x10", k as i64);
+        self.gen_addr("x9", &format!("{}_state", l));
+        emit!(self, "    str x10, [x9]\n    b {}\n", l);
+    }
+
+    /// Rolls once below the total weight, then walks the running totals to
+    /// find the arm the roll landed in.
+    fn gen_choose(&mut self, arms: Vec<(u32, Vec<StmtId>)>) {
+        let total: u32 = arms.iter().map(|(w, _)| w).sum();
+        let l = self.new_label("choose");
+        emit!(self, "\n    // Weighted choice out of {}\n", total);
+        self.gen_roll(&l, total);
+        let live: Vec<usize> = (0..arms.len()).filter(|&k| arms[k].0 > 0).collect();
+        let mut upto = 0;
+        for &k in &live[..live.len().saturating_sub(1)] {
+            upto += arms[k].0;
+            self.gen_cmp_imm("x1", upto as i64, "x2");
+            emit!(self, "    b.lo {}_{}\n", l, k);
+        }
+        if let Some(&last) = live.last() { emit!(self, "    b {}_{}\n", l, last); }
+        for (k, (weight, body)) in arms.into_iter().enumerate() {
+            if weight == 0 { continue; }
+            emit!(self, "{}_{}:\n", l, k);
+            self.gen_block(body);
+            emit!(self, "    b {}_end\n", l);
+        }
+        emit!(self, "{}_end:\n", l);
+    }
+
+    /// Describes the memory layout of every class with a concrete layout
+    /// (including generic instances used by the program): each field's byte
+    /// offset from the object pointer, and the object's total size. Fields
+    /// holding an object store a pointer to it.
+    pub fn layout(&self) -> String {
+        let mut classes: Vec<_> = self.class_map.iter().map(|(&name, fields)| (self.name(name), fields)).collect();
+        classes.sort_by(|a, b| a.0.cmp(b.0));
+        let mut out = String::new();
+        for (name, fields) in classes {
+            let _ = writeln!(out, "class {} ({} bytes)", name, fields.len() * 8);
+            for (i, f) in fields.iter().enumerate() {
+                match f.class {
+                    Some(c) => { let _ = writeln!(out, "  {:>6}  {}: {} (pointer)", i * 8, self.name(f.name), self.name(c)); }
+                    None => { let _ = writeln!(out, "  {:>6}  {}", i * 8, self.name(f.name)); }
+                }
+            }
+        }
+        out
+    }
+
+    /// Builds a linker script matching the sections this generator emits,
+    /// with `__hamer_heap_start` marking the first free byte after .bss.
+    pub fn linker_script(&self) -> String {
+        let mut script = format!("ENTRY({})\n\nSECTIONS\n{{\n    . = 0x400000;\n", self.entry);
+        script.push_str("    .text : { *(.text*) }\n    . = ALIGN(4096);\n");
+        script.push_str("    .data : { *(.data*) }\n    .bss : { *(.bss*) *(COMMON) }\n");
+        script.push_str("    . = ALIGN(16);\n    __hamer_heap_start = .;\n}\n");
+        script
+    }
+
+    /// Emits the 2KB-aligned vector table. Handlers occupy the "current EL
+    /// with SPx" group; every other entry spins in place.
+    fn gen_vector_table(&mut self) {
+        let kind = self.target.function_type("hamer_vectors");
+        emit!(self, "\n.balign 2048\n.global hamer_vectors\n{}hamer_vectors:\n", kind);
+        for entry in 0..16 {
+            self.output.push_str("    .balign 128\n");
+            match &self.vectors[entry % 4] {
+                Some(label) if entry / 4 == 1 => emit!(self, "    b {}\n", label),
+                _ => self.output.push_str("    b .\n"),
+            }
+        }
+        // The last entry's slot is part of the table too
+        self.output.push_str("    .balign 128\n");
+        let size = self.target.symbol_size("hamer_vectors");
+        self.output.push_str(&size);
+    }
+
+    fn gen_handler(&mut self, kind: Symbol, body: Vec<StmtId>) {
+        // Sema has rejected any other kind
+        let Some(slot) = VECTOR_KINDS.iter().position(|k| *k == self.name(kind)) else { return };
+        if self.vectors[slot].is_some() {
+            self.errors.push(format!("`handler {}` is defined twice", VECTOR_KINDS[slot]));
+            return;
+        }
+        let label = format!("{}vec_{}", self.target.local_prefix(), VECTOR_KINDS[slot]);
+        // Generate the body out of line so it lands after the program's exit.
+        let main = std::mem::take(&mut self.output);
+        self.mark_loc();
+        emit!(self, "\n{}:\n    sub sp, sp, #272\n", label);
+        for r in (0..30).step_by(2) {
+            emit!(self, "    stp x{}, x{}, [sp, #{}]\n", r, r + 1, r * 8);
+        }
+        self.output.push_str("    mrs x0, elr_el1\n    mrs x1, spsr_el1\n    stp x30, x0, [sp, #240]\n    str x1, [sp, #256]\n");
+        let scope = self.enter_scope();
+        self.gen_block(body);
+        self.scope = scope;
+        self.output.push_str("    ldr x1, [sp, #256]\n    ldp x30, x0, [sp, #240]\n    msr elr_el1, x0\n    msr spsr_el1, x1\n");
+        for r in (0..30).step_by(2).rev() {
+            emit!(self, "    ldp x{}, x{}, [sp, #{}]\n", r, r + 1, r * 8);
+        }
+        self.output.push_str("    add sp, sp, #272\n    eret\n");
+        let handler = std::mem::replace(&mut self.output, main);
+        self.deferred.push_str(&handler);
+        self.vectors[slot] = Some(label);
+    }
+
+    fn gen_stmt(&mut self, id: StmtId) {
+        trace!("{}:{}: {:?}", self.file, self.session.stmt_line(id), self.session.stmts[id]);
+        let outer_line = std::mem::replace(&mut self.line, self.session.stmt_line(id));
+        if !self.sourcemap && !self.checked {
+            self.gen_node(id);
+        } else {
+            let outer = self.loc.replace((self.file.clone(), self.line));
+            self.mark_loc();
+            self.gen_node(id);
+            self.loc = outer;
+            self.mark_loc();
+        }
+        self.line = outer_line;
+    }
+
+    /// First pass over the program: makes every top-level class and function,
+    /// including those of imported files, known before any code is generated,
+    /// so they can be used above their definitions.
+    fn declare(&mut self, ast: &[StmtId]) {
+        self.declare_items(ast);
+        let classes: Vec<Symbol> = self.class_map.values().flatten().filter_map(|f| f.class).collect();
+        for c in classes { self.ensure_class(c); }
+        // One function may return what another does, so go round until nothing changes
+        let funcs: Vec<(Symbol, ItemId)> = self.func_items.iter().map(|(&n, &id)| (n, id)).collect();
+        for _ in 0..=funcs.len() {
+            let found: Vec<(Symbol, Option<Symbol>)> = funcs.iter().filter_map(|&(name, id)| {
+                let Item::Func { params, body, .. } = &self.session.items[id] else { return None };
+                let class = return_class(&self.session, params, body, self);
+                (class.is_some() && self.funcs[&name].ret_class != class).then_some((name, class))
+            }).collect();
+            if found.is_empty() { break; }
+            for (name, class) in found {
+                if let Some(info) = self.funcs.get_mut(&name) { info.ret_class = class; }
+            }
+        }
+    }
+
+    fn declare_items(&mut self, body: &[StmtId]) {
+        for &stmt in body {
+            let Stmt::Item(id) = self.session.stmts[stmt] else { continue };
+            match self.session.items[id].clone() {
+                Item::Class { name, params, fields } => self.declare_class(name, params, fields),
+                Item::Func { name, params, .. } => {
+                    // A second definition is reported where it is generated
+                    if self.func_items.contains_key(&name) { continue; }
+                    self.func_items.insert(name, id);
+                    self.funcs.insert(name, FuncInfo { params, ret_class: None });
+                }
+                Item::Import { body, .. } | Item::Emit { body, .. } => self.declare_items(&body),
+            }
+        }
+    }
+
+    fn declare_class(&mut self, name: Symbol, params: Vec<Symbol>, fields: Vec<Field>) {
+        if params.is_empty() {
+            self.class_map.insert(name, fields);
+        } else {
+            self.generics.insert(name, (params, fields));
+        }
+    }
+
+    fn gen_item(&mut self, id: ItemId) {
+        match self.session.items[id].clone() {
+            Item::Class { name, params, fields } => {
+                // Top-level classes were declared in the first pass already
+                if !params.is_empty() || self.class_map.contains_key(&name) {
+                    self.declare_class(name, params, fields);
+                    return;
+                }
+                for f in &fields {
+                    if let Some(c) = f.class { self.ensure_class(c); }
+                }
+                self.class_map.insert(name, fields);
+            }
+            Item::Func { name, params, body, public } => {
+                // Function labels are named after the function, so a second body would clash
+                match self.func_items.get(&name) {
+                    Some(&first) if first == id => {}
+                    None if !self.funcs.contains_key(&name) => {
+                        self.funcs.insert(name, FuncInfo { params: params.clone(), ret_class: None });
+                    }
+                    _ => {
+                        self.errors.push(format!("function `{}` is defined twice", self.name(name)));
+                        return;
+                    }
+                }
+                self.gen_func(name, params, body, public);
+            }
+            Item::Import { file, body, .. } => {
+                if body.is_empty() { return; }
+                let outer = std::mem::replace(&mut self.file, file);
+                self.include_depth += 1;
+                self.gen_block(body);
+                self.include_depth -= 1;
+                self.file = outer;
+            }
+            Item::Emit { file, body, .. } => {
+                let outer = std::mem::replace(&mut self.file, file);
+                self.gen_block(body);
+                self.file = outer;
+            }
+        }
+    }
+
+    fn gen_node(&mut self, id: StmtId) {
+        // Bodies are ids, so this copies only the node itself.
+        match self.session.stmts[id].clone() {
+            Stmt::Item(item) => self.gen_item(item),
+            Stmt::PythonBlock { script, line } => {
+                let at = format!("{}:{}", self.file, line);
+                if self.include_depth > 0 && !self.allow_run {
+                    self.errors.push(PythonError::refused(&at, &script));
+                    return;
+                }
+                let res = match run_python(&script, self.exec_timeout) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        let msg = e.report(&at, &script);
+                        match e {
+                            PythonError::Missing(_) if self.allow_missing_python => {
+                                self.warnings.push(format!("{} (skipped, --allow-missing-python)", msg));
+                                self.output.push_str("\n    // Python Output: skipped, python3 unavailable\n");
+                            }
+                            PythonError::Missing(_) => self.errors.push(format!("{}\n  hint: install python3 or pass --allow-missing-python", msg)),
+                            _ => self.errors.push(msg),
+                        }
+                        return;
+                    }
+                };
+                self.output.push('\n');
+                for line in res.trim_end().lines() {
+                    emit!(self, "    // Python Output: {}\n", line);
+                }
+            }
+            Stmt::IntelBlock(code) => {
+                self.output.push_str("\n    .intel_syntax noprefix\n");
+                self.gen_raw_lines(&code);
+                self.output.push_str("    .att_syntax\n");
+            }
+            Stmt::AsmBlock(code) => {
+                self.check_asm_registers(&code, id);
+                self.gen_raw_lines(&code);
+            }
+            Stmt::ProbIf { chance, body, else_body } => {
+                let l = self.new_label("prob");
+                let miss = Self::miss_label(&l, &else_body);
+                let shown = match &chance {
+                    Operand::Num(n) => n.to_string(),
+                    Operand::Path(p) => self.path_name(p),
+                };
+                emit!(self, "\n    // Chaos Roll {}%\n", shown);
+                self.gen_roll(&l, 100);
+                match &chance {
+                    Operand::Num(n) => {
+                        self.gen_cmp_imm("x1", *n as i64, "x2");
+                        emit!(self, "    b.hs {}\n", miss);
+                    }
+                    Operand::Path(p) => {
+                        // Signed, so a negative chance never runs and one over 100 always does
+                        self.load_path(p, "x2");
+                        emit!(self, "    cmp x1, x2\n    b.ge {}\n", miss);
+                    }
+                }
+                self.gen_if_branches(&l, body, else_body);
+            }
+            Stmt::IfStmt { cond, body, else_body } => {
+                let l = self.new_label("if");
+                let miss = Self::miss_label(&l, &else_body);
+                self.gen_branch(&cond, &miss, false);
+                self.gen_if_branches(&l, body, else_body);
+            }
+            Stmt::IfSame { lhs, rhs, body, else_body } => {
+                let l = self.new_label("if");
+                let miss = Self::miss_label(&l, &else_body);
+                let class = self.path_class(&lhs);
+                let count = class.and_then(|c| self.class_map.get(&c)).map_or(0, |f| f.len());
+                if class.is_none() || class != self.path_class(&rhs) {
+                    // Objects of different classes are never structurally equal
+                    emit!(self, "    b {}\n", miss);
+                } else {
+                    self.load_path(&lhs, "x3");
+                    self.load_path(&rhs, "x4");
+                    for i in 0..count {
+                        emit!(self, "    ldr x1, [x3, #{}]\n    ldr x2, [x4, #{}]\n    cmp x1, x2\n    b.ne {}\n", i * 8, i * 8, miss);
+                    }
+                }
+                self.gen_if_branches(&l, body, else_body);
+            }
+            Stmt::WhileStmt { cond, body } => {
+                let l = self.new_label("while");
+                emit!(self, "{}:\n", l);
+                self.gen_branch(&cond, &format!("{}_end", l), false);
+                self.gen_block(body);
+                emit!(self, "    b {}\n{}_end:\n", l, l);
+            }
+            Stmt::Every { period, body } => self.gen_every(period, body),
+            Stmt::After { delay, body } => self.gen_after(delay, body),
+            Stmt::LocalAssign { name, value } => {
+                if self.is_text(&value) {
+                    self.assign_text(&[name], &value);
+                    return;
+                }
+                if self.is_float(&value) {
+                    self.assign_float(&[name], &value, true);
+                    return;
+                }
+                self.builtins.remove(&name);
+                self.arrays.remove(&name);
+                // The new variable isn't in scope in its own initializer
+                let loc = self.symbols.get(&name).cloned().unwrap_or_else(|| self.alloc_var(name));
+                let reg = Self::var_out(&loc, "x9");
+                let shown = self.name(name).to_string();
+                self.gen_expr(&value, &reg, 0, &shown);
+                self.store_var(name, &loc, &reg);
+                self.symbols.insert(name, loc);
+            }
+            Stmt::FieldAssign { path, value } => self.assign_field(&path, &value),
+            Stmt::RefAssign { path, src } => {
+                // Text is copied, so changing one copy leaves the other alone
+                if self.is_text_path(&src) {
+                    self.assign_text(&path, &Expr::Path(src));
+                    return;
+                }
+                if self.is_float_path(&src) || self.is_float_path(&path) {
+                    self.assign_float(&path, &Expr::Path(src), false);
+                    return;
+                }
+                self.load_path(&src, "x1");
+                if let [var] = path[..] && let Some(loc) = self.symbols.get(&var).cloned() {
+                    self.store_var(var, &loc, "x1");
+                    // `e = wave[2]` points into the array just as a loop variable does
+                    let array = match src[..] {
+                        [v] => self.elements.get(&v).copied(),
+                        [array, index] if self.is_index(index) && self.arrays.contains_key(&array) => Some(array),
+                        _ => None,
+                    };
+                    match array {
+                        Some(a) => { self.elements.insert(var, a); }
+                        None => { self.elements.remove(&var); }
+                    }
+                    return;
+                }
+                let (reg, offset) = self.get_path_info(&path);
+                if self.in_memory(&path) {
+                    emit!(self, "    str x1, [{}, #{}]\n", reg, offset);
+                } else {
+                    emit!(self, "    mov {}, x1\n", reg);
+                }
+            }
+            Stmt::PrintVar(name) if self.builtins.get(&name) == Some(&Builtin::Text) => {
+                let loc = self.var_loc(name);
+                let reg = self.var_in(name, &loc, "x1");
+                emit!(self, "    ldp x1, x2, [{}]\n    mov x0, #{}\n    mov x8, #64\n    svc #0\n", reg, self.out_fd);
+                self.gen_write_str("\n");
+            }
+            Stmt::PrintVar(name) if self.builtins.get(&name) == Some(&Builtin::Float) => {
+                let loc = self.var_loc(name);
+                self.copy_var(name, &loc, "x0");
+                self.gen_print_call("__hamer_print_float");
+                self.gen_write_str("\n");
+            }
+            Stmt::PrintVar(name) => {
+                if let Some(loc) = self.symbols.get(&name).cloned() {
+                    self.copy_var(name, &loc, "x0");
+                    self.output.push_str("    mov x1, #10\n    mov x2, #0\n    mov x3, #32\n    mov x4, #1\n");
+                    self.gen_print_call("__hamer_print_num");
+                    self.gen_write_str("\n");
+                }
+            }
+            Stmt::PrintString(s) => self.gen_write_str(&format!("{}\n", s)),
+            Stmt::PrintList(items) => self.gen_print_list(items, "\n"),
+            Stmt::Cls => self.gen_write_str("\x1b[2J\x1b[H"),
+            Stmt::Cursor { x, y } => {
+                let items = vec![PrintItem::Str("\x1b[".into()), PrintItem::Value(y), PrintItem::Str(";".into()), PrintItem::Value(x)];
+                self.gen_print_list(items, "H");
+            }
+            Stmt::Color(Some(n)) => self.gen_print_list(vec![PrintItem::Str("\x1b[38;5;".into()), PrintItem::Value(n)], "m"),
+            Stmt::Color(None) => self.gen_write_str("\x1b[0m"),
+            Stmt::Eprint(print) => {
+                let outer = std::mem::replace(&mut self.out_fd, 2);
+                self.gen_node(print);
+                self.out_fd = outer;
+            }
+            Stmt::PrintFormat { fmt, args } => self.gen_print_format(fmt, args),
+            Stmt::PrintBits { bits, value } => {
+                self.load_operand(&value, "x0");
+                emit!(self, "    mov x1, #{}\n", bits);
+                self.gen_print_call("__hamer_print_bits");
+                self.gen_write_str("\n");
+            }
+            Stmt::Handler { kind, body } => self.gen_handler(kind, body),
+            Stmt::Spawn(body) => self.gen_spawn(body),
+            Stmt::Join => self.gen_join(),
+            Stmt::OnInterrupt(body) => self.gen_on_interrupt(body),
+            Stmt::Run { argv, dest } => self.gen_run(argv, dest),
+            Stmt::Try { body, err, rescue } => self.gen_try(body, err, rescue),
+            Stmt::AtomicAdd { path, value } => {
+                self.load_operand(&value, "x2");
+                if !self.field_address(&path, "atomic_add") { return; }
+                let l = self.new_label("atomic");
+                emit!(self, "{}:\n    ldaxr x1, [x10]\n    add x1, x1, x2\n    stlxr w3, x1, [x10]\n    cbnz w3, {}\n", l, l);
+            }
+            Stmt::Lock(path) => {
+                if !self.field_address(&path, "lock") { return; }
+                let l = self.new_label("lock");
+                emit!(self, "    mov x2, #1\n{}:\n    ldaxr x1, [x10]\n    cbnz x1, {}\n    stxr w3, x2, [x10]\n    cbnz w3, {}\n", l, l, l);
+            }
+            Stmt::Unlock(path) => {
+                if !self.field_address(&path, "unlock") { return; }
+                self.output.push_str("    stlr xzr, [x10]\n");
+            }
+            Stmt::Call { name, args, dest, from } => {
+                let tail = self.tail_calls.contains(&id);
+                self.gen_call(name, args, dest, from, tail);
+            }
+            Stmt::Return(value) => self.gen_return(value),
+            Stmt::HeapAlloc { var_name, class_name, args } => {
+                let reg = self.alloc_object(var_name, class_name);
+                self.init_object(var_name, class_name, &reg, args);
+            }
+            Stmt::ArrayAlloc { var_name, class_name, len } => self.alloc_array(var_name, class_name, len),
+            Stmt::NumArrayAlloc { var_name, len } => {
+                self.gen_array(var_name, len, 8);
+                self.obj_types.remove(&var_name);
+                self.builtins.remove(&var_name);
+                self.arrays.insert(var_name, len);
+            }
+            Stmt::ForEach { var, array, body } => self.gen_for_each(var, array, body),
+            Stmt::Sort { array, field } => {
+                if !self.load_array_key("sort", array, field) { return; }
+                self.call_runtime("__hamer_sort");
+            }
+            Stmt::Find { array, value, field, dest } => {
+                if !self.load_array_key("find", array, field) { return; }
+                self.load_operand(&value, "x4");
+                self.call_runtime("__hamer_find");
+                self.set_var(dest, "x0");
+                self.obj_types.remove(&dest);
+            }
+            Stmt::MapAlloc { var_name } => {
+                let loc = self.alloc_var(var_name);
+                let reg = Self::var_out(&loc, "x9");
+                let size = runtime::MAP_HEADER + runtime::MAP_SLOTS * 16;
+                self.reserve_heap(size);
+                emit!(self, "    mov {r}, x28\n    add x28, x28, #{}\n    add x10, {r}, #{}\n    mov x11, #{}\n    stp x10, x11, [{r}]\n",
+                    size, runtime::MAP_HEADER, runtime::MAP_SLOTS - 1, r = reg);
+                self.store_var(var_name, &loc, &reg);
+                self.symbols.insert(var_name, loc);
+                self.obj_types.remove(&var_name);
+                self.builtins.insert(var_name, Builtin::Map);
+            }
+            Stmt::TextAlloc { var_name } => { self.alloc_text(var_name, runtime::TEXT_INITIAL_CAP); }
+            Stmt::Input { var_name } => {
+                let loc = self.alloc_text(var_name, runtime::INPUT_CAP);
+                let reg = self.var_in(var_name, &loc, "x9");
+                emit!(self, "    ldr x0, [{}]\n    mov x1, #{}\n", reg, runtime::INPUT_CAP);
+                self.call_runtime("__hamer_read_line");
+                // The routine is free to clobber the scratch registers
+                let reg = self.var_in(var_name, &loc, "x9");
+                emit!(self, "    str x0, [{}, #8]\n", reg);
+            }
+            Stmt::Match { subject, arms, default } => self.gen_match(subject, arms, default),
+            Stmt::Choose { arms } => self.gen_choose(arms),
+            Stmt::Machine { states, .. } => self.gen_machine(states),
+            Stmt::Go(state) => self.gen_go(state),
+            Stmt::Delete(var) => self.gen_delete(var),
+            Stmt::Label(name) => self.gen_label(name),
+            Stmt::Assert(cond) => {
+                if !self.checked { return; }
+                let l = self.new_label("assert");
+                self.gen_branch(&cond, &l, true);
+                self.gen_trap("assertion failed", TRAP_STATUS, false);
+                emit!(self, "{}:\n", l);
+            }
+            Stmt::Goto(name) => {
+                emit!(self, "    b {}\n", self.user_label(self.scope, name));
+                self.gotos.push((self.scope, name));
+            }
+            Stmt::TextAppend { text, src } => {
+                if text.len() != 1 || self.builtins.get(&text[0]) != Some(&Builtin::Text) {
+                    self.errors.push(format!("`{}` is not text", self.path_name(&text)));
+                    return;
+                }
+                match src {
+                    TextSrc::Lit(s) => {
+                        let label = self.data_str(&s);
+                        self.gen_addr("x1", &label);
+                        self.gen_mov_imm("x2", s.len() as i64);
+                    }
+                    TextSrc::Var(v) => {
+                        if self.builtins.get(&v) != Some(&Builtin::Text) {
+                            self.errors.push(format!("`{}` is not text", self.name(v)));
+                            return;
+                        }
+                        let loc = self.var_loc(v);
+                        let reg = self.var_in(v, &loc, "x1");
+                        emit!(self, "    ldp x1, x2, [{}]\n", reg);
+                    }
+                }
+                self.load_path(&text, "x0");
+                self.call_runtime("__hamer_text_append");
+            }
+            Stmt::MapPut { map, key, value } => {
+                if !self.load_map(&map) { return; }
+                self.load_operand(&value, "x2");
+                let key = self.map_key(&key);
+                self.gen_addr("x1", &key);
+                self.call_runtime("__hamer_map_put");
+            }
+            Stmt::MapGet { map, key, dest } => {
+                if !self.load_map(&map) { return; }
+                let key = self.map_key(&key);
+                self.gen_addr("x1", &key);
+                self.call_runtime("__hamer_map_get");
+                self.set_var(dest, "x0");
+                self.obj_types.remove(&dest);
+            }
+            Stmt::CopyAlloc { var_name, src } => {
+                let Some(class_name) = self.obj_types.get(&src).copied() else { return };
+                // Allocating uses x10, so a source on the stack is loaded after
+                let reg = self.alloc_object(var_name, class_name);
+                let src_reg = match self.symbols.get(&src).cloned() {
+                    Some(loc) => self.var_in(src, &loc, "x10"),
+                    None => "x0".to_string(),
+                };
+                let count = self.class_map.get(&class_name).map_or(0, |f| f.len());
+                for i in 0..count {
+                    emit!(self, "    ldr x1, [{}, #{}]\n    str x1, [{}, #{}]\n", src_reg, i * 8, reg, i * 8);
+                }
+            }
+        }
+    }
+}
+
+/// True if a single `mov` encodes `value`: one 16-bit chunk set (movz),
+/// or all but one chunk all ones (movn).
+fn mov_encodable(value: i64) -> bool {
+    let one_chunk = |v: u64| (0..4).filter(|i| v >> (i * 16) & 0xffff != 0).count() <= 1;
+    one_chunk(value as u64) || one_chunk(!value as u64)
+}
+
+/// Moves sp by `bytes` with `op` (`add` or `sub`), going through x9 for
+/// amounts too big for an immediate.
+fn adjust_sp(op: &str, bytes: usize) -> String {
+    if bytes < 4096 {
+        format!("    {} sp, sp, #{}\n", op, bytes)
+    } else {
+        format!("    ldr x9, ={}\n    {} sp, sp, x9\n", bytes, op)
+    }
+}
+
+/// Pops the frame record and variable slots `frame_prologue` pushed.
+fn frame_epilogue(frame: usize) -> String {
+    let mut out = "    ldp x29, x30, [sp], #16\n".to_string();
+    if frame > 0 { out.push_str(&adjust_sp("add", frame)); }
+    out
+}
+
+/// Splits `Hero,Pair<A,B>` into its top-level generic arguments.
+pub(crate) fn split_generic_args(args: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => { out.push(args[start..i].to_string()); start = i + 1; }
+            _ => {}
+        }
+    }
+    out.push(args[start..].to_string());
+    out
+}
+
+/// The base and generic arguments of `class`: `Box<Hero>` is `Box` with
+/// `[Hero]`, and a plain class is itself with none. Every level of a
+/// generic name is interned where the name is made, so nothing new is.
+pub(crate) fn generic_parts(interner: &Interner, class: Symbol) -> (Symbol, Vec<Symbol>) {
+    let name = interner.resolve(class);
+    let Some((base, rest)) = name.split_once('<') else { return (class, Vec::new()) };
+    let part = |s: &str| interner.lookup(s).expect("each level of a generic name is interned");
+    let args = split_generic_args(rest.strip_suffix('>').unwrap_or(rest)).iter().map(|a| part(a)).collect();
+    (part(base), args)
+}
+
+/// Replaces generic parameters in a (possibly generic) class name,
+/// spelling out the result.
+fn substitute(interner: &Interner, class: Symbol, params: &[Symbol], args: &[Symbol]) -> String {
+    if let Some(i) = params.iter().position(|&p| p == class) {
+        return args.get(i).map_or(String::new(), |&a| interner.resolve(a).to_string());
+    }
+    let (base, inner) = generic_parts(interner, class);
+    if inner.is_empty() { return interner.resolve(class).to_string(); }
+    let inner: Vec<String> = inner.iter().map(|&c| substitute(interner, c, params, args)).collect();
+    format!("{}<{}>", interner.resolve(base), inner.join(","))
+}
+
+impl Classes for Generator {
+    fn field_class(&self, class: Symbol, field: Symbol) -> Option<Symbol> {
+        if let Some(fields) = self.class_map.get(&class) {
+            return fields.iter().find(|f| f.name == field)?.class;
+        }
+        // A generic instance nothing has stamped out yet; an argument list
+        // no program text spells out can't have been stamped out either
+        let interner = &self.session.interner;
+        let (base, args) = generic_parts(interner, class);
+        let (params, template) = self.generics.get(&base)?;
+        let c = template.iter().find(|f| f.name == field)?.class?;
+        interner.lookup(&substitute(interner, c, params, &args))
+    }
+
+    fn returns(&self, name: Symbol) -> Option<Symbol> {
+        self.funcs.get(&name)?.ret_class
+    }
+}
+
+/// What `return_class` needs to know about the rest of the program.
+pub(crate) trait Classes {
+    /// The class of object field `field` of `class` holds, if any.
+    fn field_class(&self, class: Symbol, field: Symbol) -> Option<Symbol>;
+    /// The class of object function `name` returns, if known.
+    fn returns(&self, name: Symbol) -> Option<Symbol>;
+}
+
+/// The class of object a function returns, as far as its body tells
+/// without generating it: that of the first `return` of a variable (or a
+/// field of one) holding a parameter, a `new`, a copy, an array element
+/// or a call result whose class is known.
+pub(crate) fn return_class(session: &Session, params: &[Field], body: &[StmtId], classes: &dyn Classes) -> Option<Symbol> {
+    let mut vars: HashMap<Symbol, Symbol> = params.iter().filter_map(|p| Some((p.name, p.class?))).collect();
+    find_return(session, body, &mut vars, classes)
+}
+
+fn find_return(session: &Session, body: &[StmtId], vars: &mut HashMap<Symbol, Symbol>, classes: &dyn Classes) -> Option<Symbol> {
+    let path_class = |vars: &HashMap<Symbol, Symbol>, path: &[Symbol]| {
+        path[1..].iter().filter(|&&s| !session.interner.resolve(s).starts_with('['))
+            .try_fold(*vars.get(&path[0])?, |c, &f| classes.field_class(c, f))
+    };
+    for &id in body {
+        let stmt = &session.stmts[id];
+        let bound = match stmt {
+            Stmt::HeapAlloc { var_name, class_name, .. } | Stmt::ArrayAlloc { var_name, class_name, .. } => Some((var_name, Some(*class_name))),
+            Stmt::CopyAlloc { var_name, src } => Some((var_name, vars.get(src).copied())),
+            Stmt::ForEach { var, array, .. } => Some((var, vars.get(array).copied())),
+            Stmt::Call { name, dest: Some(dest), .. } => Some((dest, classes.returns(*name))),
+            Stmt::LocalAssign { name, .. } => Some((name, None)),
+            Stmt::Return(Some(Operand::Path(p))) => {
+                if let Some(c) = path_class(vars, p) { return Some(c); }
+                None
+            }
+            _ => None,
+        };
+        match bound {
+            Some((var, Some(c))) => { vars.insert(*var, c); }
+            Some((var, None)) => { vars.remove(var); }
+            None => {}
+        }
+        for b in stmt.bodies() {
+            if let Some(c) = find_return(session, b, vars, classes) { return Some(c); }
+        }
+    }
+    None
+}
+
+/// Why a compile-time Python script gave no output.
+pub(crate) enum PythonError {
+    /// python3 couldn't be started.
+    Missing(io::Error),
+    /// Killed once the timeout passed, with what it printed until then.
+    TimedOut(Duration, String),
+    /// Exited with a failure; its stderr went to ours.
+    Failed(ExitStatus),
+}
+
+impl PythonError {
+    /// The error for a script from an included file, which only runs with
+    /// `--allow-run`; `at` is the block's `file:line`.
+    pub(crate) fn refused(at: &str, script: &str) -> String {
+        let first = script.lines().next().unwrap_or("");
+        format!("{}: @python block wants to run `{}` at compile time\n  hint: code from included files only runs with --allow-run", at, first)
+    }
+
+    /// The diagnostic for the block at `at` (`file:line`) running `script`.
+    pub(crate) fn report(&self, at: &str, script: &str) -> String {
+        let first = script.lines().next().unwrap_or("");
+        match self {
+            PythonError::Missing(e) => format!("{}: cannot run python3 for @python block `{}`: {}", at, first, e),
+            PythonError::TimedOut(timeout, partial) => format!("{}: @python block `{}` timed out after {:?} (exec_timeout in hamer.toml)\n  partial output:\n{}", at, first, timeout, partial.trim_end()),
+            PythonError::Failed(status) => format!("{}: @python block failed ({}):\n{}", at, status, script),
+        }
+    }
+}
+
+/// Runs `script` with python3 and returns what it printed. A failing
+/// script's stderr is passed through to ours.
+pub(crate) fn run_python(script: &str, timeout: Duration) -> Result<String, PythonError> {
+    let mut cmd = Command::new("python3");
+    cmd.arg("-c").arg(script);
+    let (out, timed_out) = run_with_timeout(cmd, timeout).map_err(PythonError::Missing)?;
+    if timed_out {
+        return Err(PythonError::TimedOut(timeout, String::from_utf8_lossy(&out.stdout).into_owned()));
+    }
+    if !out.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&out.stderr));
+        return Err(PythonError::Failed(out.status));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Runs a compile-time subprocess, killing it once `timeout` elapses.
+/// Returns whatever output was captured and whether the timeout hit.
+fn run_with_timeout(mut cmd: Command, timeout: Duration) -> io::Result<(Output, bool)> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    // Drain both pipes on threads so a chatty child can't block on a full pipe.
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let mut stderr = child.stderr.take().expect("piped stderr");
+    let out_reader = thread::spawn(move || { let mut b = Vec::new(); let _ = stdout.read_to_end(&mut b); b });
+    let err_reader = thread::spawn(move || { let mut b = Vec::new(); let _ = stderr.read_to_end(&mut b); b });
+    let deadline = Instant::now() + timeout;
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? { break status; }
+        if Instant::now() >= deadline {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let stdout = out_reader.join().unwrap_or_default();
+    let stderr = err_reader.join().unwrap_or_default();
+    Ok((Output { status, stdout, stderr }, timed_out))
+}
+
+/// Quotes `s` as a JSON string.
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `name` as the assemblers accept it in a label or operand. Names from
+/// non-English identifiers are quoted; the symbol itself keeps the UTF-8.
+fn asm_symbol(name: String) -> String {
+    if name.is_ascii() { name } else { format!("\"{}\"", name) }
+}
+
+/// How `print` writes a float: up to six decimals, without trailing zeros
+/// but with at least one, as `__hamer_print_float` does.
+fn float_text(n: f64) -> String {
+    let mut s = format!("{:.6}", n);
+    s.truncate(s.trim_end_matches('0').len());
+    if s.ends_with('.') { s.push('0'); }
+    s
+}
+
+/// Escapes `s` for the inside of an assembler string directive, so it
+/// assembles to exactly the bytes of `s`. Control characters become octal
+/// escapes; UTF-8 passes through, both assemblers copy it byte for byte.
+fn asm_escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 || c == '\x7f' => { let _ = write!(out, "\\{:03o}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out
+}
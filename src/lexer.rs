@@ -1,105 +1,372 @@
+use crate::session::{self, kw, Interner, Symbol};
+
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Token {
-    Class, Is, Done, Local, Print, Get, At, Assign, Dot, New,
-    If, Then, While, Do, Greater, Less, Equal,
+    Class, Is, Done, Local, Print, Eprint, Get, At, Assign, Dot, New, Handler, Copy, Same,
+    Func, Call, Return, Spawn, Join, AtomicAdd, Lock, Unlock,
+    OnInterrupt, Run, Try, Rescue, Match, Choose, Label, Goto, Assert,
+    If, Then, While, For, Do, Greater, Less, Equal,
     Plus, Minus, Star, Slash, Comma, Rest,
-    Quest, Percent, LeftBracket, RightBracket,
-    Identifier(String), Number(f64), StringLit(String), EOF,
+    Quest, Percent, LeftBracket, RightBracket, LeftParen, RightParen, Colon, Semicolon, Arrow,
+    Identifier(Symbol), Number(f64), Float(f64), StringLit(String), RawText(String), EOF,
 }
 
-pub struct Lexer {
-    input: Vec<char>,
+/// Where a token starts: 1-based line and column, counting characters
+/// rather than bytes so columns match what an editor shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Span { pub line: usize, pub col: usize }
+
+impl Span {
+    /// `file:line:col: message`, followed by the source line and a caret
+    /// under the column, for printing after `error: ` or `warning: `.
+    pub fn show(&self, file: &str, source: &str, message: &str) -> String {
+        let mut out = format!("{}:{}:{}: {}", file, self.line, self.col, message);
+        if let Some(text) = source.lines().nth(self.line.wrapping_sub(1)) {
+            let gutter = self.line.to_string().len();
+            // Tabs keep their width so the caret lines up under them
+            let pad: String = text.chars().take(self.col.saturating_sub(1)).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+            out.push_str(&format!("\n {} | {}\n {} | {}^", self.line, text.trim_end(), " ".repeat(gutter), pad));
+        }
+        out
+    }
+}
+
+/// Progress through an `@kind is ... done` header. The block body is
+/// captured verbatim as `RawText` instead of being tokenized.
+#[derive(PartialEq)]
+enum RawState { Idle, SawAt, SawKind, Body }
+
+pub struct Lexer<'s> {
+    input: &'s str,
+    /// Byte offset of the next character.
     pos: usize,
+    line: usize,
+    /// Byte offset where the current line starts, for columns.
+    line_start: usize,
+    raw: RawState,
+    /// Where the most recently returned token starts.
+    pub token_span: Span,
+    /// Keywords must be spelled exactly; when off, `Print` and `DONE` are
+    /// keywords too. Identifiers are case-sensitive either way.
+    pub strict_case: bool,
+    /// Malformed literals and stray characters, as (span, message). Lexing
+    /// carries on past them.
+    pub errors: Vec<(Span, String)>,
+    interner: &'s mut Interner,
 }
 
-impl Lexer {
-    pub fn new(input: String) -> Self { 
-        Self { input: input.chars().collect(), pos: 0 } 
+impl<'s> Lexer<'s> {
+    pub fn new(input: &'s str, interner: &'s mut Interner) -> Self { 
+        Self { input, pos: 0, line: 1, line_start: 0, raw: RawState::Idle, token_span: Span { line: 1, col: 1 }, strict_case: true, errors: Vec::new(), interner } 
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// Moves past the current character, whatever its UTF-8 width,
+    /// keeping count of lines.
+    fn bump(&mut self) {
+        let Some(c) = self.peek_char() else { return };
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = self.pos;
+        }
+    }
+
+    /// Where the character at the cursor is.
+    fn span(&self) -> Span {
+        Span { line: self.line, col: self.input[self.line_start..self.pos].chars().count() + 1 }
+    }
+
+    /// Lexes the whole input, returning the tokens and where each one
+    /// starts. The last token is always a single EOF sentinel.
+    pub fn tokenize(&mut self) -> (Vec<Token>, Vec<Span>) {
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        loop {
+            let token = self.next_token();
+            let end = token == Token::EOF;
+            let Span { line, col } = self.token_span;
+            match &token {
+                Token::Identifier(s) => trace!("{}:{}: identifier `{}`", line, col, self.interner.resolve(*s)),
+                _ => trace!("{}:{}: {:?}", line, col, token),
+            }
+            tokens.push(token);
+            spans.push(self.token_span);
+            if end { break; }
+        }
+        debug!("{} tokens", tokens.len());
+        (tokens, spans)
     }
 
     pub fn next_token(&mut self) -> Token {
+        match self.raw {
+            RawState::SawKind => {
+                // `is` is optional; either way the body starts right after
+                self.skip_whitespace();
+                self.token_span = self.span();
+                if self.at_word("is") {
+                    self.pos += 2;
+                    self.raw = RawState::Body;
+                    return Token::Is;
+                }
+                // `@python emit is`: the header goes on to its `is`
+                if self.at_word("emit") {
+                    self.pos += 4;
+                    return Token::Identifier(kw::EMIT);
+                }
+                self.raw = RawState::Idle;
+                return self.lex_raw_block();
+            }
+            RawState::Body => {
+                self.raw = RawState::Idle;
+                self.token_span = self.span();
+                return self.lex_raw_block();
+            }
+            _ => {}
+        }
+        let token = self.lex_token();
+        self.raw = match (&self.raw, &token) {
+            (_, Token::At) => RawState::SawAt,
+            (RawState::SawAt, Token::Identifier(_)) => RawState::SawKind,
+            _ => RawState::Idle,
+        };
+        token
+    }
+
+    fn lex_token(&mut self) -> Token {
         loop {
             self.skip_whitespace();
-            if self.pos >= self.input.len() { return Token::EOF; }
-
-            let ch = self.input[self.pos];
+            self.token_span = self.span();
+            let Some(ch) = self.peek_char() else { return Token::EOF };
             match ch {
+                '#' => self.skip_comment(),
                 '?' => { self.pos += 1; return Token::Quest },
                 '%' => { self.pos += 1; return Token::Percent },
                 '@' => { self.pos += 1; return Token::At },
                 ',' => { self.pos += 1; return Token::Comma },
+                ':' => { self.pos += 1; return Token::Colon },
+                ';' => { self.pos += 1; return Token::Semicolon },
                 '.' => { self.pos += 1; return Token::Dot },
                 '[' => { self.pos += 1; return Token::LeftBracket },
                 ']' => { self.pos += 1; return Token::RightBracket },
+                '(' => { self.pos += 1; return Token::LeftParen },
+                ')' => { self.pos += 1; return Token::RightParen },
                 '>' => { self.pos += 1; return Token::Greater },
                 '<' => { self.pos += 1; return Token::Less },
                 '+' => { self.pos += 1; return Token::Plus },
-                '-' => { self.pos += 1; return Token::Minus },
+                '-' => {
+                    self.pos += 1;
+                    if self.peek_char() == Some('>') {
+                        self.pos += 1; return Token::Arrow;
+                    } else { return Token::Minus; }
+                },
                 '*' => { self.pos += 1; return Token::Star },
                 '/' => { self.pos += 1; return Token::Slash },
                 '=' => {
                     self.pos += 1;
-                    if self.pos < self.input.len() && self.input[self.pos] == '=' {
+                    if self.peek_char() == Some('=') {
                         self.pos += 1; return Token::Equal;
                     } else { return Token::Assign; }
                 },
                 '"' => return self.lex_string(),
                 '0'..='9' => return self.lex_number(),
-                'a'..='z' | 'A'..='Z' | '_' => return self.lex_identifier(),
-                _ => { 
-                    // Skip unknown characters safely instead of recursing
-                    self.pos += 1; 
+                c if is_ident_start(c) => return self.lex_identifier(),
+                c => {
+                    self.errors.push((self.token_span, format!("unexpected character `{}`", c)));
+                    self.bump();
                 }
             }
         }
     }
 
+    /// Skips a comment: `#` to the end of the line, or `#[ ... ]#`, which
+    /// can span lines and nest. Lines are still counted inside it.
+    fn skip_comment(&mut self) {
+        if !self.input[self.pos..].starts_with("#[") {
+            while self.peek_char().is_some_and(|c| c != '\n') { self.bump(); }
+            return;
+        }
+        let mut depth = 0;
+        while self.peek_char().is_some() {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("#[") {
+                depth += 1;
+                self.pos += 2;
+            } else if rest.starts_with("]#") {
+                depth -= 1;
+                self.pos += 2;
+                if depth == 0 { return; }
+            } else {
+                self.bump();
+            }
+        }
+        self.errors.push((self.token_span, "block comment is never closed with `]#`".into()));
+    }
+
+    /// A letter (in any script) or `_`, then letters, digits, combining
+    /// marks and `_`, as `is_ident_start` and `is_ident_continue` define them.
     fn lex_identifier(&mut self) -> Token {
-        let mut ident = String::new();
-        while self.pos < self.input.len() && (self.input[self.pos].is_alphanumeric() || self.input[self.pos] == '_') {
-            ident.push(self.input[self.pos]); 
-            self.pos += 1;
+        let start = self.pos;
+        while self.peek_char().is_some_and(is_ident_continue) {
+            self.bump();
         }
-        match ident.as_str() {
+        let ident = &self.input[start..self.pos];
+        let folded;
+        let word = if self.strict_case {
+            ident
+        } else {
+            folded = ident.to_lowercase();
+            // Lowercase `get` is the map lookup; any other spelling imports
+            if folded == "get" && ident != "get" { return Token::Get; }
+            &folded
+        };
+        match word {
             "Get" => Token::Get,
             "class" => Token::Class, 
             "new" => Token::New,
+            "copy" => Token::Copy,
+            "same" => Token::Same,
             "local" => Token::Local, 
             "print" => Token::Print, 
+            "eprint" => Token::Eprint,
             "rest" => Token::Rest,
             "if" => Token::If, 
             "then" => Token::Then, 
             "while" => Token::While,
+            "for" => Token::For,
             "do" => Token::Do, 
             "is" => Token::Is, 
             "done" => Token::Done,
-            _ => Token::Identifier(ident),
+            "func" => Token::Func,
+            "call" => Token::Call,
+            "return" => Token::Return,
+            "spawn" => Token::Spawn,
+            "join" => Token::Join,
+            "atomic_add" => Token::AtomicAdd,
+            "lock" => Token::Lock,
+            "unlock" => Token::Unlock,
+            "on_interrupt" => Token::OnInterrupt,
+            "run" => Token::Run,
+            "try" => Token::Try,
+            "rescue" => Token::Rescue,
+            "match" => Token::Match,
+            "choose" => Token::Choose,
+            "label" => Token::Label,
+            "goto" => Token::Goto,
+            "assert" => Token::Assert,
+            "handler" => Token::Handler,
+            _ if !self.strict_case && let Some(sym) = session::keyword(word) => Token::Identifier(sym),
+            _ => Token::Identifier(self.interner.intern(ident)),
         }
     }
 
+    /// Digits with an optional fraction, `_` separators (`1_000_000`) and
+    /// an exponent (`1.5e3`, `2e-3`). Written with either, it is a float.
     fn lex_number(&mut self) -> Token {
-        let mut n = String::new();
-        while self.pos < self.input.len() && (self.input[self.pos].is_digit(10) || self.input[self.pos] == '.') {
-            n.push(self.input[self.pos]); 
+        let start = self.pos;
+        while self.peek_char().is_some_and(|c| c.is_ascii_digit() || c == '.' || c == '_') {
             self.pos += 1;
         }
-        Token::Number(n.parse().unwrap_or(0.0))
+        // Only an `e` followed by digits is an exponent; `2each` stays a number and a name
+        let rest = &self.input.as_bytes()[self.pos..];
+        if let [b'e' | b'E', after @ ..] = rest {
+            let sign = usize::from(matches!(after.first(), Some(b'+' | b'-')));
+            if after.get(sign).is_some_and(u8::is_ascii_digit) {
+                self.pos += 1 + sign;
+                while self.peek_char().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+                    self.pos += 1;
+                }
+            }
+        }
+        let text = self.input[start..self.pos].replace('_', "");
+        match text.parse() {
+            Ok(n) if text.contains(['.', 'e', 'E']) => Token::Float(n),
+            Ok(n) => Token::Number(n),
+            Err(_) => {
+                self.errors.push((self.token_span, format!("malformed number `{}`", &self.input[start..self.pos])));
+                Token::Number(0.0)
+            }
+        }
     }
 
+    /// Lexes a string literal, resolving `\n`, `\t`, `\r`, `\0`, `\e`
+    /// (escape, for terminal codes), `\\` and `\"`. Any other backslash is
+    /// kept as written. The token holds the exact bytes the program will see.
+    /// A string ends on its own line; one left open stops at the newline.
     fn lex_string(&mut self) -> Token {
         self.pos += 1; // Skip opening quote
         let mut s = String::new();
-        while self.pos < self.input.len() && self.input[self.pos] != '"' {
-            s.push(self.input[self.pos]); 
-            self.pos += 1;
+        while let Some(c) = self.peek_char() && c != '"' && c != '\n' {
+            self.bump();
+            if c != '\\' {
+                s.push(c);
+                continue;
+            }
+            let escaped = match self.peek_char() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('e') => '\x1b',
+                Some('\\') => '\\',
+                Some('"') => '"',
+                _ => { s.push('\\'); continue; }
+            };
+            self.bump();
+            s.push(escaped);
+        }
+        if self.peek_char() == Some('"') {
+            self.pos += 1; // Skip closing quote
+        } else {
+            self.errors.push((self.token_span, "string is never closed with `\"`".into()));
         }
-        if self.pos < self.input.len() { self.pos += 1; } // Skip closing quote
         Token::StringLit(s)
     }
 
+    /// Captures source text verbatim up to (not including) the `done` that
+    /// ends a line (a `#` comment may follow it), so asm labels, registers, quotes, indentation and
+    /// newlines survive untouched.
+    fn lex_raw_block(&mut self) -> Token {
+        let start = self.pos;
+        while self.peek_char().is_some() && !self.at_block_end() {
+            self.bump();
+        }
+        Token::RawText(self.input[start..self.pos].to_string())
+    }
+
+    fn at_block_end(&self) -> bool {
+        if self.input[..self.pos].chars().next_back().is_some_and(|c| !c.is_whitespace()) { return false; }
+        if !self.at_word("done") { return false; }
+        let rest = self.input[self.pos + 4..].trim_start_matches([' ', '\t', '\r']);
+        rest.is_empty() || rest.starts_with(['\n', '#'])
+    }
+
+    /// True if `word` starts at the cursor and is not part of a longer identifier.
+    fn at_word(&self, word: &str) -> bool {
+        let Some(rest) = self.input[self.pos..].strip_prefix(word) else { return false };
+        !rest.starts_with(is_ident_continue)
+    }
+
     fn skip_whitespace(&mut self) {
-        while self.pos < self.input.len() && self.input[self.pos].is_whitespace() { 
-            self.pos += 1; 
+        while let Some(c) = self.peek_char() && c.is_whitespace() {
+            self.bump();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Unicode's XID_Start, or `_`. Roman numerals (`Ⅰ`, `Ⅴ`) are letters to
+/// Unicode but read as Latin ones, so neither they nor the rest of the
+/// Number Forms block can be part of a name.
+fn is_ident_start(c: char) -> bool {
+    (unicode_ident::is_xid_start(c) || c == '_') && !('\u{2150}'..='\u{218F}').contains(&c)
+}
+
+/// Unicode's XID_Continue, which takes in digits and `_`, less Number Forms
+/// as in `is_ident_start`.
+fn is_ident_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c) && !('\u{2150}'..='\u{218F}').contains(&c)
+}
@@ -0,0 +1,49 @@
+//! The compiler's own progress and debugging output, on stderr so it never
+//! mixes with a program's output under `hamer run`. `-v` adds what each
+//! stage decided (registers, imports, the tools invoked); `-vv` also traces
+//! every token, statement and runtime call on the way.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    /// Warnings and errors only; those are always shown.
+    Quiet,
+    /// One line per stage.
+    Info,
+    /// Decisions a stage made.
+    Debug,
+    /// Every step.
+    Trace,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// A stage starting, shown unless quiet.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Info) { eprintln!("[H@mer] {}", format_args!($($arg)*)); }
+    };
+}
+
+/// A decision worth knowing when something goes wrong, shown with `-v`.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Debug) { eprintln!("[debug] {}", format_args!($($arg)*)); }
+    };
+}
+
+/// A single step, shown with `-vv`.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Trace) { eprintln!("[trace] {}", format_args!($($arg)*)); }
+    };
+}
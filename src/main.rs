@@ -1,55 +1,364 @@
 use std::env;
 use std::fs;
+use std::path::Path;
 use std::process;
 
+#[macro_use]
+mod log;
+mod ast;
+mod config;
+mod disasm;
+mod dist;
 mod lexer;
 mod parser;
+mod resolve;
+mod sema;
 mod generator;
+mod runtime;
+mod session;
+mod source;
+mod stdlib;
+mod target;
+mod toolchain;
+mod unparse;
 
+use config::Config;
 use lexer::Lexer;
 use parser::Parser;
 use generator::Generator;
+use session::Session;
+use target::{host_triple, Dialect, Target};
 
 fn main() {
-    // Collect CLI arguments: hamer <filename>
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() < 2 {
-        println!("H@mer Compiler v0.1");
-        println!("Usage: hamer <file.hmr>");
+    // Collect CLI arguments: hamer [build|run|disasm|dist] [options] <filename>
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // `build` also assembles and links the program into an executable;
+    // `run` also assembles, links and executes it; `disasm`
+    // assembles it and shows each source line's instructions and bytes;
+    // `dist` builds a stripped binary and reports its size and syscalls
+    let build = args.first().is_some_and(|a| a == "build");
+    let run = args.first().is_some_and(|a| a == "run");
+    let disasm = args.first().is_some_and(|a| a == "disasm");
+    let dist = args.first().is_some_and(|a| a == "dist");
+    if build || run || disasm || dist { args.remove(0); }
+    let quiet = build || run || disasm || dist;
+    let mut qemu = false;
+    let mut save_temps = false;
+    let mut compress = false;
+    let mut output = None;
+    let mut cross = None;
+    let mut file_path = None;
+    let mut emit_ldscript = false;
+    let mut emit_sourcemap = false;
+    let mut emit_layout = false;
+    let mut emit_expanded = false;
+    let mut want_map = false;
+    let mut allow_missing_python = false;
+    let mut allow_run = false;
+    let mut check_div = false;
+    let mut checked = false;
+    let mut cfi = false;
+    let mut entry = "_start".to_string();
+    let mut no_runtime = false;
+    let mut stack_guard = false;
+    let mut verbosity = 0;
+    let mut target = Target::host();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--emit=ldscript" => emit_ldscript = true,
+            "--emit=sourcemap" => emit_sourcemap = true,
+            "--emit=layout" => emit_layout = true,
+            "--emit=expanded" => emit_expanded = true,
+            "--map" => want_map = true,
+            "--allow-missing-python" => allow_missing_python = true,
+            "--allow-run" => allow_run = true,
+            "--check-div" => check_div = true,
+            "--checked" => checked = true,
+            "-g" | "--cfi" => cfi = true,
+            "--qemu" => qemu = true,
+            "--save-temps" => save_temps = true,
+            "--pie" => target.pie = true,
+            "--compress" => compress = true,
+            "--no-runtime" => no_runtime = true,
+            "--stack-guard" => stack_guard = true,
+            "--cross" => cross = Some("aarch64-linux-gnu-".to_string()),
+            a if a.starts_with("--cross=") => cross = Some(a["--cross=".len()..].to_string()),
+            "-o" => {
+                let Some(name) = rest.next() else {
+                    eprintln!("error: -o needs a file name");
+                    process::exit(1);
+                };
+                output = Some(name.clone());
+            }
+            "-v" | "--verbose" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            "--entry" => {
+                let Some(name) = rest.next() else {
+                    eprintln!("error: --entry needs a symbol name");
+                    process::exit(1);
+                };
+                entry = name.clone();
+            }
+            a if a.starts_with("--entry=") => entry = a["--entry=".len()..].to_string(),
+            a if a.starts_with("--dialect=") => {
+                let name = &a["--dialect=".len()..];
+                let Some(dialect) = Dialect::from_name(name) else {
+                    eprintln!("error: unknown assembler dialect `{}` (expected gnu, llvm or apple)", name);
+                    process::exit(1);
+                };
+                target.dialect = dialect;
+            }
+            a if a.starts_with("--target=") => {
+                // Only one backend exists, so a valid triple changes nothing yet
+                if let Err(e) = Target::from_triple(&a["--target=".len()..]) {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+            }
+            _ => file_path = Some(arg.clone()),
+        }
+    }
+
+    // Building on to run or inspect the program keeps the stages quiet
+    log::set_level(match verbosity {
+        0 if quiet => log::Level::Quiet,
+        0 => log::Level::Info,
+        1 => log::Level::Debug,
+        _ => log::Level::Trace,
+    });
+
+    if quiet && target.dialect == Dialect::Apple {
+        eprintln!("error: `hamer {}` builds a Linux ELF object; drop --dialect=apple", if build { "build" } else if run { "run" } else if dist { "dist" } else { "disasm" });
         process::exit(1);
     }
 
-    let file_path = &args[1];
+    let symbol_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$';
+    if entry.is_empty() || entry.starts_with(|c: char| c.is_ascii_digit()) || !entry.chars().all(symbol_char) {
+        eprintln!("error: `{}` is not a valid symbol name for --entry", entry);
+        process::exit(1);
+    }
+    if output.is_some() && !(build || dist) {
+        eprintln!("error: -o names the executable `hamer build` or `hamer dist` writes; plain compiles name the assembly after the source");
+        process::exit(1);
+    }
+    if no_runtime && (build || run || dist) {
+        eprintln!("error: --no-runtime output is a subroutine, not a program; link the object into your own program instead");
+        process::exit(1);
+    }
+    if no_runtime && stack_guard {
+        eprintln!("error: --stack-guard keeps the stack limit in tpidr_el0, which belongs to the host program under --no-runtime");
+        process::exit(1);
+    }
+    if no_runtime && checked {
+        eprintln!("error: --checked installs process-wide signal handlers, which --no-runtime code can't own");
+        process::exit(1);
+    }
+
+    if target.pie && emit_ldscript {
+        eprintln!("error: --pie cannot be combined with --emit=ldscript, which places the program at a fixed address");
+        process::exit(1);
+    }
+
+    let Some(file_path) = file_path else {
+        println!("H@mer Compiler v0.1");
+        println!("Usage: hamer [build [-o <exe>] | run [--qemu] | disasm | dist [-o <exe>] [--compress]] [--cross[=<prefix>]] [--save-temps] [--emit=ldscript|sourcemap|layout|expanded] [--map] [--allow-missing-python] [--allow-run] [--check-div] [--checked] [--stack-guard] [--pie] [-v|-vv] [--entry <symbol>] [--no-runtime] [-g|--cfi] [--dialect=gnu|llvm|apple] [--target=aarch64-linux-gnu] <file.hmr>");
+        process::exit(1);
+    };
     
     // 1. Read the H@mer source file
-    let input = fs::read_to_string(file_path).expect("Could not read source file");
+    let mut session = Session::default();
+    let input = match session.sources.load(&file_path) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("error: cannot read `{}`: {}", file_path, e);
+            process::exit(1);
+        }
+    };
 
-    println!("[H@mer] Tokenizing...");
+    info!("Tokenizing...");
     // 2. Lexical Analysis (Tokens)
-    let mut lexer = Lexer::new(input);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == lexer::Token::EOF { break; }
-        tokens.push(token);
-    }
+    let config = Config::load(session.sources.loader());
+    let mut lexer = Lexer::new(&input, &mut session.interner);
+    lexer.strict_case = config.strict_case;
+    let (tokens, spans) = lexer.tokenize();
+    let mut errors = std::mem::take(&mut lexer.errors);
 
-    println!("[H@mer] Parsing AST...");
+    info!("Parsing AST...");
     // 3. Syntax Analysis (Abstract Syntax Tree)
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(&tokens, &spans, &mut session);
     let ast = parser.parse_program();
+    for (span, w) in &parser.warnings { eprintln!("warning: {}", span.show(&file_path, &input, w)); }
+    // A character the lexer dropped usually derails the rest of its line too
+    let bad_lines: Vec<usize> = errors.iter().map(|(span, _)| span.line).collect();
+    errors.extend(parser.errors.drain(..).filter(|(span, _)| !bad_lines.contains(&span.line)));
+    errors.sort_by_key(|(span, _)| *span);
+    if !errors.is_empty() {
+        for (span, e) in &errors { eprintln!("error: {}", span.show(&file_path, &input, e)); }
+        eprintln!("{} error{} found", errors.len(), if errors.len() == 1 { "" } else { "s" });
+        process::exit(1);
+    }
 
-    println!("[H@mer] Generating ARM64 Assembly...");
+    let imports = std::mem::take(&mut parser.imports);
+    let options = resolve::Options { strict_case: config.strict_case, allow_run, allow_missing_python, exec_timeout: config.exec_timeout };
+    let report = resolve::resolve(&file_path, imports, &mut session, &options);
+    for w in &report.warnings { eprintln!("warning: {}", w); }
+    if !report.errors.is_empty() {
+        for e in &report.errors { eprintln!("error: {}", e); }
+        process::exit(1);
+    }
+    // 5. Output, named after the source file: prog.hmr becomes prog.s
+    let stem = Path::new(&file_path).file_stem().map_or("out".into(), |s| s.to_string_lossy().into_owned());
+    if emit_expanded {
+        // Written before code generation, so it is there to look at when that fails
+        fs::write(format!("{}.expanded.hmr", stem), unparse::expand(&session, &ast)).expect("Could not write expanded source");
+    }
+
+    info!("Checking names...");
+    let report = sema::check(&file_path, &ast, &session);
+    for w in &report.warnings { eprintln!("warning: {}", w); }
+    if !report.errors.is_empty() {
+        for e in &report.errors { eprintln!("error: {}", e); }
+        eprintln!("{} error{} found", report.errors.len(), if report.errors.len() == 1 { "" } else { "s" });
+        process::exit(1);
+    }
+
+    info!("Generating ARM64 Assembly...");
     // 4. Code Generation
     let mut generator = Generator::new();
+    generator.allow_missing_python = allow_missing_python;
+    generator.allow_run = allow_run;
+    generator.check_div = check_div;
+    generator.cfi = cfi;
+    generator.sourcemap = emit_sourcemap || disasm;
+    generator.checked = checked;
+    generator.stack_guard = stack_guard;
+    generator.entry = entry;
+    generator.no_runtime = no_runtime;
+    generator.exec_timeout = config.exec_timeout;
+    generator.file = file_path.clone();
+    generator.session = session;
+    generator.target = target;
     let assembly = generator.generate(ast);
+    let listing = (disasm || dist).then(|| assembly.clone());
+    for w in &generator.warnings { eprintln!("warning: {}", w); }
+    if !generator.errors.is_empty() {
+        for e in &generator.errors { eprintln!("error: {}", e); }
+        process::exit(1);
+    }
 
-    // 5. Output to out.s (Assembly file)
-    fs::write("out.s", assembly).expect("Could not write assembly file");
-    
-    println!("[SUCCESS] compiled {} to out.s", file_path);
+    // The assembly and object `build`, `run`, `dist` and `disasm` make on the way go
+    // in a directory of this process's own, so builds side by side in one
+    // directory can't overwrite each other's; --save-temps keeps them here
+    let temps = (quiet && !save_temps).then(|| env::temp_dir().join(format!("hamer-{}", process::id())));
+    if let Some(dir) = &temps { fs::create_dir_all(dir).expect("Could not create a directory for intermediate files"); }
+    let intermediate = |name: String| match &temps {
+        Some(dir) => dir.join(name).to_string_lossy().into_owned(),
+        None => name,
+    };
+    let asm_file = intermediate(format!("{}.s", stem));
+    let obj_file = intermediate(format!("{}.o", stem));
+    let remove_temps = || if let Some(dir) = &temps { let _ = fs::remove_dir_all(dir); };
+    fs::write(&asm_file, assembly).expect("Could not write assembly file");
+    if emit_ldscript {
+        fs::write(format!("{}.ld", stem), generator.linker_script()).expect("Could not write linker script");
+    }
+    if emit_layout {
+        fs::write(format!("{}.layout", stem), generator.layout()).expect("Could not write class layout");
+    }
+    if emit_sourcemap {
+        fs::write(format!("{}.s.json", stem), generator.source_map_json(&format!("{}.s", stem))).expect("Could not write source map");
+    }
+
+    let mut ld_args = Vec::new();
+    if generator.target.pie { ld_args.extend(["-pie".to_string(), "--no-dynamic-linker".to_string()]); }
+    if emit_ldscript { ld_args.extend(["-T".to_string(), format!("{}.ld", stem)]); }
+    if want_map { ld_args.push(format!("-Map={}.map", stem)); }
+    if generator.entry != "_start" { ld_args.extend(["-e".to_string(), generator.entry.clone()]); }
+
+    let cross = cross.as_deref();
+    if build {
+        let exe = output.unwrap_or_else(|| stem.clone());
+        let built = toolchain::assemble(&asm_file, &obj_file, false, cross)
+            .and_then(|_| toolchain::link(&obj_file, &exe, &ld_args, cross));
+        remove_temps();
+        if let Err(e) = built {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+        if !generator.target.runs_on_host() {
+            eprintln!("warning: this host ({}) can't run {} programs directly", host_triple(), generator.target.triple());
+            eprintln!("  hint: `hamer run --qemu {}` runs it under qemu-aarch64", file_path);
+        }
+        return;
+    }
+
+    if run {
+        // Everything on stdout from here on is the program's own output
+        let exe = intermediate(stem.clone());
+        let built = toolchain::assemble(&asm_file, &obj_file, false, cross)
+            .and_then(|_| toolchain::link(&obj_file, &exe, &ld_args, cross))
+            .and_then(|_| toolchain::run(&exe, qemu));
+        remove_temps();
+        match built {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if dist {
+        ld_args.push("--strip-all".to_string());
+        let exe = output.unwrap_or_else(|| stem.clone());
+        let built = toolchain::assemble(&asm_file, &obj_file, false, cross)
+            .and_then(|_| toolchain::link(&obj_file, &exe, &ld_args, cross));
+        remove_temps();
+        if let Err(e) = built {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+        let size = |exe: &str| fs::metadata(exe).map(|m| m.len()).unwrap_or(0);
+        let stripped = size(&exe);
+        if compress {
+            if let Err(e) = toolchain::compress(&exe) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+            println!("{}: {} bytes ({} before compression)", exe, size(&exe), stripped);
+        } else {
+            println!("{}: {} bytes", exe, stripped);
+        }
+        let asm = listing.unwrap_or_default();
+        println!("syscalls: {}", dist::describe(&dist::syscalls(&asm)));
+        return;
+    }
+
+    if let Some(asm) = listing {
+        let dump = toolchain::assemble(&asm_file, &obj_file, true, cross).and_then(|_| toolchain::disassemble(&obj_file, cross));
+        remove_temps();
+        match dump {
+            Ok(dump) => print!("{}", disasm::annotate(&dump, &asm, &generator)),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    println!("[SUCCESS] compiled {} to {}", file_path, asm_file);
     println!("Next steps:");
-    println!("  as out.s -o out.o");
-    println!("  ld out.o -o hamer_prog");
+    println!("  as {} -o {}", asm_file, obj_file);
+    if no_runtime {
+        println!("  link {} into your program and call {}(heap), with heap pointing at free memory", obj_file, generator.entry);
+        return;
+    }
+    println!("  ld {}{} -o hamer_prog", ld_args.iter().map(|a| format!("{} ", a)).collect::<String>(), obj_file);
+    println!("or `hamer build {} -o hamer_prog` to do both", file_path);
+    if !generator.target.runs_on_host() {
+        eprintln!("warning: this host ({}) can't run {} programs directly", host_triple(), generator.target.triple());
+        eprintln!("  hint: `hamer run --qemu {}` runs it under qemu-aarch64", file_path);
+    }
 }
\ No newline at end of file
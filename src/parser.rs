@@ -1,243 +1,1564 @@
-use crate::lexer::Token;
-use std::fs;
-
-#[derive(Debug)]
-pub enum Stmt {
-    LocalAssign { name: String, value: f64 },
-    ClassDef { name: String, fields: Vec<String> },
-    HeapAlloc { var_name: String, class_name: String },
-    FieldAssign { path: Vec<String>, value: f64 },
-    FieldMath { path: Vec<String>, op: Token, rhs_val: f64 },
-    PrintVar(String),
-    PrintString(String),
-    IfStmt { path: Vec<String>, op: Token, rhs_val: f64, body: Vec<Stmt> },
-    ProbIf { chance: f64, body: Vec<Stmt> },
-    WhileStmt { path: Vec<String>, op: Token, rhs_val: f64, body: Vec<Stmt> },
-    AsmBlock(String),      
-    IntelBlock(String),    
-    PythonBlock(String),   
-    MergeBlock(String),    
+use crate::ast::{Cond, Expr, Field, Item, ItemId, Operand, PrintItem, Stmt, StmtId, TextSrc};
+use crate::lexer::{Span, Token};
+use crate::session::{kw, Session, Symbol};
+
+/// Walks a borrowed token slice; identifiers go into the AST as the
+/// symbols the lexer interned.
+pub struct Parser<'a> {
+    pub tokens: &'a [Token],
+    pub spans: &'a [Span],
+    pub pos: usize,
+    /// Syntax errors as (span, message).
+    pub errors: Vec<(Span, String)>,
+    /// Suspicious but valid code, as (span, message).
+    pub warnings: Vec<(Span, String)>,
+    /// Every `Get` and `@python emit` in source order, with its line, for
+    /// the resolver.
+    pub imports: Vec<(ItemId, usize)>,
+    /// How many errors there were when the current statement began. Each
+    /// statement reports at most one error of its own, since the first
+    /// mistake usually leaves the rest of the line unreadable.
+    stmt_errors: usize,
+    session: &'a mut Session,
 }
 
-pub struct Parser { pub tokens: Vec<Token>, pub pos: usize }
+impl<'a> Parser<'a> {
+    pub fn new(tokens: &'a [Token], spans: &'a [Span], session: &'a mut Session) -> Self {
+        Self { tokens, spans, pos: 0, errors: Vec::new(), warnings: Vec::new(), imports: Vec::new(), stmt_errors: 0, session }
+    }
+
+    fn name(&self, sym: Symbol) -> String {
+        self.session.interner.resolve(sym).to_string()
+    }
+
+    /// Stands in for a name that is missing; the error saying so has been
+    /// reported already.
+    fn blank(&mut self) -> Symbol {
+        self.session.interner.intern("")
+    }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self { Self { tokens, pos: 0 } }
-    
-    fn advance(&mut self) -> Token {
+    /// Returns the current token and moves past it. The cursor never moves
+    /// past EOF, so callers at the end of input keep seeing EOF.
+    fn advance(&mut self) -> &'a Token {
         let t = self.peek();
-        if self.pos < self.tokens.len() {
+        if *t != Token::EOF {
             self.pos += 1;
         }
         t
     }
 
-    fn peek(&self) -> Token {
-        if self.pos < self.tokens.len() {
-            self.tokens[self.pos].clone()
+    /// The current token; the lexer ends every stream with an EOF sentinel,
+    /// and anything past the slice reads as EOF too.
+    fn peek(&self) -> &'a Token {
+        self.tokens.get(self.pos).unwrap_or(&Token::EOF)
+    }
+
+    /// Parses statements up to the `done` closing a block that began at `start`.
+    fn parse_body(&mut self, start: Span, what: &str) -> Vec<StmtId> {
+        let mut body = Vec::new();
+        while !matches!(self.peek(), Token::Done | Token::EOF) {
+            body.push(self.parse_statement());
+        }
+        self.expect_done(start, what);
+        body
+    }
+
+    /// Parses the body after `then`/`do`. A statement on the same line as
+    /// the keyword is the whole body (`if hp < 1 then print "dead"`), with
+    /// an optional `done` after it; otherwise it's a block ending in `done`.
+    fn parse_then_body(&mut self, start: Span, what: &str) -> Vec<StmtId> {
+        let line = self.prev_line();
+        if self.line() != line || matches!(self.peek(), Token::Done | Token::EOF) {
+            return self.parse_body(start, what);
+        }
+        let stmt = self.parse_statement();
+        if *self.peek() == Token::Done && self.line() == line { self.advance(); }
+        vec![stmt]
+    }
+
+    /// Parses an `if` after its keyword. With `elif` set it is the `if`
+    /// an `elif` stands for, which ends at the `done` of the chain it
+    /// continues instead of needing one of its own.
+    fn parse_if(&mut self, start: Span, elif: bool) -> Stmt {
+        if *self.peek() == Token::Quest {
+            self.advance(); // ?
+            let chance = self.parse_chance();
+            let (body, else_body) = self.parse_if_branches(start, elif);
+            return Stmt::ProbIf { chance, body, else_body };
+        }
+        let p = self.parse_path();
+        if *self.peek() == Token::Same {
+            self.advance();
+            let rhs = self.parse_path();
+            let (body, else_body) = self.parse_if_branches(start, elif);
+            return Stmt::IfSame { lhs: p, rhs, body, else_body };
+        }
+        let op = self.parse_cmp_op();
+        let rhs = self.parse_expr();
+        let cond = self.parse_cond_from(Cond::Cmp { path: p, op, rhs });
+        let (body, else_body) = self.parse_if_branches(start, elif);
+        Stmt::IfStmt { cond, body, else_body }
+    }
+
+    /// Parses what follows an `if` condition: the body, then an `elif` or
+    /// `else` branch, up to the `done` closing them all. A statement on the
+    /// same line as `then` is a one-line body as in `parse_then_body`, and
+    /// may be followed on that line by `else` and a one-line else branch,
+    /// or by an `else` ending the line and a block closed with `done`.
+    fn parse_if_branches(&mut self, start: Span, elif: bool) -> (Vec<StmtId>, Vec<StmtId>) {
+        while matches!(self.peek(), Token::Then | Token::Is) { self.advance(); }
+        let line = self.prev_line();
+        if !elif && self.line() == line && !matches!(self.peek(), Token::Done | Token::EOF) {
+            let body = vec![self.parse_statement()];
+            let mut else_body = Vec::new();
+            if self.peek_word(kw::ELSE) && self.line() == line {
+                self.advance();
+                if self.line() != line || *self.peek() == Token::EOF {
+                    // `else` ending the line opens a block, as `then` does
+                    while !matches!(self.peek(), Token::Done | Token::EOF) {
+                        else_body.push(self.parse_statement());
+                    }
+                    self.expect_done(start, "if");
+                    return (body, else_body);
+                }
+                else_body.push(self.parse_statement());
+            }
+            if *self.peek() == Token::Done && self.line() == line { self.advance(); }
+            return (body, else_body);
+        }
+        let mut body = Vec::new();
+        while !matches!(self.peek(), Token::Done | Token::EOF) && !self.peek_word(kw::ELSE) && !self.peek_word(kw::ELIF) {
+            body.push(self.parse_statement());
+        }
+        let mut else_body = Vec::new();
+        if self.peek_word(kw::ELIF) {
+            let at = self.span();
+            self.advance();
+            let stmt = self.parse_if(at, true);
+            else_body.push(self.session.alloc_stmt(stmt, at.line));
+        } else if self.peek_word(kw::ELSE) {
+            self.advance();
+            while !matches!(self.peek(), Token::Done | Token::EOF) {
+                else_body.push(self.parse_statement());
+            }
+        }
+        if !elif { self.expect_done(start, "if"); }
+        (body, else_body)
+    }
+
+    /// Consumes the `done` that closes a block, reporting it if the input ran out first.
+    fn expect_done(&mut self, start: Span, what: &str) {
+        if *self.peek() == Token::Done {
+            self.advance();
         } else {
-            Token::EOF
+            self.errors.push((start, format!("`{}` block is never closed with `done`", what)));
         }
     }
 
-    pub fn parse_program(&mut self) -> Vec<Stmt> {
+    pub fn parse_program(&mut self) -> Vec<StmtId> {
         let mut stmts = Vec::new();
-        while self.peek() != Token::EOF {
+        while *self.peek() != Token::EOF {
             stmts.push(self.parse_statement());
         }
         stmts
     }
 
-    fn parse_path(&mut self) -> Vec<String> {
-        let mut path = Vec::new();
-        if let Token::Identifier(s) = self.peek() {
-            self.advance(); 
-            path.push(s);
-            while self.peek() == Token::Dot {
-                self.advance(); // consume dot
-                if let Token::Identifier(s) = self.peek() {
+    /// Where the next token starts.
+    fn span(&self) -> Span {
+        self.spans.get(self.pos).copied().unwrap_or_default()
+    }
+
+    /// Line of the next token, or 0 past the end of input.
+    fn line(&self) -> usize {
+        self.span().line
+    }
+
+    /// Line of the token `pos`, if there is one.
+    fn line_at(&self, pos: usize) -> Option<usize> {
+        self.spans.get(pos).map(|s| s.line)
+    }
+
+    /// Line of the token just consumed, or 0 before the first.
+    fn prev_line(&self) -> usize {
+        self.pos.checked_sub(1).and_then(|p| self.line_at(p)).unwrap_or(0)
+    }
+
+    /// Records a syntax error, unless the statement being parsed already
+    /// has one.
+    fn error(&mut self, span: Span, message: String) {
+        if self.errors.len() > self.stmt_errors { return; }
+        self.errors.push((span, message));
+    }
+
+    /// Reports that `what` should come next. A token on a later line
+    /// doesn't count as what was found: the statement ended before it, so
+    /// the error points at the last token of the line instead.
+    fn expected(&mut self, what: &str) {
+        let prev = self.pos.checked_sub(1).map(|p| (self.describe(&self.tokens[p]), self.spans[p]));
+        let (found, span) = match prev {
+            Some((_, span)) if !self.continues_line() => ("the end of the line".to_string(), span),
+            _ => (self.describe(self.peek()), self.span()),
+        };
+        let after = match prev {
+            Some((token, span)) if span.line == self.line() || found == "the end of the line" => format!(" after {}", token),
+            _ => String::new(),
+        };
+        self.error(span, format!("expected {}{}, found {}", what, after, found));
+    }
+
+    /// Consumes a name on the current line, or reports that `what` was
+    /// expected there.
+    fn expect_name(&mut self, what: &str) -> Option<Symbol> {
+        match self.peek() {
+            Token::Identifier(s) if self.continues_line() => {
+                self.advance();
+                Some(*s)
+            }
+            _ => {
+                self.expected(what);
+                None
+            }
+        }
+    }
+
+    /// Consumes a number on the current line, or reports that one was
+    /// expected there.
+    fn expect_number(&mut self) -> f64 {
+        match self.peek() {
+            Token::Number(n) | Token::Float(n) if self.continues_line() => {
+                self.advance();
+                *n
+            }
+            _ => {
+                self.expected("a number");
+                0.0
+            }
+        }
+    }
+
+    /// True if the next token can start an operand and sits on `line`.
+    /// `else` never does, so `if a then print b else print c` ends the
+    /// first print at it.
+    fn operand_on_line(&self, line: usize) -> bool {
+        self.line() == line && match self.peek() {
+            Token::Number(_) | Token::Float(_) | Token::Minus => true,
+            Token::Identifier(w) => *w != kw::ELSE,
+            _ => false,
+        }
+    }
+
+    /// True if the next token is on the same line as the one just consumed.
+    fn continues_line(&self) -> bool {
+        self.pos.checked_sub(1).is_some_and(|p| self.line_at(p) == Some(self.line()))
+    }
+
+    /// True if the next token is the identifier `word` (a contextual keyword).
+    fn peek_word(&self, word: Symbol) -> bool {
+        matches!(self.peek(), Token::Identifier(s) if *s == word)
+    }
+
+    /// True if the next token is `word` used as a statement rather than as
+    /// a variable: nothing on the line after it but operands.
+    fn peek_command(&self, word: Symbol) -> bool {
+        if !self.peek_word(word) { return false; }
+        let line = self.line();
+        if self.line_at(self.pos + 1) != Some(line) { return true; }
+        match self.tokens.get(self.pos + 1) {
+            Some(Token::Identifier(w)) => *w != kw::PUT && *w != kw::APPEND,
+            Some(Token::Number(_) | Token::Float(_) | Token::Minus | Token::Semicolon | Token::EOF) => true,
+            _ => false,
+        }
+    }
+
+    fn parse_operand(&mut self) -> Operand {
+        match self.peek() {
+            Token::Number(n) | Token::Float(n) if self.continues_line() => { self.advance(); Operand::Num(*n) }
+            Token::Minus if self.continues_line() => {
+                self.advance();
+                Operand::Num(-self.expect_number())
+            }
+            Token::Identifier(_) if self.continues_line() => Operand::Path(self.parse_path()),
+            _ => {
+                self.expected("a number or a variable");
+                Operand::Num(0.0)
+            }
+        }
+    }
+
+    /// Parses an expression, with `*` and `/` binding tighter than `+` and
+    /// `-` and each level associating to the left.
+    fn parse_expr(&mut self) -> Expr {
+        let mut lhs = self.parse_term();
+        while matches!(self.peek(), Token::Plus | Token::Minus) && self.continues_line() {
+            let op = self.advance().clone();
+            let rhs = self.parse_term();
+            lhs = Expr::Binary { op, lhs: self.session.exprs.alloc(lhs), rhs: self.session.exprs.alloc(rhs) };
+        }
+        lhs
+    }
+
+    fn parse_term(&mut self) -> Expr {
+        let mut lhs = self.parse_factor();
+        while matches!(self.peek(), Token::Star | Token::Slash) && self.continues_line() {
+            let op = self.advance().clone();
+            let rhs = self.parse_factor();
+            lhs = Expr::Binary { op, lhs: self.session.exprs.alloc(lhs), rhs: self.session.exprs.alloc(rhs) };
+        }
+        lhs
+    }
+
+    /// A number, a string, a path, `-factor` or a parenthesized expression.
+    fn parse_factor(&mut self) -> Expr {
+        if !self.continues_line() {
+            self.expected("a value");
+            return Expr::Num(0.0);
+        }
+        match self.peek() {
+            Token::Number(n) => { let n = *n; self.advance(); Expr::Num(n) }
+            Token::Float(n) => { let n = *n; self.advance(); Expr::Float(n) }
+            Token::StringLit(s) => { let s = s.clone(); self.advance(); Expr::Str(s) }
+            Token::Minus => {
+                self.advance();
+                match self.parse_factor() {
+                    Expr::Num(n) => Expr::Num(-n),
+                    Expr::Float(n) => Expr::Float(-n),
+                    e => Expr::Binary { op: Token::Minus, lhs: self.session.exprs.alloc(Expr::Num(0.0)), rhs: self.session.exprs.alloc(e) },
+                }
+            }
+            Token::LeftParen => {
+                let open = self.span();
+                self.advance();
+                let inner = self.parse_expr();
+                if *self.peek() == Token::RightParen {
                     self.advance();
-                    path.push(s);
-                } else { break; }
+                } else {
+                    self.error(open, "expected `)` to close `(`".into());
+                }
+                inner
+            }
+            Token::Identifier(_) => Expr::Path(self.parse_path()),
+            _ => {
+                self.expected("a value");
+                Expr::Num(0.0)
+            }
+        }
+    }
+
+    /// Parses one `path op number` comparison.
+    fn parse_cmp(&mut self) -> Cond {
+        let path = self.parse_path();
+        let op = self.parse_cmp_op();
+        let rhs = self.parse_expr();
+        Cond::Cmp { path, op, rhs }
+    }
+
+    /// Consumes `<`, `>` or `==`.
+    fn parse_cmp_op(&mut self) -> Token {
+        match self.peek() {
+            Token::Less | Token::Greater | Token::Equal if self.continues_line() => self.advance().clone(),
+            _ => {
+                self.expected("a comparison (`<`, `>` or `==`)");
+                Token::Equal
             }
         }
+    }
+
+    /// Parses any `and`/`or` clauses following an already parsed comparison.
+    fn parse_cond_from(&mut self, first: Cond) -> Cond {
+        let mut cond = self.parse_and(first);
+        while self.peek_word(kw::OR) {
+            self.advance();
+            let first = self.parse_cmp();
+            let rhs = self.parse_and(first);
+            cond = Cond::Or(self.session.conds.alloc(cond), self.session.conds.alloc(rhs));
+        }
+        cond
+    }
+
+    fn parse_and(&mut self, mut cond: Cond) -> Cond {
+        while self.peek_word(kw::AND) {
+            self.advance();
+            let rhs = self.parse_cmp();
+            cond = Cond::And(self.session.conds.alloc(cond), self.session.conds.alloc(rhs));
+        }
+        cond
+    }
+
+    /// Parses the `<N%>` after `?`. N may also be a variable or field, read
+    /// when the roll runs.
+    fn parse_chance(&mut self) -> Operand {
+        while matches!(self.peek(), Token::Less | Token::Percent) { self.advance(); }
+        let chance = self.parse_operand();
+        while matches!(self.peek(), Token::Percent | Token::Greater) { self.advance(); }
+        chance
+    }
+
+    /// The field after `by` in `sort` and `find`, if there is one.
+    fn parse_by(&mut self) -> Option<Symbol> {
+        if !(self.peek_word(kw::BY) && self.continues_line()) { return None; }
+        self.advance();
+        self.expect_name("a field to compare by")
+    }
+
+    /// Parses `name arg arg ...` after `call`; arguments end with the line.
+    fn parse_call(&mut self, dest: Option<Symbol>) -> Stmt {
+        let line = self.prev_line();
+        let name = self.expect_name("a function name").unwrap_or_else(|| self.blank());
+        let mut args = Vec::new();
+        while self.operand_on_line(line) {
+            args.push(self.parse_operand());
+            if *self.peek() == Token::Comma { self.advance(); }
+        }
+        Stmt::Call { name, args, dest, from: None }
+    }
+
+    /// Parses the `-> name arg ...` stages of a pipeline after its first
+    /// value. Each stage calls `name` with the value so far put before its
+    /// own arguments, so `hero -> heal 5 -> show` is `show(heal(hero, 5))`.
+    /// A stage may start a new line, for long chains.
+    fn parse_pipe(&mut self, first: Operand, dest: Option<Symbol>) -> Stmt {
+        let mut first = Some(first);
+        let mut inner = None;
+        while *self.peek() == Token::Arrow {
+            let line = self.line();
+            self.advance();
+            let Stmt::Call { name, mut args, .. } = self.parse_call(None) else { unreachable!() };
+            let from = inner.take().map(|call| self.session.alloc_stmt(call, line));
+            if let Some(value) = first.take() { args.insert(0, value); }
+            inner = Some(Stmt::Call { name, args, dest: None, from });
+        }
+        let Some(Stmt::Call { name, args, from, .. }) = inner else { unreachable!() };
+        Stmt::Call { name, args, dest, from }
+    }
+
+    /// Parses a class name with optional generic arguments, e.g. `Box<Hero>`,
+    /// normalized to `Box<Hero>` / `Pair<A,B>` and interned, along with
+    /// each argument.
+    fn parse_class_name(&mut self) -> Option<Symbol> {
+        let Token::Identifier(name) = self.peek() else { return None };
+        let name = *name;
+        self.advance();
+        if *self.peek() != Token::Less { return Some(name); }
+        self.advance();
+        let mut args = Vec::new();
+        while let Some(arg) = self.parse_class_name() {
+            args.push(arg);
+            if *self.peek() == Token::Comma { self.advance(); }
+        }
+        if *self.peek() == Token::Greater { self.advance(); } else { self.expected("`>`"); }
+        let interner = &mut self.session.interner;
+        let name = format!("{}<{}>", interner.resolve(name), interner.join(&args, ","));
+        Some(interner.intern(&name))
+    }
+
+    /// The class after `name:` in a field or parameter.
+    fn parse_field_class(&mut self) -> Option<Symbol> {
+        let class = self.parse_class_name();
+        if class.is_none() { self.expected("a class name"); }
+        class
+    }
+
+    /// Parses `run "prog" "arg" ...`; arguments end with the line.
+    fn parse_run(&mut self, dest: Option<Symbol>) -> Stmt {
+        let line = self.line();
+        self.advance(); // run
+        let mut argv = Vec::new();
+        while self.line() == line && let Token::StringLit(s) = self.peek() {
+            self.advance();
+            argv.push(s.clone());
+        }
+        if argv.is_empty() { self.expected("a program name in quotes"); }
+        Stmt::Run { argv, dest }
+    }
+
+    /// Parses `a.b.c`. Array indices become their own segments, so
+    /// `wave[i].hp` is `["wave", "[i]", "hp"]`.
+    fn parse_path(&mut self) -> Vec<Symbol> {
+        let mut path = Vec::new();
+        let Token::Identifier(s) = self.peek() else {
+            self.expected("a name");
+            return path;
+        };
+        self.advance();
+        path.push(*s);
+        self.parse_index(&mut path);
+        while *self.peek() == Token::Dot {
+            self.advance(); // consume dot
+            let Some(field) = self.expect_name("a field name") else { break };
+            path.push(field);
+            self.parse_index(&mut path);
+        }
         path
     }
 
-    fn parse_statement(&mut self) -> Stmt {
+    fn parse_index(&mut self, path: &mut Vec<Symbol>) {
+        if *self.peek() != Token::LeftBracket { return; }
+        self.advance();
+        let index = match self.peek() {
+            Token::Number(n) | Token::Float(n) if self.continues_line() => (*n as i64).to_string(),
+            Token::Identifier(v) if self.continues_line() => self.name(*v),
+            _ => return self.expected("an index (a number or a variable)"),
+        };
+        self.advance();
+        if *self.peek() == Token::RightBracket { self.advance(); } else { self.expected("`]`"); }
+        path.push(self.session.interner.intern(&format!("[{}]", index)));
+    }
+
+    /// `func name a, b: Class is ... done`, after any `pub`.
+    fn parse_func(&mut self, start: Span, public: bool) -> Stmt {
+        self.advance();
+        let name = self.expect_name("a function name").unwrap_or_else(|| self.blank());
+        let mut params = Vec::new();
+        while let Token::Identifier(p) = self.peek() {
+            self.advance();
+            let mut class = None;
+            if *self.peek() == Token::Colon {
+                self.advance();
+                class = self.parse_field_class();
+            }
+            params.push(Field { name: *p, class, init: None });
+            if *self.peek() == Token::Comma { self.advance(); }
+        }
+        if *self.peek() == Token::Is { self.advance(); }
+        let body = self.parse_body(start, "func");
+        self.item(Item::Func { name, params, body, public })
+    }
+
+    /// What follows `print` (or `eprint`): a format string, `hex`/`bin`
+    /// and a value, or strings and values up to the end of the line.
+    fn parse_print(&mut self) -> Stmt {
+        let line = self.prev_line();
+        match self.peek() {
+            Token::Identifier(f) if *f == kw::FORMAT && matches!(self.tokens.get(self.pos + 1), Some(Token::StringLit(_))) => {
+                let line = self.line();
+                self.advance();
+                let fmt = if let Token::StringLit(s) = self.advance() { s.clone() } else { String::new() };
+                let mut args = Vec::new();
+                while self.operand_on_line(line) {
+                    args.push(self.parse_operand());
+                    if *self.peek() == Token::Comma { self.advance(); }
+                }
+                Stmt::PrintFormat { fmt, args }
+            }
+            Token::Identifier(f) if (*f == kw::HEX || *f == kw::BIN)
+                && matches!(self.tokens.get(self.pos + 1), Some(Token::Identifier(_) | Token::Number(_) | Token::Float(_)))
+                && self.line_at(self.pos + 1) == Some(self.line()) => {
+                self.advance();
+                let bits = if *f == kw::HEX { 4 } else { 1 };
+                Stmt::PrintBits { bits, value: self.parse_operand() }
+            }
+            _ => self.parse_print_items(line),
+        }
+    }
+
+    /// The strings and values after `print`, up to the end of the line. A
+    /// lone string or variable keeps its own simpler statement.
+    fn parse_print_items(&mut self, line: usize) -> Stmt {
+        let mut items = Vec::new();
+        while self.line() == line {
+            match self.peek() {
+                Token::StringLit(s) => {
+                    self.advance();
+                    items.push(PrintItem::Str(s.clone()));
+                }
+                _ if self.operand_on_line(line) => items.push(PrintItem::Value(self.parse_operand())),
+                _ => break,
+            }
+            if *self.peek() == Token::Comma { self.advance(); }
+        }
+        match items.as_slice() {
+            [] => {
+                self.expected("something to print");
+                Stmt::PrintVar(self.blank())
+            }
+            [PrintItem::Str(s)] => Stmt::PrintString(s.clone()),
+            [PrintItem::Value(Operand::Path(p))] if p.len() == 1 => Stmt::PrintVar(p[0]),
+            _ => Stmt::PrintList(items),
+        }
+    }
+
+    /// Stores a declaration, returning the statement that stands in for it.
+    fn item(&mut self, item: Item) -> Stmt {
+        Stmt::Item(self.session.items.alloc(item))
+    }
+
+    /// Parses one statement into the session's arena.
+    fn parse_statement(&mut self) -> StmtId {
+        let line = self.line();
+        let outer = std::mem::replace(&mut self.stmt_errors, self.errors.len());
+        let stmt = self.parse_node();
+        self.end_statement();
+        self.stmt_errors = outer;
+        trace!("line {}: {:?}", line, stmt);
+        self.session.alloc_stmt(stmt, line)
+    }
+
+    /// A statement ends at the end of its line or at `;`. Anything else
+    /// left on the line is reported and skipped rather than being read as
+    /// the start of another statement, unless it closes or continues the
+    /// enclosing block (`done`, `rescue`, `else`, a `"x" then` arm).
+    fn end_statement(&mut self) {
+        if *self.peek() == Token::Semicolon {
+            while *self.peek() == Token::Semicolon { self.advance(); }
+            return;
+        }
+        if self.pos == 0 || !self.continues_line() { return; }
+        let line = self.line();
+        let closes = match (self.peek(), self.tokens.get(self.pos + 1)) {
+            (Token::Done | Token::Rescue | Token::EOF, _) => true,
+            (Token::Identifier(w), _) => *w == kw::ELSE,
+            (Token::StringLit(_) | Token::Number(_) | Token::Float(_), Some(Token::Then)) => true,
+            _ => false,
+        };
+        if closes { return; }
+        let what = self.describe(self.peek());
+        self.error(self.span(), format!("unexpected {} after the end of a statement; start a new line or separate statements with `;`", what));
+        while self.line() == line && !matches!(self.peek(), Token::Semicolon | Token::Done | Token::EOF) { self.advance(); }
+        if *self.peek() == Token::Semicolon { self.advance(); }
+    }
+
+    /// How a token is shown in a diagnostic.
+    fn describe(&self, token: &Token) -> String {
+        match token {
+            Token::Identifier(s) => format!("`{}`", self.name(*s)),
+            Token::Number(n) | Token::Float(n) => format!("`{}`", n),
+            Token::StringLit(_) => "a string".into(),
+            Token::RawText(_) => "a block body".into(),
+            Token::EOF => "the end of the file".into(),
+            Token::AtomicAdd => "`atomic_add`".into(),
+            Token::OnInterrupt => "`on_interrupt`".into(),
+            other => match punctuation(other) {
+                Some(p) => format!("`{}`", p),
+                None => format!("`{:?}`", other).to_lowercase(),
+            },
+        }
+    }
+
+    fn parse_node(&mut self) -> Stmt {
+        let start = self.span();
         match self.peek() {
             Token::Get => {
                 self.advance();
-                let filename = if let Token::Identifier(s) = self.advance() { s } else { "lib".into() };
-                let path = format!("{}.hmr", filename);
-                match fs::read_to_string(&path) {
-                    Ok(content) => Stmt::MergeBlock(content),
-                    Err(_) => Stmt::AsmBlock(format!("// Error: File not found {}.hmr", filename)),
+                let Some(first) = self.expect_name("a file name") else { return Stmt::AsmBlock(String::new()) };
+                let mut name = self.name(first);
+                // `Get std.ecs` names one of the modules built into the compiler
+                while *self.peek() == Token::Dot && self.continues_line() {
+                    self.advance();
+                    let Some(part) = self.expect_name("a module name") else { break };
+                    name = format!("{}.{}", name, self.name(part));
                 }
+                let id = self.session.items.alloc(Item::Import { name, file: String::new(), body: Vec::new() });
+                self.imports.push((id, start.line));
+                Stmt::Item(id)
             }
             Token::At => {
+                let line = start.line;
                 self.advance(); // @
-                let type_ident = if let Token::Identifier(s) = self.advance() { s } else { "".into() };
-                if self.peek() == Token::Is { self.advance(); }
-                
-                let mut content = String::new();
-                let mut last_was_comma = false;
-                let mut last_was_bracket = false;
-
-                while self.peek() != Token::Done && self.peek() != Token::EOF {
-                    let t = self.advance();
-                    match t {
-                        Token::Identifier(id) => {
-                            // Only add newline if this identifier looks like a new instruction
-                            // (not following a comma or an open bracket)
-                            if !content.is_empty() && !last_was_comma && !last_was_bracket {
-                                content.push('\n');
-                                content.push_str("    ");
-                            }
-                            content.push_str(&id);
-                            content.push(' ');
-                            last_was_comma = false;
-                            last_was_bracket = false;
-                        }
-                        Token::Number(n) => {
-                            content.push_str(&format!("{} ", n));
-                            last_was_comma = false;
-                            last_was_bracket = false;
-                        }
-                        Token::StringLit(s) => {
-                            content.push_str(&format!("\"{}\" ", s));
-                            last_was_comma = false;
-                            last_was_bracket = false;
-                        }
-                        Token::Comma => {
-                            content = content.trim_end().to_string();
-                            content.push_str(", ");
-                            last_was_comma = true;
-                        }
-                        Token::LeftBracket => {
-                            content.push_str("[ ");
-                            last_was_bracket = true;
-                        }
-                        Token::RightBracket => {
-                            content = content.trim_end().to_string();
-                            content.push_str("] ");
-                            last_was_comma = false;
-                            last_was_bracket = false;
-                        }
-                        Token::Plus => content.push_str("+ "),
-                        Token::Minus => content.push_str("- "),
-                        Token::Star => content.push_str("* "),
-                        Token::Slash => content.push_str("/ "),
-                        Token::Quest => content.push_str("? "),
-                        Token::Percent => content.push_str("% "),
-                        _ => {}
-                    }
-                }
-                if self.peek() == Token::Done { self.advance(); }
+                let type_ident = self.expect_name("a block kind such as `asm`").map(|k| self.name(k)).unwrap_or_default();
+                let emit = self.peek_word(kw::EMIT);
+                if emit { self.advance(); }
+                if *self.peek() == Token::Is { self.advance(); }
+
+                let content = if let Token::RawText(s) = self.peek() { self.advance(); s.clone() } else { String::new() };
+                self.expect_done(start, &format!("@{}", type_ident));
 
+                if emit {
+                    if type_ident != "python" { self.error(start, format!("only @python blocks can `emit`, not @{}", type_ident)); }
+                    let id = self.session.items.alloc(Item::Emit { script: dedent(&content), file: String::new(), body: Vec::new() });
+                    self.imports.push((id, line));
+                    return Stmt::Item(id);
+                }
                 match type_ident.as_str() {
                     "intel" => Stmt::IntelBlock(content.trim().to_string()),
-                    "python" => Stmt::PythonBlock(content.trim().to_string()),
+                    "python" => Stmt::PythonBlock { script: dedent(&content), line },
                     _ => Stmt::AsmBlock(content.trim().to_string()),
                 }
             }
             Token::Local => {
                 self.advance();
-                let name = if let Token::Identifier(s) = self.advance() { s } else { "tmp".into() };
-                if self.peek() == Token::Assign { self.advance(); }
-                if self.peek() == Token::New {
+                let name = self.expect_name("a variable name").unwrap_or_else(|| self.blank());
+                if *self.peek() == Token::Assign { self.advance(); } else { self.expected("`=`"); }
+                if *self.peek() == Token::New {
+                    self.advance();
+                    let cn = match self.parse_class_name() {
+                        Some(cn) => cn,
+                        None => {
+                            self.expected("a class name");
+                            self.blank()
+                        }
+                    };
+                    if *self.peek() == Token::LeftBracket {
+                        self.advance();
+                        let len = match self.peek() {
+                            Token::Number(n) | Token::Float(n) if self.continues_line() => { self.advance(); *n as usize }
+                            _ => {
+                                self.expected(&format!("the length of `{}` as a number", self.name(name)));
+                                0
+                            }
+                        };
+                        if *self.peek() == Token::RightBracket { self.advance(); } else { self.expected("`]`"); }
+                        Stmt::ArrayAlloc { var_name: name, class_name: cn, len }
+                    } else {
+                        let mut args = Vec::new();
+                        while self.operand_on_line(start.line) { args.push(self.parse_operand()); }
+                        Stmt::HeapAlloc { var_name: name, class_name: cn, args }
+                    }
+                } else if *self.peek() == Token::Copy {
+                    self.advance();
+                    let src = self.expect_name("an object to copy").unwrap_or_else(|| self.blank());
+                    Stmt::CopyAlloc { var_name: name, src }
+                } else if self.peek_word(kw::TEXT) {
+                    self.advance();
+                    Stmt::TextAlloc { var_name: name }
+                } else if self.peek_word(kw::INPUT) {
+                    self.advance();
+                    Stmt::Input { var_name: name }
+                } else if self.peek_word(kw::ARRAY) && matches!(self.tokens.get(self.pos + 1), Some(Token::Number(_) | Token::Float(_))) {
+                    self.advance();
+                    let n = self.expect_number();
+                    if n < 0.0 || n.fract() != 0.0 {
+                        self.error(start, format!("the length of `{}` must be a whole number, not {}", self.name(name), n));
+                    }
+                    Stmt::NumArrayAlloc { var_name: name, len: n as usize }
+                } else if self.peek_word(kw::MAP) {
                     self.advance();
-                    let cn = if let Token::Identifier(s) = self.advance() { s } else { "Object".into() };
-                    Stmt::HeapAlloc { var_name: name, class_name: cn }
+                    Stmt::MapAlloc { var_name: name }
+                } else if matches!(self.tokens.get(self.pos + 1), Some(Token::Identifier(w)) if *w == kw::GET)
+                    && matches!(self.tokens.get(self.pos + 2), Some(Token::StringLit(_))) {
+                    let map = self.parse_path();
+                    self.advance(); // get
+                    let key = if let Token::StringLit(k) = self.advance() { k.clone() } else { String::new() };
+                    Stmt::MapGet { map, key, dest: name }
+                } else if self.peek_word(kw::FIND) && matches!(self.tokens.get(self.pos + 1), Some(Token::Identifier(_))) {
+                    self.advance();
+                    let array = self.expect_name("an array to search").unwrap_or_else(|| self.blank());
+                    let value = self.parse_operand();
+                    let field = self.parse_by();
+                    Stmt::Find { array, value, field, dest: name }
+                } else if *self.peek() == Token::Run {
+                    self.parse_run(Some(name))
+                } else if *self.peek() == Token::Call {
+                    self.advance();
+                    self.parse_call(Some(name))
                 } else {
-                    let val = if let Token::Number(n) = self.advance() { n } else { 0.0 };
-                    Stmt::LocalAssign { name, value: val }
+                    let value = self.parse_expr();
+                    if *self.peek() != Token::Arrow { return Stmt::LocalAssign { name, value }; }
+                    let first = match value {
+                        Expr::Num(n) | Expr::Float(n) => Operand::Num(n),
+                        Expr::Path(p) => Operand::Path(p),
+                        Expr::Str(_) | Expr::Binary { .. } => {
+                            self.error(self.span(), "a pipeline starts with a number or a variable; compute the value into a variable first".into());
+                            Operand::Num(0.0)
+                        }
+                    };
+                    self.parse_pipe(first, Some(name))
                 }
             }
             Token::Class => {
                 self.advance();
-                let name = if let Token::Identifier(s) = self.advance() { s } else { "Unnamed".into() };
-                if self.peek() == Token::Is { self.advance(); }
+                let name = self.expect_name("a class name").unwrap_or_else(|| self.blank());
+                let mut params = Vec::new();
+                if *self.peek() == Token::Less {
+                    self.advance();
+                    while let Token::Identifier(p) = self.peek() {
+                        self.advance();
+                        params.push(*p);
+                        if *self.peek() == Token::Comma { self.advance(); }
+                    }
+                    if *self.peek() == Token::Greater { self.advance(); }
+                }
+                if *self.peek() == Token::Is { self.advance(); }
                 let mut fields = Vec::new();
-                while self.peek() != Token::Done && self.peek() != Token::EOF {
-                    if let Token::Identifier(s) = self.advance() { fields.push(s); }
-                    else { self.advance(); }
+                while *self.peek() != Token::Done && *self.peek() != Token::EOF {
+                    if let Token::Identifier(s) = self.advance() {
+                        let mut s = *s;
+                        // `field enemy: Enemy` reads the same as `enemy: Enemy`
+                        if s == kw::FIELD && let Token::Identifier(n) = self.peek() {
+                            self.advance();
+                            s = *n;
+                        }
+                        let mut class = None;
+                        if *self.peek() == Token::Colon {
+                            self.advance();
+                            class = self.parse_field_class();
+                        }
+                        // `hp 100` starts every new object's `hp` at 100
+                        let init = match self.peek() {
+                            Token::Number(_) | Token::Float(_) | Token::Minus if self.continues_line() => match self.parse_operand() {
+                                Operand::Num(n) => Some(n),
+                                Operand::Path(_) => None,
+                            },
+                            _ => None,
+                        };
+                        fields.push(Field { name: s, class, init });
+                    } else {
+                        let what = self.describe(&self.tokens[self.pos - 1]);
+                        self.error(self.spans[self.pos - 1], format!("expected a field name, found {}", what));
+                    }
                 }
-                if self.peek() == Token::Done { self.advance(); }
-                Stmt::ClassDef { name, fields }
+                self.expect_done(start, "class");
+                self.item(Item::Class { name, params, fields })
             }
             Token::Print => {
                 self.advance();
-                match self.peek() {
-                    Token::StringLit(s) => {
-                        self.advance();
-                        Stmt::PrintString(s)
-                    },
-                    _ => {
-                        let path = self.parse_path();
-                        let name = path.get(0).cloned().unwrap_or("".into());
-                        Stmt::PrintVar(name)
+                self.parse_print()
+            }
+            Token::Eprint => {
+                self.advance();
+                let print = self.parse_print();
+                Stmt::Eprint(self.session.alloc_stmt(print, start.line))
+            }
+            Token::If => {
+                self.advance();
+                self.parse_if(start, false)
+            }
+            Token::While => {
+                self.advance();
+                let first = self.parse_cmp();
+                let cond = self.parse_cond_from(first);
+                while matches!(self.peek(), Token::Do | Token::Is) { self.advance(); }
+                let body = self.parse_then_body(start, "while");
+                Stmt::WhileStmt { cond, body }
+            }
+            Token::Match => {
+                self.advance();
+                let subject = self.expect_name("a variable to match on").unwrap_or_else(|| self.blank());
+                if *self.peek() == Token::Is { self.advance(); }
+                let mut arms: Vec<(String, Vec<StmtId>)> = Vec::new();
+                let mut default = Vec::new();
+                let mut in_default = false;
+                loop {
+                    match (self.peek(), self.tokens.get(self.pos + 1)) {
+                        (Token::Done | Token::EOF, _) => break,
+                        (Token::StringLit(s), Some(Token::Then)) => {
+                            self.advance();
+                            self.advance();
+                            arms.push((s.clone(), Vec::new()));
+                            in_default = false;
+                        }
+                        (Token::Identifier(w), _) if *w == kw::ELSE => {
+                            self.advance();
+                            if *self.peek() == Token::Then { self.advance(); }
+                            in_default = true;
+                        }
+                        _ => {
+                            let stmt = self.parse_statement();
+                            match arms.last_mut() {
+                                Some((_, body)) if !in_default => body.push(stmt),
+                                _ => default.push(stmt),
+                            }
+                        }
                     }
                 }
+                self.expect_done(start, "match");
+                Stmt::Match { subject, arms, default }
             }
-            Token::If => {
+            Token::Choose => {
                 self.advance();
-                if self.peek() == Token::Quest {
-                    self.advance(); // ?
-                    while matches!(self.peek(), Token::Less | Token::Percent) { self.advance(); }
-                    let chance = if let Token::Number(n) = self.advance() { n } else { 0.0 };
-                    while matches!(self.peek(), Token::Then | Token::Is) { self.advance(); }
-                    let mut body = Vec::new();
-                    while self.peek() != Token::Done && self.peek() != Token::EOF {
-                        body.push(self.parse_statement());
+                if *self.peek() == Token::Is { self.advance(); }
+                let mut arms: Vec<(u32, Vec<StmtId>)> = Vec::new();
+                loop {
+                    match (self.peek(), self.tokens.get(self.pos + 1)) {
+                        (Token::Done | Token::EOF, _) => break,
+                        (Token::Number(n) | Token::Float(n), Some(Token::Then)) => {
+                            let at = self.span();
+                            if n.fract() != 0.0 {
+                                self.errors.push((at, format!("choose weight {} must be a whole number", n)));
+                            } else if *n == 0.0 {
+                                self.warnings.push((at, "choose arm with weight 0 never runs".into()));
+                            }
+                            arms.push((*n as u32, Vec::new()));
+                            self.advance();
+                            self.advance();
+                        }
+                        _ => {
+                            let at = self.span();
+                            let stmt = self.parse_statement();
+                            match arms.last_mut() {
+                                Some((_, body)) => body.push(stmt),
+                                None => self.errors.push((at, "expected `<weight> then` to start a `choose` arm".into())),
+                            }
+                        }
                     }
-                    if self.peek() == Token::Done { self.advance(); }
-                    Stmt::ProbIf { chance, body }
-                } else {
-                    let p = self.parse_path(); 
-                    let op = self.advance();
-                    let val = if let Token::Number(n) = self.advance() { n } else { 0.0 };
-                    while matches!(self.peek(), Token::Then | Token::Is) { self.advance(); }
-                    let mut body = Vec::new();
-                    while self.peek() != Token::Done && self.peek() != Token::EOF {
-                        body.push(self.parse_statement());
+                }
+                self.expect_done(start, "choose");
+                let total: u32 = arms.iter().map(|(w, _)| w).sum();
+                if total == 0 || total > 4095 {
+                    self.errors.push((start, format!("choose weights add up to {}; they must total 1-4095", total)));
+                }
+                Stmt::Choose { arms }
+            }
+            Token::Identifier(s) if *s == kw::MACHINE
+                && matches!(self.tokens.get(self.pos + 1), Some(Token::Identifier(_)))
+                && matches!(self.tokens.get(self.pos + 2), Some(Token::Is | Token::Do)) => {
+                self.advance();
+                let name = self.expect_name("a machine name").unwrap_or_else(|| self.blank());
+                self.advance(); // is
+                let mut states: Vec<(Symbol, Vec<StmtId>)> = Vec::new();
+                while !matches!(self.peek(), Token::Done | Token::EOF) {
+                    if self.peek_word(kw::STATE) && matches!(self.tokens.get(self.pos + 1), Some(Token::Identifier(_))) {
+                        self.advance();
+                        let state = self.expect_name("a state name").unwrap_or_else(|| self.blank());
+                        states.push((state, Vec::new()));
+                        continue;
+                    }
+                    let at = self.span();
+                    let stmt = self.parse_statement();
+                    match states.last_mut() {
+                        Some((_, body)) => body.push(stmt),
+                        None => self.errors.push((at, "expected `state <name>` to start a `machine` state".into())),
                     }
-                    if self.peek() == Token::Done { self.advance(); }
-                    Stmt::IfStmt { path: p, op, rhs_val: val, body }
                 }
+                self.expect_done(start, "machine");
+                Stmt::Machine { name, states }
             }
-            Token::While => {
+            Token::Identifier(_) if self.peek_command(kw::GO) => {
+                self.advance();
+                Stmt::Go(self.expect_name("a state to go to").unwrap_or_else(|| self.blank()))
+            }
+            Token::Identifier(_) if self.peek_command(kw::DELETE) => {
+                self.advance();
+                Stmt::Delete(self.expect_name("an object to delete").unwrap_or_else(|| self.blank()))
+            }
+            Token::For => {
+                self.advance();
+                let var = self.expect_name("a loop variable").unwrap_or_else(|| self.blank());
+                if self.peek_word(kw::IN) { self.advance(); } else { self.expected("`in`"); }
+                let array = self.expect_name("an array to loop over").unwrap_or_else(|| self.blank());
+                while matches!(self.peek(), Token::Do | Token::Is) { self.advance(); }
+                let body = self.parse_then_body(start, "for");
+                Stmt::ForEach { var, array, body }
+            }
+            Token::Identifier(s) if *s == kw::PUB && matches!(self.tokens.get(self.pos + 1), Some(Token::Func)) => {
+                self.advance();
+                self.parse_func(start, true)
+            }
+            Token::Func => self.parse_func(start, false),
+            Token::Identifier(s) if *s == kw::EVERY
+                && matches!(self.tokens.get(self.pos + 1), Some(Token::Number(_) | Token::Float(_) | Token::Identifier(_)))
+                && matches!(self.tokens.get(self.pos + 2), Some(Token::Identifier(w)) if *w == kw::MS) => {
+                self.advance();
+                let period = self.parse_operand();
+                self.advance(); // ms
+                while matches!(self.peek(), Token::Do | Token::Is) { self.advance(); }
+                let body = self.parse_then_body(start, "every");
+                Stmt::Every { period, body }
+            }
+            Token::Identifier(s) if *s == kw::AFTER
+                && matches!(self.tokens.get(self.pos + 1), Some(Token::Number(_) | Token::Float(_) | Token::Identifier(_)))
+                && (matches!(self.tokens.get(self.pos + 2), Some(Token::Do | Token::Is))
+                    || matches!(self.tokens.get(self.pos + 2), Some(Token::Identifier(w)) if *w == kw::MS)) => {
                 self.advance();
-                let p = self.parse_path();
-                let op = self.advance();
-                let val = if let Token::Number(n) = self.advance() { n } else { 0.0 };
+                let delay = self.parse_operand();
+                if matches!(self.peek(), Token::Identifier(w) if *w == kw::MS) { self.advance(); }
                 while matches!(self.peek(), Token::Do | Token::Is) { self.advance(); }
+                let body = self.parse_then_body(start, "after");
+                Stmt::After { delay, body }
+            }
+            Token::Identifier(_) if self.peek_command(kw::SORT) => {
+                self.advance();
+                let array = self.expect_name("an array to sort").unwrap_or_else(|| self.blank());
+                let field = self.parse_by();
+                Stmt::Sort { array, field }
+            }
+            Token::Identifier(_) if self.peek_command(kw::CLS) => {
+                self.advance();
+                Stmt::Cls
+            }
+            Token::Identifier(_) if self.peek_command(kw::CURSOR) => {
+                self.advance();
+                let mut at = Vec::new();
+                while at.len() < 2 && self.operand_on_line(start.line) { at.push(self.parse_operand()); }
+                if at.len() < 2 {
+                    self.error(start, "`cursor` takes a column and a row".into());
+                    at.resize(2, Operand::Num(1.0));
+                }
+                let y = at.pop().unwrap();
+                let x = at.pop().unwrap();
+                Stmt::Cursor { x, y }
+            }
+            Token::Identifier(_) if self.peek_command(kw::COLOR) => {
+                self.advance();
+                Stmt::Color(self.operand_on_line(start.line).then(|| self.parse_operand()))
+            }
+            Token::Call => {
+                self.advance();
+                self.parse_call(None)
+            }
+            Token::Return => {
+                self.advance();
+                if self.operand_on_line(start.line) {
+                    Stmt::Return(Some(self.parse_operand()))
+                } else {
+                    Stmt::Return(None)
+                }
+            }
+            Token::Spawn => {
+                self.advance();
+                if *self.peek() == Token::Is { self.advance(); }
+                let body = self.parse_body(start, "spawn");
+                Stmt::Spawn(body)
+            }
+            Token::Join => {
+                self.advance();
+                Stmt::Join
+            }
+            Token::Assert => {
+                self.advance();
+                let first = self.parse_cmp();
+                Stmt::Assert(self.parse_cond_from(first))
+            }
+            Token::Label | Token::Goto => {
+                let goto = *self.advance() == Token::Goto;
+                let name = self.expect_name("a label name").unwrap_or_else(|| self.blank());
+                if goto { Stmt::Goto(name) } else { Stmt::Label(name) }
+            }
+            Token::OnInterrupt => {
+                self.advance();
+                if *self.peek() == Token::Is { self.advance(); }
+                let body = self.parse_body(start, "on_interrupt");
+                Stmt::OnInterrupt(body)
+            }
+            Token::Run => self.parse_run(None),
+            Token::Try => {
+                self.advance();
                 let mut body = Vec::new();
-                while self.peek() != Token::Done && self.peek() != Token::EOF {
+                while !matches!(self.peek(), Token::Rescue | Token::Done | Token::EOF) {
                     body.push(self.parse_statement());
                 }
-                if self.peek() == Token::Done { self.advance(); }
-                Stmt::WhileStmt { path: p, op, rhs_val: val, body }
+                let mut err = None;
+                let mut rescue = Vec::new();
+                if *self.peek() == Token::Rescue {
+                    let line = self.line();
+                    self.advance();
+                    if self.line() == line && let Token::Identifier(e) = self.peek() {
+                        self.advance();
+                        err = Some(*e);
+                    }
+                    while !matches!(self.peek(), Token::Done | Token::EOF) {
+                        rescue.push(self.parse_statement());
+                    }
+                }
+                self.expect_done(start, "try");
+                Stmt::Try { body, err, rescue }
+            }
+            Token::AtomicAdd => {
+                self.advance();
+                let path = self.parse_path();
+                let value = self.parse_operand();
+                Stmt::AtomicAdd { path, value }
+            }
+            Token::Lock => {
+                self.advance();
+                Stmt::Lock(self.parse_path())
+            }
+            Token::Unlock => {
+                self.advance();
+                Stmt::Unlock(self.parse_path())
+            }
+            Token::Handler => {
+                self.advance();
+                let kind = self.expect_name("a handler kind (`irq`, `fiq`, `sync` or `serror`)").unwrap_or_else(|| self.blank());
+                if *self.peek() == Token::Is { self.advance(); }
+                let body = self.parse_body(start, "handler");
+                Stmt::Handler { kind, body }
+            }
+            Token::Done => {
+                self.advance();
+                self.error(start, "`done` without a block to close".into());
+                Stmt::AsmBlock(String::new())
+            }
+            Token::Identifier(w) if (*w == kw::ELSE || *w == kw::ELIF) && self.tokens.get(self.pos + 1) != Some(&Token::Assign) => {
+                let word = self.name(*w);
+                self.advance();
+                self.error(start, format!("`{}` without an `if` before it", word));
+                Stmt::AsmBlock(String::new())
+            }
+            Token::Number(_) | Token::Float(_) | Token::Minus if matches!(&self.tokens[self.pos..], [Token::Number(_) | Token::Float(_), Token::Arrow, ..] | [Token::Minus, Token::Number(_) | Token::Float(_), Token::Arrow, ..]) => {
+                let negative = *self.peek() == Token::Minus;
+                if negative { self.advance(); }
+                let n = if negative { -self.expect_number() } else if let Token::Number(n) | Token::Float(n) = self.advance() { *n } else { 0.0 };
+                self.parse_pipe(Operand::Num(n), None)
             }
             _ => {
                 let path = self.parse_path();
-                if self.peek() == Token::Assign {
+                if *self.peek() == Token::Arrow {
+                    self.parse_pipe(Operand::Path(path), None)
+                } else if *self.peek() == Token::Assign {
                     self.advance();
-                    if let Token::Number(v) = self.peek() {
-                        self.advance();
-                        Stmt::FieldAssign { path, value: v }
-                    } else {
-                        // Handle math like 'hp = hp + 10' or compressed formats
-                        self.advance(); // Skip self-ref identifier if exists
-                        let op = self.advance();
-                        let val = if let Token::Number(v) = self.advance() { v } else { 0.0 };
-                        Stmt::FieldMath { path, op, rhs_val: val }
+                    // `a.b = c.d` stores the value (usually an object pointer),
+                    // anything else is arithmetic
+                    match self.parse_expr() {
+                        Expr::Path(src) => Stmt::RefAssign { path, src },
+                        value => Stmt::FieldAssign { path, value },
                     }
+                } else if self.peek_word(kw::PUT) && matches!(self.tokens.get(self.pos + 1), Some(Token::StringLit(_))) {
+                    self.advance();
+                    let key = if let Token::StringLit(k) = self.advance() { k.clone() } else { String::new() };
+                    let value = self.parse_operand();
+                    Stmt::MapPut { map: path, key, value }
+                } else if self.peek_word(kw::APPEND) {
+                    self.advance();
+                    let src = match self.peek() {
+                        Token::StringLit(s) if self.continues_line() => { self.advance(); TextSrc::Lit(s.clone()) }
+                        Token::Identifier(v) if self.continues_line() => { self.advance(); TextSrc::Var(*v) }
+                        _ => {
+                            self.expected("a string or a text variable");
+                            TextSrc::Lit(String::new())
+                        }
+                    };
+                    Stmt::TextAppend { text: path, src }
                 } else {
-                    self.advance(); // Safety: always consume at least one token
-                    Stmt::AsmBlock("nop".into())
+                    if !path.is_empty() { self.expected("`=`"); }
+                    // Always consume at least one token, so parsing moves on
+                    if self.span() == start { self.advance(); }
+                    Stmt::AsmBlock(String::new())
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// How a symbol token is written in the source.
+fn punctuation(token: &Token) -> Option<&'static str> {
+    Some(match token {
+        Token::At => "@",
+        Token::Assign => "=",
+        Token::Equal => "==",
+        Token::Dot => ".",
+        Token::Greater => ">",
+        Token::Less => "<",
+        Token::Plus => "+",
+        Token::Minus => "-",
+        Token::Star => "*",
+        Token::Slash => "/",
+        Token::Comma => ",",
+        Token::Quest => "?",
+        Token::Percent => "%",
+        Token::LeftBracket => "[",
+        Token::RightBracket => "]",
+        Token::LeftParen => "(",
+        Token::RightParen => ")",
+        Token::Colon => ":",
+        Token::Semicolon => ";",
+        Token::Arrow => "->",
+        _ => return None,
+    })
+}
+
+/// Strips surrounding blank lines and the indentation common to every
+/// non-blank line, so an indented @python body is valid top-level Python.
+/// Only spaces and tabs count as indentation, so the cut is always at a
+/// character boundary.
+fn dedent(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let indent = lines.iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    let body: Vec<&str> = lines.iter()
+        .map(|l| if l.trim().is_empty() { "" } else { &l[indent..] })
+        .collect();
+    body.join("\n").trim_matches('\n').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    /// Parses `src`, returning the session holding the statements, the
+    /// top-level statement ids and any syntax errors.
+    fn parse(src: &str) -> (Session, Vec<StmtId>, Vec<(Span, String)>) {
+        let mut session = Session::default();
+        let (tokens, spans) = Lexer::new(src, &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &spans, &mut session);
+        let ast = parser.parse_program();
+        let errors = std::mem::take(&mut parser.errors);
+        (session, ast, errors)
+    }
+
+    #[test]
+    fn token_stream_ends_with_one_eof() {
+        let mut session = Session::default();
+        let (tokens, lines) = Lexer::new("", &mut session.interner).tokenize();
+        assert_eq!(tokens, vec![Token::EOF]);
+        assert_eq!(lines.len(), 1);
+
+        let (tokens, _) = Lexer::new("print x\n", &mut session.interner).tokenize();
+        assert_eq!(tokens.last(), Some(&Token::EOF));
+        assert_eq!(tokens.iter().filter(|t| **t == Token::EOF).count(), 1);
+    }
+
+    #[test]
+    fn string_escapes_become_the_bytes_printed() {
+        let mut session = Session::default();
+        let src = r#"print "a\tb\n\"q\" \\ \e[0m \z" print "héllo → 日本""#;
+        let (tokens, _) = Lexer::new(src, &mut session.interner).tokenize();
+        assert_eq!(tokens[1], Token::StringLit("a\tb\n\"q\" \\ \x1b[0m \\z".to_string()));
+        let Token::StringLit(s) = &tokens[3] else { panic!("expected a string") };
+        assert_eq!((s.chars().count(), s.len()), (10, 17));
+    }
+
+    #[test]
+    fn comments_are_skipped_and_lines_still_counted() {
+        let mut session = Session::default();
+        let src = "# header\nprint x # trailing\n#[ two\nlines #[ nested ]# ]# print y\n@asm is\n    mov x0, #1\ndone # asm\n#[ open";
+        let mut lexer = Lexer::new(src, &mut session.interner);
+        let (tokens, spans) = lexer.tokenize();
+        assert_eq!(tokens.len(), 10, "{:?}", tokens);
+        assert_eq!((spans[0].line, spans[2].line, spans[3].col), (2, 4, 29));
+        assert_eq!(tokens[7], Token::RawText("\n    mov x0, #1\n".to_string()));
+        assert_eq!(lexer.errors, vec![(Span { line: 8, col: 1 }, "block comment is never closed with `]#`".to_string())]);
+    }
+
+    #[test]
+    fn advance_stops_at_eof() {
+        let mut session = Session::default();
+        let (tokens, lines) = Lexer::new("x", &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &lines, &mut session);
+        parser.advance();
+        assert_eq!(parser.advance(), &Token::EOF);
+        assert_eq!(parser.advance(), &Token::EOF);
+        assert_eq!(parser.pos, 1);
+    }
+
+    #[test]
+    fn missing_done_is_reported_at_block_start() {
+        let (_, ast, errors) = parse("local x = 1\nwhile x < 3 do\n    x = x + 1\n");
+        assert_eq!(ast.len(), 2);
+        assert_eq!(errors, vec![(Span { line: 2, col: 1 }, "`while` block is never closed with `done`".to_string())]);
+    }
+
+    #[test]
+    fn else_ending_the_line_opens_a_block() {
+        let (session, ast, errors) = parse("if x > 3 then print x else\n    print y\n    print z\ndone\nprint w\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(ast.len(), 2);
+        let Stmt::IfStmt { body, else_body, .. } = &session.stmts[ast[0]] else { panic!("expected if") };
+        assert_eq!((body.len(), else_body.len()), (1, 2));
+    }
+
+    #[test]
+    fn last_statement_of_truncated_block_is_kept() {
+        let (session, ast, errors) = parse("if x > 3 then\n    print x");
+        assert_eq!(errors.len(), 1);
+        let Stmt::IfStmt { body, .. } = &session.stmts[ast[0]] else { panic!("expected if") };
+        assert_eq!(body.len(), 1);
+        assert!(matches!(&session.stmts[body[0]], Stmt::PrintVar(name) if session.interner.resolve(*name) == "x"));
+    }
+
+    #[test]
+    fn truncated_statements_terminate() {
+        let blocks = [
+            "class", "class Hero is hp", "func", "func f a", "if", "if x", "if x >",
+            "if ?<%50>", "while x <", "for e in", "match", "match a is \"y\" then",
+            "try", "try rescue e", "spawn", "on_interrupt", "handler irq", "@asm is nop",
+            "@python", "@asm is\n    nop\n", "class Box<T> is\n    v: T\n",
+            "if x == 1 then\n    print x\nelif x == 2 then\n    print x\n",
+            "if x == 1 then\n    print x\nelif x == 2 then print x\n",
+            "if x == 1 then\n    print x\nelse\n    print y\n",
+            "if x == 1 then print x else\n    print y\n", "while x < 1 do\n",
+            "choose\n    1 then print x\n", "machine m is\n    state a\n",
+            "every 16 ms do\n", "after 10 ms do\n",
+        ];
+        for src in blocks {
+            let (_, _, errors) = parse(src);
+            assert!(errors.iter().any(|(_, e)| e.contains("never closed")), "{:?} should report its missing done: {:?}", src, errors);
+        }
+        let statements = [
+            "local", "local x =", "local w = new", "local w = new Enemy[", "print",
+            "x =", "x = x +", "a[", "a.", "call", "run", "atomic_add", "lock",
+            "m put \"k\"", "t append", ",", "]",
+        ];
+        for src in statements {
+            let (_, _, errors) = parse(src);
+            assert_eq!(errors.len(), 1, "{:?} reported {:?}", src, errors);
+        }
+        for src in ["return", "print hex", "print format \"%d\""] {
+            let (_, _, errors) = parse(src);
+            assert!(errors.is_empty(), "{:?} reported {:?}", src, errors);
+        }
+    }
+
+    #[test]
+    fn pub_is_only_a_keyword_before_func() {
+        let (session, ast, errors) = parse("pub func f a is\n    return a\ndone\nfunc g is\ndone\npub = 3\n");
+        assert!(errors.is_empty());
+        let item = |id| match &session.stmts[id] { Stmt::Item(item) => &session.items[*item], other => panic!("expected an item, got {:?}", other) };
+        assert!(matches!(item(ast[0]), Item::Func { name, public: true, .. } if session.interner.resolve(*name) == "f"));
+        assert!(matches!(item(ast[1]), Item::Func { public: false, .. }));
+        assert!(matches!(&session.stmts[ast[2]], Stmt::FieldAssign { path, .. } if session.interner.join(path, ".") == "pub"));
+    }
+
+    #[test]
+    fn terminal_commands_leave_variables_alone() {
+        let (session, ast, errors) = parse("cls\ncursor x 3\ncolor 2\ncolor\ncolor = 4\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(&session.stmts[ast[0]], Stmt::Cls));
+        assert!(matches!(&session.stmts[ast[1]], Stmt::Cursor { x: Operand::Path(p), y: Operand::Num(3.0) } if session.interner.join(p, ".") == "x"));
+        assert!(matches!(&session.stmts[ast[2]], Stmt::Color(Some(Operand::Num(2.0)))));
+        assert!(matches!(&session.stmts[ast[3]], Stmt::Color(None)));
+        assert!(matches!(&session.stmts[ast[4]], Stmt::FieldAssign { path, .. } if session.interner.join(path, ".") == "color"));
+    }
+
+    #[test]
+    fn every_needs_a_period_in_ms() {
+        let (session, ast, errors) = parse("every 16 ms do\n    print x\ndone\nevery = 2\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(&session.stmts[ast[0]], Stmt::Every { period: Operand::Num(16.0), body } if body.len() == 1));
+        assert!(matches!(&session.stmts[ast[1]], Stmt::FieldAssign { path, .. } if session.interner.join(path, ".") == "every"));
+    }
+
+    #[test]
+    fn machine_states_split_its_body() {
+        let (session, ast, errors) = parse("machine m is\n    state a\n        print x\n        go b\n    state b\ndone\nlocal state = 1\ngo = 2\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Stmt::Machine { name, states } = &session.stmts[ast[0]] else { panic!("expected a machine") };
+        assert_eq!(session.interner.resolve(*name), "m");
+        assert_eq!(states.iter().map(|(s, body)| (session.interner.resolve(*s), body.len())).collect::<Vec<_>>(), [("a", 2), ("b", 0)]);
+        assert!(matches!(&session.stmts[states[0].1[1]], Stmt::Go(s) if session.interner.resolve(*s) == "b"));
+        assert!(matches!(&session.stmts[ast[1]], Stmt::LocalAssign { name, .. } if session.interner.resolve(*name) == "state"));
+        assert!(matches!(&session.stmts[ast[2]], Stmt::FieldAssign { path, .. } if session.interner.join(path, ".") == "go"));
+    }
+
+    #[test]
+    fn fields_take_starting_values_and_new_takes_arguments() {
+        let (session, ast, errors) = parse("class P is\n    hp 100 mana -5\n    speed: float 1.5\n    name\ndone\nlocal p = new P 1 x\nlocal q = new P\nprint q\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Stmt::Item(id) = session.stmts[ast[0]] else { panic!("expected a class") };
+        let Item::Class { fields, .. } = &session.items[id] else { panic!("expected a class") };
+        assert_eq!(fields.iter().map(|f| (session.interner.resolve(f.name), f.init)).collect::<Vec<_>>(),
+            [("hp", Some(100.0)), ("mana", Some(-5.0)), ("speed", Some(1.5)), ("name", None)]);
+        assert!(matches!(&session.stmts[ast[1]], Stmt::HeapAlloc { args, .. } if args.len() == 2));
+        assert!(matches!(&session.stmts[ast[2]], Stmt::HeapAlloc { args, .. } if args.is_empty()));
+    }
+
+    #[test]
+    fn after_takes_ms_or_leaves_it_out() {
+        let (session, ast, errors) = parse("after 100 ms do print x
+after delay do
+    print y
+done
+after = 2
+");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(&session.stmts[ast[0]], Stmt::After { delay: Operand::Num(100.0), body } if body.len() == 1));
+        assert!(matches!(&session.stmts[ast[1]], Stmt::After { delay: Operand::Path(p), .. } if session.interner.join(p, ".") == "delay"));
+        assert!(matches!(&session.stmts[ast[2]], Stmt::FieldAssign { path, .. } if session.interner.join(path, ".") == "after"));
+    }
+
+    #[test]
+    fn parentheses_group_before_precedence() {
+        let (session, ast, errors) = parse("local x = (a + b) * 2\nlocal y = a + b * 2\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Stmt::LocalAssign { value: Expr::Binary { op: Token::Star, lhs, .. }, .. } = &session.stmts[ast[0]] else { panic!("expected `*` at the top") };
+        assert!(matches!(session.exprs[*lhs], Expr::Binary { op: Token::Plus, .. }));
+        let Stmt::LocalAssign { value: Expr::Binary { op: Token::Plus, rhs, .. }, .. } = &session.stmts[ast[1]] else { panic!("expected `+` at the top") };
+        assert!(matches!(session.exprs[*rhs], Expr::Binary { op: Token::Star, .. }));
+    }
+
+    #[test]
+    fn field_assignments_take_expressions() {
+        let (session, ast, errors) = parse("hero.hp = hero.hp * 2 + (y - 3)\nhero.target = goblin\nx = -x\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Stmt::FieldAssign { path, value: Expr::Binary { op: Token::Plus, lhs, .. } } = &session.stmts[ast[0]] else { panic!("expected `+` at the top") };
+        assert_eq!(session.interner.join(path, "."), "hero.hp");
+        assert!(matches!(session.exprs[*lhs], Expr::Binary { op: Token::Star, .. }));
+        assert!(matches!(&session.stmts[ast[1]], Stmt::RefAssign { src, .. } if session.interner.join(src, ".") == "goblin"));
+        assert!(matches!(&session.stmts[ast[2]], Stmt::FieldAssign { value: Expr::Binary { op: Token::Minus, .. }, .. }));
+    }
+
+    #[test]
+    fn statements_end_at_newline_or_semicolon() {
+        let (_, ast, errors) = parse("local a = 1; local b = 2; cls;\nprint a\n");
+        assert_eq!(ast.len(), 4);
+        assert!(errors.is_empty(), "{:?}", errors);
+        // An operator starting a line doesn't continue the expression above
+        let (session, ast, errors) = parse("local b = a\n* 2\n");
+        assert!(matches!(&session.stmts[ast[0]], Stmt::LocalAssign { value: Expr::Path(_), .. }));
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        let (_, ast, errors) = parse("print x local y = 2\nprint y\n");
+        assert_eq!(ast.len(), 2);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].1.contains("`local`"), "{:?}", errors);
+    }
+
+    #[test]
+    fn keywords_fold_case_unless_strict() {
+        let src = "Local Hp = 3\nIF Hp > 1 THEN PRINT Hp\nGET lib\nlocal v = m get \"k\"\n";
+        let mut session = Session::default();
+        let mut lexer = Lexer::new(src, &mut session.interner);
+        lexer.strict_case = false;
+        let (tokens, _) = lexer.tokenize();
+        assert_eq!(tokens[0], Token::Local);
+        assert_eq!(tokens[1], Token::Identifier(session.interner.intern("Hp")), "identifiers keep their case");
+        assert!(tokens.contains(&Token::If) && tokens.contains(&Token::Print) && tokens.contains(&Token::Get));
+        assert!(tokens.contains(&Token::Identifier(kw::GET)), "lowercase `get` is still the map lookup");
+        let (tokens, _) = Lexer::new(src, &mut session.interner).tokenize();
+        assert_ne!(tokens[0], Token::Local);
+    }
+
+    #[test]
+    fn identifiers_may_use_any_script() {
+        let mut session = Session::default();
+        let (tokens, spans) = Lexer::new("local größe = 1\n_速度2 = größe\n", &mut session.interner).tokenize();
+        assert_eq!(tokens[1], Token::Identifier(session.interner.intern("größe")));
+        assert_eq!(tokens[4], Token::Identifier(session.interner.intern("_速度2")));
+        assert_eq!(spans[4], Span { line: 2, col: 1 });
+        // Columns count characters, not bytes
+        assert_eq!(spans[6], Span { line: 2, col: 8 });
+    }
+
+    #[test]
+    fn identifiers_follow_unicode_xid() {
+        let mut session = Session::default();
+        let mut lexer = Lexer::new("local cafe\u{301} = 1\nlocal \u{2160} = 2\n", &mut session.interner);
+        let (tokens, _) = lexer.tokenize();
+        assert_eq!(lexer.errors, vec![(Span { line: 2, col: 7 }, "unexpected character `\u{2160}`".to_string())]);
+        assert_eq!(tokens[1], Token::Identifier(session.interner.intern("cafe\u{301}")));
+    }
+
+    #[test]
+    fn numbers_take_separators_and_exponents() {
+        let mut session = Session::default();
+        let (tokens, _) = Lexer::new("1_000_000 1.5e3 25E-2 2e+1 3each", &mut session.interner).tokenize();
+        assert_eq!(&tokens[..5], &[Token::Number(1_000_000.0), Token::Float(1500.0), Token::Float(0.25), Token::Float(20.0), Token::Number(3.0)]);
+        assert_eq!(tokens[5], Token::Identifier(session.interner.intern("each")));
+    }
+
+    #[test]
+    fn malformed_numbers_are_reported() {
+        let mut session = Session::default();
+        let mut lexer = Lexer::new("local a = 1.5\nlocal v = 1.2.3\n", &mut session.interner);
+        lexer.tokenize();
+        assert_eq!(lexer.errors, vec![(Span { line: 2, col: 11 }, "malformed number `1.2.3`".to_string())]);
+    }
+
+    #[test]
+    fn unclosed_strings_stop_at_the_end_of_the_line() {
+        let mut session = Session::default();
+        let mut lexer = Lexer::new("print \"ok\"\nprint \"oops\nprint x\n", &mut session.interner);
+        let (tokens, _) = lexer.tokenize();
+        assert_eq!(lexer.errors, vec![(Span { line: 2, col: 7 }, "string is never closed with `\"`".to_string())]);
+        assert_eq!(tokens[3], Token::StringLit("oops".to_string()));
+        assert_eq!(tokens[5], Token::Identifier(session.interner.intern("x")));
+    }
+
+    #[test]
+    fn get_leaves_an_import_for_the_resolver() {
+        let mut session = Session::default();
+        let (tokens, spans) = Lexer::new("print 1\nGet lib\n", &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &spans, &mut session);
+        let ast = parser.parse_program();
+        let imports = std::mem::take(&mut parser.imports);
+        assert_eq!(imports.len(), 1);
+        let (id, line) = imports[0];
+        assert_eq!(line, 2);
+        assert!(matches!(session.stmts[ast[1]], Stmt::Item(item) if item == id));
+        assert!(matches!(&session.items[id], Item::Import { name, body, .. } if name == "lib" && body.is_empty()));
+    }
+
+    #[test]
+    fn dedent_only_strips_spaces_and_tabs() {
+        assert_eq!(dedent("\n  a = 1\n\u{3000}b = 2\n"), "  a = 1\n\u{3000}b = 2");
+        assert_eq!(dedent("\n\u{3000}\u{3000}a\n\u{3000}b\n"), "\u{3000}\u{3000}a\n\u{3000}b");
+        assert_eq!(dedent("\n\tif x:\n\t    y()\n"), "if x:\n    y()");
+    }
+
+    #[test]
+    fn python_emit_leaves_its_script_for_the_resolver() {
+        let (session, ast, errors) = parse("@python emit is\n    print('local a = 1')\ndone\n@asm emit is\n    nop\ndone\n");
+        assert_eq!(errors.iter().map(|(_, e)| e.as_str()).collect::<Vec<_>>(), ["only @python blocks can `emit`, not @asm"]);
+        let Stmt::Item(id) = session.stmts[ast[0]] else { panic!("expected an item") };
+        assert!(matches!(&session.items[id], Item::Emit { script, body, .. } if script.trim_end() == "print('local a = 1')" && body.is_empty()));
+    }
+
+    #[test]
+    fn stray_done_is_reported() {
+        let (session, ast, errors) = parse("print x\n  done\nprint x\n");
+        assert_eq!(errors, vec![(Span { line: 2, col: 3 }, "`done` without a block to close".to_string())]);
+        assert!(matches!(&session.stmts[*ast.last().unwrap()], Stmt::PrintVar(name) if session.interner.resolve(*name) == "x"));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let (session, ast, errors) = parse("if a == 1 or b == 2 and c < 3 then\n    print a\ndone\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Stmt::IfStmt { cond, .. } = &session.stmts[ast[0]] else { panic!("expected if") };
+        let Cond::Or(lhs, rhs) = cond else { panic!("expected `or` at the top, got {:?}", cond) };
+        assert!(matches!(session.conds[*lhs], Cond::Cmp { .. }));
+        assert!(matches!(session.conds[*rhs], Cond::And(..)));
+    }
+
+    #[test]
+    fn statement_after_then_is_a_one_line_body() {
+        let (session, ast, errors) = parse("while x < 3 do x = x + 1\nif x > 5 then print \"big\"\nprint x\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(ast.len(), 3);
+        let Stmt::IfStmt { body, .. } = &session.stmts[ast[1]] else { panic!("expected if") };
+        assert_eq!(body.len(), 1);
+    }
+
+    #[test]
+    fn errors_carry_columns_and_parsing_goes_on() {
+        let (_, ast, errors) = parse("local x = 5\nlocal y =\nif x > then\n    print x\ndone\nlocal z 4\nprint x\n");
+        assert_eq!(ast.len(), 5);
+        let found: Vec<_> = errors.iter().map(|(span, e)| (span.line, span.col, e.as_str())).collect();
+        assert_eq!(found, vec![
+            (2, 9, "expected a value after `=`, found the end of the line"),
+            (3, 8, "expected a value after `>`, found `then`"),
+            (6, 9, "expected `=` after `z`, found `4`"),
+        ]);
+    }
+
+    #[test]
+    fn elif_nests_an_if_in_the_else_branch() {
+        let src = "if x == 1 then\n    print 1\nelif x == 2 then\n    print 2\nelse\n    print 3\ndone\nprint x\n";
+        let (session, ast, errors) = parse(src);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(ast.len(), 2);
+        let Stmt::IfStmt { else_body, .. } = &session.stmts[ast[0]] else { panic!("expected if") };
+        assert_eq!(else_body.len(), 1);
+        assert_eq!(session.stmt_line(else_body[0]), 3);
+        let Stmt::IfStmt { body, else_body, .. } = &session.stmts[else_body[0]] else { panic!("expected elif") };
+        assert_eq!((body.len(), else_body.len()), (1, 1));
+    }
+
+    #[test]
+    fn one_line_if_takes_an_else() {
+        let (session, ast, errors) = parse("if x > 9 then print \"big\" else print x\nprint y\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(ast.len(), 2);
+        let Stmt::IfStmt { body, else_body, .. } = &session.stmts[ast[0]] else { panic!("expected if") };
+        assert!(matches!(&session.stmts[body[0]], Stmt::PrintString(s) if s == "big"));
+        assert!(matches!(&session.stmts[else_body[0]], Stmt::PrintVar(name) if session.interner.resolve(*name) == "x"));
+    }
+
+    #[test]
+    fn else_without_if_is_reported() {
+        let (_, _, errors) = parse("print x\nelse\nprint y\n");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!((errors[0].0.line, errors[0].1.as_str()), (2, "`else` without an `if` before it"));
+    }
+
+    #[test]
+    fn pipeline_nests_each_call_in_the_next() {
+        let (session, ast, errors) = parse("local hp = hero -> heal 5\n    -> show\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Stmt::Call { name, args, dest, from: Some(inner) } = &session.stmts[ast[0]] else { panic!("expected a call") };
+        let dest = dest.map(|d| session.interner.resolve(d));
+        assert_eq!((session.interner.resolve(*name), args.len(), dest), ("show", 0, Some("hp")));
+        let Stmt::Call { name, args, from: None, .. } = &session.stmts[*inner] else { panic!("expected the inner call") };
+        assert_eq!(session.interner.resolve(*name), "heal");
+        assert!(matches!(&args[..], [Operand::Path(p), Operand::Num(n)] if session.interner.join(p, ".") == "hero" && *n == 5.0));
+    }
+    #[test]
+    fn array_only_allocates_before_a_length() {
+        let (session, ast, errors) = parse("local xs = array 4\nlocal n = array\nlocal ys = array 1.5\n");
+        assert!(matches!(&session.stmts[ast[0]], Stmt::NumArrayAlloc { var_name, len: 4 } if session.interner.resolve(*var_name) == "xs"));
+        assert!(matches!(&session.stmts[ast[1]], Stmt::LocalAssign { .. }));
+        assert_eq!(errors, vec![(Span { line: 3, col: 1 }, "the length of `ys` must be a whole number, not 1.5".to_string())]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Finds and parses the files a program pulls in with `Get`, between
+//! parsing and code generation, so the generator only ever sees a fully
+//! resolved tree. Each file is read once, however many times it is
+//! imported, and a missing file or an import cycle is reported against
+//! the `Get` that caused it. `@python emit` blocks are run here too, and
+//! what they print parsed in their place.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::ast::{Item, ItemId, StmtId};
+use crate::generator::{run_python, PythonError};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::session::Session;
+use crate::stdlib;
+
+/// The file `Get name` refers to from `importer`: `name.hmr` in the same
+/// directory, or for `Get std.name` the std module built into the compiler.
+pub fn import_path(importer: &str, name: &str) -> String {
+    if let Some(module) = name.strip_prefix("std.") {
+        return format!("{}/{}.hmr", stdlib::DIR, module);
+    }
+    let dir = Path::new(importer).parent().unwrap_or(Path::new(""));
+    dir.join(format!("{}.hmr", name)).to_string_lossy().into_owned()
+}
+
+/// Diagnostics from the included files, as `file:line: message`.
+#[derive(Default)]
+pub struct Report {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// How the resolver reads source and runs `@python emit` scripts.
+pub struct Options {
+    pub strict_case: bool,
+    /// Run the scripts of included files as well as the root's.
+    pub allow_run: bool,
+    /// Leave a block empty with a warning when python3 can't be started.
+    pub allow_missing_python: bool,
+    pub exec_timeout: Duration,
+}
+
+/// Resolves the `Get`s and `@python emit`s of `root` (as its parser
+/// collected them), and of every file they reach, filling in each
+/// `Item::Import` and `Item::Emit`.
+pub fn resolve(root: &str, imports: Vec<(ItemId, usize)>, session: &mut Session, options: &Options) -> Report {
+    let root_key = session.sources.loader().canonical(root);
+    let mut resolver = Resolver {
+        session,
+        options,
+        stack: vec![(root_key, root.to_string())],
+        loaded: HashSet::new(),
+        report: Report::default(),
+    };
+    resolver.visit(root, imports);
+    resolver.report
+}
+
+struct Resolver<'s> {
+    session: &'s mut Session,
+    options: &'s Options,
+    /// The chain of imports being followed, as (identity, path as written).
+    stack: Vec<(PathBuf, String)>,
+    loaded: HashSet<PathBuf>,
+    report: Report,
+}
+
+impl Resolver<'_> {
+    fn visit(&mut self, file: &str, imports: Vec<(ItemId, usize)>) {
+        for (id, line) in imports {
+            let name = match &self.session.items[id] {
+                Item::Import { name, .. } => name.clone(),
+                Item::Emit { script, .. } => {
+                    let script = script.clone();
+                    self.emit(file, line, id, script);
+                    continue;
+                }
+                _ => continue,
+            };
+            let path = import_path(file, &name);
+            let key = self.session.sources.loader().canonical(&path);
+            if let Some(start) = self.stack.iter().position(|(f, _)| *f == key) {
+                let mut chain: Vec<&str> = self.stack[start..].iter().map(|(_, p)| p.as_str()).collect();
+                chain.push(&path);
+                self.report.errors.push(format!("{}:{}: `Get {}` is an import cycle: {}", file, line, name, chain.join(" -> ")));
+                continue;
+            }
+            if !self.loaded.insert(key.clone()) {
+                self.session.items[id] = Item::Import { name, file: path, body: Vec::new() };
+                continue;
+            }
+            let content = match self.session.sources.load(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    self.report.errors.push(format!("{}:{}: cannot read `{}` for `Get {}`: {}", file, line, path, name, e));
+                    continue;
+                }
+            };
+            debug!("{}:{}: `Get {}` reads {}", file, line, name, path);
+            let (body, nested) = self.parse(&path, &content);
+            self.session.items[id] = Item::Import { name, file: path.clone(), body };
+            self.stack.push((key, path.clone()));
+            self.visit(&path, nested);
+            self.stack.pop();
+        }
+    }
+
+    /// Runs the `@python emit` block `id` at `file:line` and parses what it
+    /// prints as the block's body. The output is named `file:line:@python`.
+    fn emit(&mut self, file: &str, line: usize, id: ItemId, script: String) {
+        let at = format!("{}:{}", file, line);
+        // Only the root file is on the stack while its own blocks run
+        if self.stack.len() > 1 && !self.options.allow_run {
+            self.report.errors.push(PythonError::refused(&at, &script));
+            return;
+        }
+        let output = match run_python(&script, self.options.exec_timeout) {
+            Ok(output) => output,
+            Err(e @ PythonError::Missing(_)) if self.options.allow_missing_python => {
+                self.report.warnings.push(format!("{} (skipped, --allow-missing-python)", e.report(&at, &script)));
+                return;
+            }
+            Err(e @ PythonError::Missing(_)) => {
+                self.report.errors.push(format!("{}\n  hint: install python3 or pass --allow-missing-python", e.report(&at, &script)));
+                return;
+            }
+            Err(e) => {
+                self.report.errors.push(e.report(&at, &script));
+                return;
+            }
+        };
+        let path = format!("{}:@python", at);
+        debug!("{}: @python emit printed {} lines", at, output.lines().count());
+        self.session.sources.add(&path, &output);
+        let (body, nested) = self.parse(&path, &output);
+        self.session.items[id] = Item::Emit { script, file: path.clone(), body };
+        self.visit(&path, nested);
+    }
+
+    /// Parses an included file, returning its statements and its own `Get`s.
+    fn parse(&mut self, path: &str, content: &str) -> (Vec<StmtId>, Vec<(ItemId, usize)>) {
+        let mut lexer = Lexer::new(content, &mut self.session.interner);
+        lexer.strict_case = self.options.strict_case;
+        let (tokens, spans) = lexer.tokenize();
+        let mut errors = std::mem::take(&mut lexer.errors);
+        let mut parser = Parser::new(&tokens, &spans, self.session);
+        let body = parser.parse_program();
+        // A character the lexer dropped usually derails the rest of its line too
+        let bad_lines: Vec<usize> = errors.iter().map(|(span, _)| span.line).collect();
+        errors.extend(parser.errors.drain(..).filter(|(span, _)| !bad_lines.contains(&span.line)));
+        errors.sort_by_key(|(span, _)| *span);
+        self.report.errors.extend(errors.iter().map(|(span, e)| span.show(path, content, e)));
+        self.report.warnings.extend(parser.warnings.iter().map(|(span, w)| span.show(path, content, w)));
+        (body, std::mem::take(&mut parser.imports))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{MemoryLoader, SourceMap};
+
+    /// Parses `root` out of `files` (path, contents) and resolves its
+    /// `Get`s, without touching the disk.
+    fn resolve_in_memory(root: &str, files: &[(&str, &str)]) -> (Session, Report) {
+        let mut loader = MemoryLoader::default();
+        for (path, contents) in files { loader.add(path, contents); }
+        let mut session = Session::default();
+        session.sources = SourceMap::new(Box::new(loader));
+        let input = session.sources.load(root).unwrap();
+        let (tokens, spans) = Lexer::new(&input, &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &spans, &mut session);
+        parser.parse_program();
+        let imports = std::mem::take(&mut parser.imports);
+        let options = Options { strict_case: true, allow_run: false, allow_missing_python: false, exec_timeout: Duration::from_secs(30) };
+        let report = resolve(root, imports, &mut session, &options);
+        (session, report)
+    }
+
+    #[test]
+    fn imports_come_from_the_loader() {
+        let (session, report) = resolve_in_memory("game/main.hmr", &[
+            ("game/main.hmr", "Get lib\nGet ./lib\nprint 1\n"),
+            ("game/lib.hmr", "func f is\n    return 1\ndone\n"),
+        ]);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(session.sources.get("game/lib.hmr"), Some("func f is\n    return 1\ndone\n"));
+    }
+
+    #[test]
+    fn missing_files_and_cycles_are_reported() {
+        let (_, report) = resolve_in_memory("a.hmr", &[("a.hmr", "Get b\nGet nope\n"), ("b.hmr", "Get a\n")]);
+        assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+        assert!(report.errors[0].contains("import cycle: a.hmr -> b.hmr -> a.hmr"), "{:?}", report.errors);
+        assert!(report.errors[1].starts_with("a.hmr:2: cannot read `nope.hmr`"), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn std_modules_are_built_in() {
+        let (session, report) = resolve_in_memory("main.hmr", &[("main.hmr", "Get std.ecs\nGet std.nope\n")]);
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert!(report.errors[0].starts_with("main.hmr:2: cannot read `<std>/nope.hmr`"), "{:?}", report.errors);
+        assert!(session.sources.get("<std>/ecs.hmr").is_some_and(|text| text.contains("func ecs_spawn")));
+    }
+}
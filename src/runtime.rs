@@ -0,0 +1,590 @@
+//! Assembly routines emitted once per program when codegen needs them.
+//! Each routine is a leaf called with `bl` and may clobber x0-x9 and d0-d7;
+//! `clobbers` says exactly which registers one overwrites. The exceptions
+//! are `__hamer_text_append` and `__hamer_map_put`, which call the
+//! generator's `__hamer_heap_reserve` and so also change x10 and x11.
+
+/// x0 = value, x1 = base, x2 = minimum width, x3 = pad character,
+/// x4 = 1 to treat x0 as signed, x8 = file descriptor. Writes the digits
+/// of x0; zero padding goes between a '-' and the digits.
+pub const PRINT_NUM: &str = "
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+";
+
+/// x0 = the bits of a float, x8 = file descriptor. Writes it rounded to
+/// six decimals, dropping trailing zeros but keeping at least one, so 2.5
+/// prints as `2.5` and 3 as `3.0`. Values from 2^64 / 10^6 on saturate.
+pub const PRINT_FLOAT: &str = "
+__hamer_print_float:
+    sub sp, sp, #64
+    fmov d0, x0
+    mov x9, #0
+    fcmp d0, #0.0
+    b.pl .Lrt_pf_scale
+    fneg d0, d0
+    mov x9, #1
+.Lrt_pf_scale:
+    mov x1, #0x4240
+    movk x1, #0xf, lsl #16
+    ucvtf d1, x1
+    fmul d0, d0, d1
+    fcvtau x0, d0
+    cmp x0, #0
+    csel x9, xzr, x9, eq
+    udiv x2, x0, x1
+    msub x3, x2, x1, x0
+    add x5, sp, #64
+    mov x4, #6
+    mov x7, #0
+    mov x1, #10
+.Lrt_pf_frac:
+    udiv x6, x3, x1
+    msub x0, x6, x1, x3
+    mov x3, x6
+    cbnz x7, .Lrt_pf_keep
+    cbnz x0, .Lrt_pf_keep
+    cmp x4, #1
+    b.ne .Lrt_pf_next
+.Lrt_pf_keep:
+    mov x7, #1
+    add x0, x0, #48
+    sub x5, x5, #1
+    strb w0, [x5]
+.Lrt_pf_next:
+    subs x4, x4, #1
+    b.ne .Lrt_pf_frac
+    mov w0, #46
+    sub x5, x5, #1
+    strb w0, [x5]
+.Lrt_pf_whole:
+    udiv x6, x2, x1
+    msub x0, x6, x1, x2
+    add x0, x0, #48
+    sub x5, x5, #1
+    strb w0, [x5]
+    mov x2, x6
+    cbnz x2, .Lrt_pf_whole
+    cbz x9, .Lrt_pf_write
+    mov w0, #45
+    sub x5, x5, #1
+    strb w0, [x5]
+.Lrt_pf_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #64
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #64
+    ret
+";
+
+/// x0 = value, x1 = bits per digit (4 for hex, 1 for binary). Writes x0
+/// with a `0x`/`0b` prefix using shifts and masks instead of division.
+pub const PRINT_BITS: &str = "
+__hamer_print_bits:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x4, #1
+    lsl x4, x4, x1
+    sub x4, x4, #1
+.Lrt_pb_digit:
+    and x7, x0, x4
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pb_store
+    add x7, x7, #39
+.Lrt_pb_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    lsr x0, x0, x1
+    cbnz x0, .Lrt_pb_digit
+    cmp x1, #4
+    mov w7, #120
+    mov w6, #98
+    csel w7, w7, w6, eq
+    mov w6, #48
+    strb w7, [x5, #-1]
+    strb w6, [x5, #-2]!
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+";
+
+/// Slots a new map starts with; each slot is a (key, value) pair of 8-byte
+/// words. The table doubles before it gets more than 3/4 full.
+pub const MAP_SLOTS: usize = 32;
+
+/// Bytes before a map's table: its address, its slot count less one (the
+/// mask probing wraps with) and how many keys it holds.
+pub const MAP_HEADER: usize = 32;
+
+/// x0 = map, x1 = key, x2 = value. A key is the address of its record,
+/// whose first word is its hash; each distinct key has one record, so
+/// equal keys compare equal as addresses. Linear probing. A new key that
+/// would fill the table past 3/4 first moves every entry to a table twice
+/// the size, bumped off the x28 heap after making room with
+/// `__hamer_heap_reserve`, and the store starts over there.
+pub const MAP_PUT: &str = "
+__hamer_map_put:
+    ldp x3, x4, [x0]
+    ldr x5, [x1]
+.Lrt_mp_probe:
+    and x5, x5, x4
+    add x6, x3, x5, lsl #4
+    ldr x7, [x6]
+    cmp x7, x1
+    b.eq .Lrt_mp_store
+    cbz x7, .Lrt_mp_new
+    add x5, x5, #1
+    b .Lrt_mp_probe
+.Lrt_mp_new:
+    ldr x7, [x0, #16]
+    add x7, x7, #1
+    add x8, x4, #1
+    add x8, x8, x8, lsl #1
+    cmp x8, x7, lsl #2
+    b.lo .Lrt_mp_grow
+    str x7, [x0, #16]
+.Lrt_mp_store:
+    stp x1, x2, [x6]
+    ret
+.Lrt_mp_grow:
+    stp x30, x2, [sp, #-16]!
+    add x9, x4, #1
+    lsl x9, x9, #5
+    bl __hamer_heap_reserve
+    add x7, x4, #1
+    lsl x7, x7, #5
+    mov x8, x28
+    add x28, x28, x7
+    lsl x7, x4, #1
+    add x7, x7, #1
+    stp x8, x7, [x0]
+    mov x5, #0
+.Lrt_mp_move:
+    add x6, x3, x5, lsl #4
+    ldp x9, x10, [x6]
+    cbz x9, .Lrt_mp_moved
+    ldr x11, [x9]
+.Lrt_mp_slot:
+    and x11, x11, x7
+    add x6, x8, x11, lsl #4
+    ldr x2, [x6]
+    add x11, x11, #1
+    cbnz x2, .Lrt_mp_slot
+    stp x9, x10, [x6]
+.Lrt_mp_moved:
+    add x5, x5, #1
+    cmp x5, x4
+    b.ls .Lrt_mp_move
+    ldp x30, x2, [sp], #16
+    b __hamer_map_put
+";
+
+/// x0 = map, x1 = key (as for `__hamer_map_put`). Returns the stored value
+/// in x0, or 0 if absent. The table always has an empty slot to stop at.
+pub const MAP_GET: &str = "
+__hamer_map_get:
+    ldp x3, x4, [x0]
+    ldr x5, [x1]
+.Lrt_mg_probe:
+    and x5, x5, x4
+    add x6, x3, x5, lsl #4
+    ldp x7, x8, [x6]
+    cbz x7, .Lrt_mg_miss
+    cmp x7, x1
+    b.eq .Lrt_mg_hit
+    add x5, x5, #1
+    b .Lrt_mg_probe
+.Lrt_mg_miss:
+    mov x0, #0
+    ret
+.Lrt_mg_hit:
+    mov x0, x8
+    ret
+";
+
+/// Bytes reserved for a new text value's buffer.
+pub const TEXT_INITIAL_CAP: usize = 16;
+
+/// Bytes reserved for a line read with `input`; longer lines are cut off.
+pub const INPUT_CAP: usize = 256;
+
+/// x0 = buffer, x1 = capacity. Reads stdin a byte at a time up to a newline
+/// or EOF, so later reads still see the following lines. Returns x0 = bytes
+/// stored, without the newline.
+pub const READ_LINE: &str = "
+__hamer_read_line:
+    sub sp, sp, #16
+    mov x4, x0
+    mov x5, x0
+    add x6, x0, x1
+.Lrt_rl_next:
+    mov x0, #0
+    mov x1, sp
+    mov x2, #1
+    mov x8, #63
+    svc #0
+    cmp x0, #1
+    b.ne .Lrt_rl_done
+    ldrb w7, [sp]
+    cmp w7, #10
+    b.eq .Lrt_rl_done
+    cmp x4, x6
+    b.hs .Lrt_rl_next
+    strb w7, [x4], #1
+    b .Lrt_rl_next
+.Lrt_rl_done:
+    sub x0, x4, x5
+    add sp, sp, #16
+    ret
+";
+
+/// x0 = text header, x1 = bytes, x2 = length. Returns x0 = 1 if the text
+/// holds exactly those bytes, otherwise 0.
+pub const TEXT_EQ: &str = "
+__hamer_text_eq:
+    ldp x3, x4, [x0]
+    mov x0, #0
+    cmp x4, x2
+    b.ne .Lrt_te_done
+.Lrt_te_loop:
+    cbz x2, .Lrt_te_same
+    ldrb w5, [x3], #1
+    ldrb w6, [x1], #1
+    cmp w5, w6
+    b.ne .Lrt_te_done
+    sub x2, x2, #1
+    b .Lrt_te_loop
+.Lrt_te_same:
+    mov x0, #1
+.Lrt_te_done:
+    ret
+";
+
+/// x0 = text header [ptr, len, cap], x1 = source bytes, x2 = source length.
+/// Doubles the capacity into a fresh heap buffer (bumping the x28 heap
+/// pointer, after making room with `__hamer_heap_reserve`) when the bytes
+/// don't fit.
+pub const TEXT_APPEND: &str = "
+__hamer_text_append:
+    ldp x3, x4, [x0]
+    ldr x5, [x0, #16]
+    add x6, x4, x2
+    cmp x6, x5
+    b.ls .Lrt_ta_copy
+.Lrt_ta_grow:
+    lsl x5, x5, #1
+    cmp x6, x5
+    b.hi .Lrt_ta_grow
+    str x30, [sp, #-16]!
+    add x9, x5, #15
+    bl __hamer_heap_reserve
+    ldr x30, [sp], #16
+    mov x7, x28
+    add x28, x28, x5
+    add x28, x28, #15
+    and x28, x28, #0xfffffffffffffff0
+    mov x8, #0
+.Lrt_ta_move:
+    cmp x8, x4
+    b.hs .Lrt_ta_moved
+    ldrb w9, [x3, x8]
+    strb w9, [x7, x8]
+    add x8, x8, #1
+    b .Lrt_ta_move
+.Lrt_ta_moved:
+    mov x3, x7
+    str x3, [x0]
+    str x5, [x0, #16]
+.Lrt_ta_copy:
+    add x7, x3, x4
+    mov x8, #0
+.Lrt_ta_append:
+    cmp x8, x2
+    b.hs .Lrt_ta_done
+    ldrb w9, [x1, x8]
+    strb w9, [x7, x8]
+    add x8, x8, #1
+    b .Lrt_ta_append
+.Lrt_ta_done:
+    str x6, [x0, #8]
+    ret
+";
+
+/// x0 = array, x1 = length, x2 = element size, x3 = offset of the field
+/// compared. Insertion sort, smallest first: each element is swapped back
+/// a word at a time past the larger ones before it, so equal elements keep
+/// their order.
+pub const SORT: &str = "
+__hamer_sort:
+    mov x4, #1
+.Lrt_so_outer:
+    cmp x4, x1
+    b.hs .Lrt_so_done
+    madd x5, x4, x2, x0
+.Lrt_so_inner:
+    cmp x5, x0
+    b.ls .Lrt_so_next
+    sub x6, x5, x2
+    ldr x7, [x6, x3]
+    ldr x8, [x5, x3]
+    cmp x7, x8
+    b.le .Lrt_so_next
+    mov x7, #0
+.Lrt_so_swap:
+    ldr x8, [x6, x7]
+    ldr x9, [x5, x7]
+    str x9, [x6, x7]
+    str x8, [x5, x7]
+    add x7, x7, #8
+    cmp x7, x2
+    b.lo .Lrt_so_swap
+    mov x5, x6
+    b .Lrt_so_inner
+.Lrt_so_next:
+    add x4, x4, #1
+    b .Lrt_so_outer
+.Lrt_so_done:
+    ret
+";
+
+/// x0 = array, x1 = length, x2 = element size, x3 = offset of the field
+/// compared, x4 = value. Returns x0 = the index of the first element
+/// holding the value, or -1.
+pub const FIND: &str = "
+__hamer_find:
+    add x5, x0, x3
+    mov x0, #0
+.Lrt_fi_loop:
+    cmp x0, x1
+    b.hs .Lrt_fi_miss
+    ldr x6, [x5]
+    cmp x6, x4
+    b.eq .Lrt_fi_done
+    add x5, x5, x2
+    add x0, x0, #1
+    b .Lrt_fi_loop
+.Lrt_fi_miss:
+    mov x0, #-1
+.Lrt_fi_done:
+    ret
+";
+
+/// x0 = message, x1 = message length, x2 = address of the failing code,
+/// x3 = location table, x4 = exit status. The table is an entry count and
+/// then one [code address, file name, name length, line] per statement,
+/// with both addresses stored relative to the table so it needs no
+/// relocations. The entry with the highest address not above x2 is where
+/// the program was. Writes `error: <message> at <file>:<line>` to stderr
+/// and exits, so unlike the others it never returns and may use x10-x13.
+pub const TRAP: &str = "
+__hamer_trap:
+    mov x10, x0
+    mov x11, x1
+    mov x12, x4
+    mov x13, x3
+    ldr x4, [x3], #8
+    mov x5, #0
+    mov x6, #0
+.Lrt_tr_scan:
+    cbz x4, .Lrt_tr_found
+    ldr x7, [x3]
+    add x7, x7, x13
+    cmp x7, x2
+    b.hi .Lrt_tr_next
+    cmp x7, x6
+    b.lo .Lrt_tr_next
+    mov x6, x7
+    mov x5, x3
+.Lrt_tr_next:
+    add x3, x3, #32
+    sub x4, x4, #1
+    b .Lrt_tr_scan
+.Lrt_tr_found:
+    mov x8, #64
+    mov x0, #2
+    adr x1, .Lrt_tr_error
+    mov x2, #7
+    svc #0
+    mov x0, #2
+    mov x1, x10
+    mov x2, x11
+    svc #0
+    cbz x5, .Lrt_tr_newline
+    ldr x11, [x5, #24]
+    cbz x11, .Lrt_tr_newline
+    mov x0, #2
+    adr x1, .Lrt_tr_at
+    mov x2, #4
+    svc #0
+    mov x0, #2
+    ldp x1, x2, [x5, #8]
+    add x1, x1, x13
+    svc #0
+    mov x0, #2
+    adr x1, .Lrt_tr_colon
+    mov x2, #1
+    svc #0
+    sub sp, sp, #32
+    add x1, sp, #32
+    mov x4, #10
+.Lrt_tr_digit:
+    udiv x6, x11, x4
+    msub x7, x6, x4, x11
+    add x7, x7, #48
+    sub x1, x1, #1
+    strb w7, [x1]
+    mov x11, x6
+    cbnz x11, .Lrt_tr_digit
+    mov x0, #2
+    add x2, sp, #32
+    sub x2, x2, x1
+    svc #0
+.Lrt_tr_newline:
+    mov x0, #2
+    adr x1, .Lrt_tr_nl
+    mov x2, #1
+    svc #0
+    mov x0, x12
+    mov x8, #93
+    svc #0
+.Lrt_tr_error: .ascii \"error: \"
+.Lrt_tr_at: .ascii \" at \"
+.Lrt_tr_colon: .ascii \":\"
+.Lrt_tr_nl: .ascii \"\\n\"
+    .balign 4
+";
+
+/// FNV-1a hash of a map key, computed at compile time for its record.
+pub fn key_hash(key: &str) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in key.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+/// Looks up a routine's source by its symbol name.
+pub fn source(name: &str) -> &'static str {
+    match name {
+        "__hamer_print_num" => PRINT_NUM,
+        "__hamer_print_float" => PRINT_FLOAT,
+        "__hamer_print_bits" => PRINT_BITS,
+        "__hamer_map_put" => MAP_PUT,
+        "__hamer_map_get" => MAP_GET,
+        "__hamer_text_append" => TEXT_APPEND,
+        "__hamer_read_line" => READ_LINE,
+        "__hamer_text_eq" => TEXT_EQ,
+        "__hamer_sort" => SORT,
+        "__hamer_find" => FIND,
+        "__hamer_trap" => TRAP,
+        _ => "",
+    }
+}
+
+/// Whether a call to the routine comes back; `__hamer_trap` exits instead.
+pub fn returns(name: &str) -> bool {
+    name != "__hamer_trap"
+}
+
+/// Registers the routine `name` may overwrite, one bit per register number.
+/// Worked out from the routine's own instructions, so it can't drift from
+/// them: the destination of each instruction, both halves of a load pair,
+/// write-back bases, x0 for a system call and x30 for a call.
+pub fn clobbers(name: &str) -> u32 {
+    let mut regs = 0;
+    for line in source(name).lines().map(str::trim) {
+        let Some((op, args)) = line.split_once(' ') else { continue };
+        if op.ends_with(':') || op.starts_with('.') { continue; }
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        let written = match op {
+            "svc" => { regs |= 1; 0 }
+            "bl" | "blr" => { regs |= 1 << 30; 0 }
+            "ldp" | "ldxp" | "ldaxp" => 2,
+            "stxr" | "stlxr" | "stxp" | "stlxp" => 1,
+            _ if op.starts_with("st") || op.starts_with("b.") => 0,
+            "b" | "cbz" | "cbnz" | "tbz" | "tbnz" | "cmp" | "cmn" | "tst" | "ret" | "nop" | "msr" => 0,
+            _ => 1,
+        };
+        for arg in &args[..written.min(args.len())] {
+            if let Some(r) = register(arg) { regs |= 1 << r; }
+        }
+        // `[xN], #k` and `[xN, #k]!` move the base register
+        if let Some(base) = args.iter().find(|a| a.starts_with('['))
+            && (line.ends_with("]!") || line.contains("], "))
+            && let Some(r) = register(base.trim_start_matches('[').trim_end_matches(['!', ']']))
+        {
+            regs |= 1 << r;
+        }
+    }
+    regs
+}
+
+/// Number of a general register written `xN` or `wN`; `sp`, `xzr` and
+/// the like give `None`.
+fn register(arg: &str) -> Option<u32> {
+    arg.strip_prefix('x').or_else(|| arg.strip_prefix('w'))?.parse().ok().filter(|&r| r < 31)
+}
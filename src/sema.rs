@@ -0,0 +1,651 @@
+//! Checks names before code generation: every variable is defined before
+//! it is used, every class and field a program names exists, and nothing
+//! is defined twice. Scoping follows the generator: a function body sees
+//! the variables defined above it and its own, while `if`, loops and the
+//! other blocks share the body they sit in.
+//!
+//! A literal written with a fraction or exponent is a float inside
+//! expressions, but elsewhere (an argument, a period, a format value) an
+//! integer is needed, so it is narrowed there; that loses its fraction and
+//! is warned about.
+//!
+//! A literal chance (`if ?<30%> then`) must be a percentage; 0% and 100%
+//! are warned about, as the roll then decides nothing. A `handler` must
+//! name one of the exception kinds of the vector table.
+//!
+//! A few variable names mean something to the compiler or its libraries;
+//! `RESERVED` lists them, and defining one is an error.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use crate::ast::{Cond, Expr, Field, Item, ItemId, Operand, PrintItem, Stmt, StmtId, TextSrc};
+use crate::generator::{generic_parts, return_class, Classes, VECTOR_KINDS};
+use crate::resolve::Report;
+use crate::session::{Session, Symbol};
+
+/// Names a program can't give its own variables or parameters: the name,
+/// the file (by name) that may define it, and why it is taken.
+const RESERVED: &[(&str, Option<&str>, &str)] = &[
+    ("math", Some("math.hmr"), "the entropy library (`Get math`) keeps its state in it"),
+    ("std", None, "it names the modules built into the compiler, as in `Get std.ecs`"),
+    ("args", None, "it is kept for the program's command-line arguments"),
+    ("self", None, "it is kept for the object a method is called on"),
+];
+
+/// Checks `ast`, the resolved program read from `file`.
+pub fn check(file: &str, ast: &[StmtId], session: &Session) -> Report {
+    let mut sema = Sema {
+        session,
+        file: file.to_string(),
+        line: 0,
+        classes: HashMap::new(),
+        funcs: HashMap::new(),
+        declared: HashSet::new(),
+        scopes: vec![HashMap::new()],
+        labels: vec![HashSet::new()],
+        machines: Vec::new(),
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+    sema.declare(ast);
+    sema.declare_returns();
+    sema.file = file.to_string();
+    sema.block(ast);
+    // The first pass found some errors early; list each file's in line order
+    let files: Vec<String> = sema.errors.iter().fold(Vec::new(), |mut files, (f, ..)| {
+        if !files.contains(f) { files.push(f.clone()); }
+        files
+    });
+    sema.errors.sort_by_key(|(f, line, _)| (files.iter().position(|x| x == f), *line));
+    Report {
+        errors: sema.errors.into_iter().map(|(f, line, e)| format!("{}:{}: {}", f, line, e)).collect(),
+        warnings: sema.warnings,
+    }
+}
+
+struct Sema<'s> {
+    session: &'s Session,
+    /// File and line of the statement being checked, for diagnostics.
+    file: String,
+    line: usize,
+    /// Each class's generic parameters and fields.
+    classes: HashMap<Symbol, (Vec<Symbol>, Vec<Field>)>,
+    /// Each function's return class, when it returns an object of one.
+    funcs: HashMap<Symbol, Option<Symbol>>,
+    /// Classes and functions the first pass already took in.
+    declared: HashSet<ItemId>,
+    /// Variables visible in each enclosing function body, innermost last,
+    /// with the class of the object each holds when known.
+    scopes: Vec<HashMap<Symbol, Option<Symbol>>>,
+    /// Labels of each code body a `goto` can't leave, innermost last.
+    labels: Vec<HashSet<Symbol>>,
+    /// Name and states of each enclosing `machine` a `go` can reach,
+    /// innermost last.
+    machines: Vec<(Symbol, Vec<Symbol>)>,
+    /// Diagnostics as (file, line, message).
+    errors: Vec<(String, usize, String)>,
+    warnings: Vec<String>,
+}
+
+impl<'s> Sema<'s> {
+    fn name(&self, sym: Symbol) -> &'s str {
+        self.session.interner.resolve(sym)
+    }
+
+    fn error(&mut self, msg: String) {
+        self.errors.push((self.file.clone(), self.line, msg));
+    }
+
+    fn warning(&mut self, msg: &str) {
+        self.warnings.push(format!("{}:{}: {}", self.file, self.line, msg));
+    }
+
+    /// Warns when the integer code generation would drop part of `n`.
+    fn number(&mut self, n: f64) {
+        if n.fract() != 0.0 {
+            self.warning(&format!("`{}` is narrowed to the integer {}, losing its fraction", n, n as i64));
+        }
+    }
+
+    /// Checks that field `f` of `class` can start at `n`: a number field
+    /// can, warning if `n` gets narrowed; text and objects can't.
+    fn starts_at(&mut self, class: Symbol, f: &Field, n: f64) {
+        match f.class.map(|c| self.name(c)) {
+            None => self.number(n),
+            Some("float") => {}
+            Some(c) => self.error(format!("field `{}` of `{}` holds a `{}`, so it can't start at {}", self.name(f.name), self.name(class), c, n)),
+        }
+    }
+
+    /// First pass: takes in the top-level classes and functions of the
+    /// program and the files it imports, so they can be used above their
+    /// definitions as the generator allows.
+    fn declare(&mut self, body: &[StmtId]) {
+        let session = self.session;
+        for &stmt in body {
+            let Stmt::Item(id) = session.stmts[stmt] else { continue };
+            self.line = session.stmt_line(stmt);
+            match &session.items[id] {
+                Item::Class { .. } | Item::Func { .. } => self.declare_item(id),
+                Item::Import { file, body, .. } | Item::Emit { file, body, .. } => {
+                    let outer = std::mem::replace(&mut self.file, file.clone());
+                    self.declare(body);
+                    self.file = outer;
+                }
+            }
+        }
+    }
+
+    fn declare_item(&mut self, id: ItemId) {
+        self.declared.insert(id);
+        let session = self.session;
+        match &session.items[id] {
+            Item::Class { name, params, fields } => {
+                if self.classes.contains_key(name) {
+                    self.error(format!("class `{}` is defined twice", self.name(*name)));
+                    return;
+                }
+                let mut seen = HashSet::new();
+                for f in fields {
+                    if !seen.insert(f.name) { self.error(format!("class `{}` has two fields named `{}`", self.name(*name), self.name(f.name))); }
+                }
+                self.classes.insert(*name, (params.clone(), fields.clone()));
+            }
+            Item::Func { name, params, body, .. } => {
+                if self.funcs.contains_key(name) {
+                    self.error(format!("function `{}` is defined twice", self.name(*name)));
+                    return;
+                }
+                let class = return_class(session, params, body, self);
+                self.funcs.insert(*name, class);
+            }
+            Item::Import { .. } | Item::Emit { .. } => {}
+        }
+    }
+
+    /// Works out the return classes of the functions the first pass took
+    /// in, going round until nothing changes, as one may return what
+    /// another does.
+    fn declare_returns(&mut self) {
+        let session = self.session;
+        let funcs: Vec<(Symbol, &[Field], &[StmtId])> = self.declared.iter().filter_map(|&id| match &session.items[id] {
+            Item::Func { name, params, body, .. } => Some((*name, params.as_slice(), body.as_slice())),
+            _ => None,
+        }).collect();
+        for _ in 0..=funcs.len() {
+            let found: Vec<(Symbol, Option<Symbol>)> = funcs.iter().filter_map(|&(name, params, body)| {
+                let class = return_class(session, params, body, self);
+                (class.is_some() && self.funcs.get(&name) != Some(&class)).then_some((name, class))
+            }).collect();
+            if found.is_empty() { break; }
+            for (name, class) in found { self.funcs.insert(name, class); }
+        }
+    }
+
+    fn block(&mut self, body: &[StmtId]) {
+        for &stmt in body { self.stmt(stmt); }
+    }
+
+    /// Checks a body with labels of its own, such as a thread's. Like a
+    /// `goto`, a `go` can't leave it.
+    fn body(&mut self, body: &[StmtId]) {
+        self.labels.push(HashSet::new());
+        let machines = std::mem::take(&mut self.machines);
+        self.block(body);
+        self.machines = machines;
+        self.labels.pop();
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<Option<Symbol>> {
+        self.scopes.iter().rev().find_map(|s| s.get(&name).copied())
+    }
+
+    /// Defines `name` in the innermost body, holding an object of `class` if known.
+    fn define(&mut self, name: Symbol, class: Option<Symbol>) {
+        self.check_reserved(name);
+        self.scopes.last_mut().expect("the program's own scope").insert(name, class);
+    }
+
+    /// Reports `name` if it is in `RESERVED` and this file may not define it.
+    fn check_reserved(&mut self, name: Symbol) {
+        let name = self.name(name);
+        let Some((_, owner, why)) = RESERVED.iter().find(|(n, ..)| *n == name) else { return };
+        if owner.is_some_and(|o| Path::new(&self.file).file_name().is_some_and(|f| f == o)) { return; }
+        self.error(format!("`{}` is a reserved name: {}", name, why));
+    }
+
+    fn use_var(&mut self, name: Symbol) -> Option<Symbol> {
+        match self.lookup(name) {
+            Some(class) => class,
+            None => {
+                self.error(format!("undefined variable `{}`", self.name(name)));
+                None
+            }
+        }
+    }
+
+    /// Checks that `class` names a class, and for a generic instance such
+    /// as `Box<Hero>`, that its arguments do; names in `params` stand for
+    /// the arguments of the template being checked.
+    fn check_class(&mut self, class: Symbol, params: &[Symbol]) {
+        if params.contains(&class) { return; }
+        let (base, args) = generic_parts(&self.session.interner, class);
+        match self.classes.get(&base) {
+            None => self.error(format!("unknown class `{}`", self.name(base))),
+            Some((want, _)) if want.len() != args.len() => {
+                self.error(format!("class `{}` takes {} type arguments, `{}` gives {}", self.name(base), want.len(), self.name(class), args.len()));
+            }
+            Some(_) => for arg in args { self.check_class(arg, params); }
+        }
+    }
+
+    /// Checks a variable or field path and returns the class of the
+    /// object it leads to, when that is known.
+    fn path(&mut self, path: &[Symbol]) -> Option<Symbol> {
+        let interner = &self.session.interner;
+        let mut class = self.use_var(path[0]);
+        for &name in &path[1..] {
+            if let Some(index) = self.name(name).strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+                // The lexer interned the variable an index names
+                if index.parse::<usize>().is_err() && let Some(var) = interner.lookup(index) { self.use_var(var); }
+                continue;
+            }
+            let c = class?;
+            let (base, args) = generic_parts(interner, c);
+            let (params, fields) = self.classes.get(&base)?;
+            let Some(field) = fields.iter().find(|f| f.name == name) else {
+                self.error(format!("class `{}` has no field `{}` (in `{}`)", self.name(c), self.name(name), interner.join(path, ".")));
+                return None;
+            };
+            class = field.class.map(|f| match params.iter().position(|&p| p == f) {
+                Some(i) => args.get(i).copied().unwrap_or(f),
+                None => f,
+            });
+        }
+        class
+    }
+
+    fn operand(&mut self, op: &Operand) {
+        match op {
+            Operand::Num(n) => self.number(*n),
+            Operand::Path(p) => { self.path(p); }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Num(n) => self.number(*n),
+            Expr::Float(_) | Expr::Str(_) => {}
+            Expr::Path(p) => { self.path(p); }
+            Expr::Binary { lhs, rhs, .. } => {
+                let session = self.session;
+                self.expr(&session.exprs[*lhs]);
+                self.expr(&session.exprs[*rhs]);
+            }
+        }
+    }
+
+    fn cond(&mut self, cond: &Cond) {
+        match cond {
+            Cond::Cmp { path, rhs, .. } => {
+                self.path(path);
+                self.expr(rhs);
+            }
+            Cond::And(a, b) | Cond::Or(a, b) => {
+                let session = self.session;
+                self.cond(&session.conds[*a]);
+                self.cond(&session.conds[*b]);
+            }
+        }
+    }
+
+    fn item(&mut self, id: ItemId) {
+        let session = self.session;
+        let item = &session.items[id];
+        if !matches!(item, Item::Import { .. } | Item::Emit { .. }) && !self.declared.contains(&id) {
+            self.declare_item(id);
+        }
+        match item {
+            Item::Class { name, params, fields } => {
+                for f in fields {
+                    // `name: text` and `name: float` hold values rather than objects
+                    if let Some(c) = f.class && !matches!(self.name(c), "text" | "float") { self.check_class(c, params); }
+                    if let Some(n) = f.init { self.starts_at(*name, f, n); }
+                }
+            }
+            Item::Func { params, body, .. } => {
+                let mut scope = HashMap::new();
+                for p in params {
+                    if let Some(c) = p.class { self.check_class(c, &[]); }
+                    self.check_reserved(p.name);
+                    if scope.insert(p.name, p.class).is_some() {
+                        self.error(format!("parameter `{}` is named twice", self.name(p.name)));
+                    }
+                }
+                self.scopes.push(scope);
+                self.body(body);
+                self.scopes.pop();
+            }
+            Item::Import { file, body, .. } | Item::Emit { file, body, .. } => {
+                let outer = std::mem::replace(&mut self.file, file.clone());
+                self.block(body);
+                self.file = outer;
+            }
+        }
+    }
+
+    fn stmt(&mut self, id: StmtId) {
+        let session = self.session;
+        self.line = session.stmt_line(id);
+        match &session.stmts[id] {
+            Stmt::Item(item) => self.item(*item),
+            Stmt::LocalAssign { name, value } => {
+                // The new variable isn't in scope in its own initializer
+                self.expr(value);
+                match self.lookup(*name) {
+                    None => self.define(*name, None),
+                    // Assigning it again is still taking it over
+                    Some(_) => self.check_reserved(*name),
+                }
+            }
+            Stmt::HeapAlloc { var_name, class_name, args } => {
+                self.check_class(*class_name, &[]);
+                let (base, _) = generic_parts(&session.interner, *class_name);
+                if let Some((_, fields)) = self.classes.get(&base).cloned() {
+                    if args.len() > fields.len() {
+                        self.error(format!("`new {}` gives {} values, but `{}` has {} fields", self.name(*class_name), args.len(), self.name(base), fields.len()));
+                    }
+                    for (f, arg) in fields.iter().zip(args) {
+                        match arg {
+                            Operand::Num(n) => self.starts_at(base, f, *n),
+                            Operand::Path(p) => { self.path(p); }
+                        }
+                    }
+                }
+                self.define(*var_name, Some(*class_name));
+            }
+            Stmt::ArrayAlloc { var_name, class_name, .. } => {
+                self.check_class(*class_name, &[]);
+                self.define(*var_name, Some(*class_name));
+            }
+            Stmt::CopyAlloc { var_name, src } => {
+                let class = self.use_var(*src);
+                self.define(*var_name, class);
+            }
+            Stmt::MapAlloc { var_name } | Stmt::TextAlloc { var_name } | Stmt::Input { var_name } => self.define(*var_name, None),
+            Stmt::TextAppend { text, src } => {
+                self.path(text);
+                if let TextSrc::Var(v) = src { self.use_var(*v); }
+            }
+            Stmt::MapPut { map, value, .. } => {
+                self.path(map);
+                self.operand(value);
+            }
+            Stmt::MapGet { map, dest, .. } => {
+                self.path(map);
+                self.define(*dest, None);
+            }
+            Stmt::FieldAssign { path, value } => {
+                self.expr(value);
+                self.path(path);
+            }
+            Stmt::RefAssign { path, src } => {
+                self.path(src);
+                self.path(path);
+            }
+            Stmt::PrintVar(name) => { self.use_var(*name); }
+            Stmt::PrintString(_) | Stmt::Cls | Stmt::Color(None) | Stmt::Join => {}
+            Stmt::PrintList(items) => {
+                for item in items {
+                    // A literal prints as written, fraction and all
+                    if let PrintItem::Value(Operand::Path(p)) = item { self.path(p); }
+                }
+            }
+            Stmt::Eprint(print) => self.stmt(*print),
+            Stmt::PrintFormat { args, .. } => for a in args { self.operand(a); },
+            Stmt::PrintBits { value, .. } | Stmt::Color(Some(value)) => self.operand(value),
+            Stmt::Cursor { x, y } => {
+                self.operand(x);
+                self.operand(y);
+            }
+            Stmt::IfStmt { cond, body, else_body } => {
+                self.cond(cond);
+                self.block(body);
+                self.block(else_body);
+            }
+            Stmt::IfSame { lhs, rhs, body, else_body } => {
+                self.path(lhs);
+                self.path(rhs);
+                self.block(body);
+                self.block(else_body);
+            }
+            Stmt::ProbIf { chance, body, else_body } => {
+                match *chance {
+                    Operand::Num(n) if !(0.0..=100.0).contains(&n) => self.error(format!("chance of {}% is outside 0-100%", n)),
+                    Operand::Num(0.0) => self.warning("chance of 0% never runs this block"),
+                    Operand::Num(100.0) => self.warning("chance of 100% always runs this block; the roll does nothing"),
+                    _ => {}
+                }
+                self.operand(chance);
+                self.block(body);
+                self.block(else_body);
+            }
+            Stmt::WhileStmt { cond, body } => {
+                self.cond(cond);
+                self.block(body);
+            }
+            Stmt::Every { period, body } | Stmt::After { delay: period, body } => {
+                self.operand(period);
+                self.block(body);
+            }
+            Stmt::ForEach { var, array, body } => {
+                let class = self.use_var(*array);
+                self.define(*var, class);
+                self.block(body);
+            }
+            Stmt::NumArrayAlloc { var_name, .. } => self.define(*var_name, None),
+            Stmt::Sort { array, .. } => { self.use_var(*array); }
+            Stmt::Find { array, value, dest, .. } => {
+                self.use_var(*array);
+                self.operand(value);
+                self.define(*dest, None);
+            }
+            Stmt::Match { subject, arms, default } => {
+                self.use_var(*subject);
+                for (_, body) in arms { self.block(body); }
+                self.block(default);
+            }
+            Stmt::Choose { arms } => for (_, body) in arms { self.block(body); },
+            Stmt::Label(name) => {
+                if !self.labels.last_mut().expect("the program's own labels").insert(*name) {
+                    self.error(format!("label `{}` is defined twice", self.name(*name)));
+                }
+            }
+            Stmt::Goto(_) => {}
+            Stmt::Machine { name, states } => {
+                if states.is_empty() { self.error(format!("machine `{}` has no states", self.name(*name))); }
+                let mut names = Vec::new();
+                for (state, _) in states {
+                    if names.contains(state) {
+                        self.error(format!("machine `{}` has two states named `{}`", self.name(*name), self.name(*state)));
+                    }
+                    names.push(*state);
+                }
+                self.machines.push((*name, names));
+                for (_, body) in states { self.block(body); }
+                self.machines.pop();
+            }
+            Stmt::Go(state) => match self.machines.last() {
+                None => self.error(format!("`go {}` is outside any `machine`", self.name(*state))),
+                Some((name, states)) if !states.contains(state) => {
+                    let msg = format!("machine `{}` has no state `{}`", self.name(*name), self.name(*state));
+                    self.error(msg);
+                }
+                Some(_) => {}
+            },
+            Stmt::Delete(name) => { self.use_var(*name); }
+            Stmt::Assert(cond) => self.cond(cond),
+            Stmt::Handler { kind, body } => {
+                if !VECTOR_KINDS.contains(&self.name(*kind)) {
+                    self.error(format!("unknown handler kind `{}`; expected `sync`, `irq`, `fiq` or `serror`", self.name(*kind)));
+                }
+                self.body(body);
+            }
+            Stmt::Spawn(body) | Stmt::OnInterrupt(body) => self.body(body),
+            Stmt::AtomicAdd { path, value } => {
+                self.operand(value);
+                self.path(path);
+            }
+            Stmt::Lock(path) | Stmt::Unlock(path) => { self.path(path); }
+            Stmt::Run { dest, .. } => {
+                if let Some(d) = dest { self.define(*d, None); }
+            }
+            Stmt::Try { body, err, rescue } => {
+                self.block(body);
+                if let Some(e) = err { self.define(*e, None); }
+                self.block(rescue);
+            }
+            Stmt::Call { name, args, dest, from } => {
+                if let Some(inner) = from {
+                    self.stmt(*inner);
+                    self.line = session.stmt_line(id);
+                }
+                if !self.funcs.contains_key(name) { self.error(format!("call to undefined function `{}`", self.name(*name))); }
+                for a in args { self.operand(a); }
+                if let Some(d) = dest {
+                    let class = self.funcs.get(name).cloned().flatten();
+                    self.define(*d, class);
+                }
+            }
+            Stmt::Return(value) => {
+                if let Some(v) = value { self.operand(v); }
+            }
+            Stmt::AsmBlock(_) | Stmt::IntelBlock(_) | Stmt::PythonBlock { .. } => {}
+        }
+    }
+}
+
+impl Classes for Sema<'_> {
+    fn field_class(&self, class: Symbol, field: Symbol) -> Option<Symbol> {
+        let (base, args) = generic_parts(&self.session.interner, class);
+        let (params, fields) = self.classes.get(&base)?;
+        let f = fields.iter().find(|f| f.name == field)?.class?;
+        Some(match params.iter().position(|&p| p == f) {
+            Some(i) => args.get(i).copied().unwrap_or(f),
+            None => f,
+        })
+    }
+
+    fn returns(&self, name: Symbol) -> Option<Symbol> {
+        self.funcs.get(&name).copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Parses `src` as `main.hmr` and returns what the check reports.
+    fn errors(src: &str) -> Vec<String> {
+        let mut session = Session::default();
+        let (tokens, spans) = Lexer::new(src, &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &spans, &mut session);
+        let ast = parser.parse_program();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        check("main.hmr", &ast, &session).errors
+    }
+
+    #[test]
+    fn variables_must_be_defined_first() {
+        let found = errors("print a\nlocal a = 1\nfunc f n is\n    local m = n + a\n    return m\ndone\nprint m\nlocal r = call f a\nprint r\n");
+        assert_eq!(found, ["main.hmr:1: undefined variable `a`", "main.hmr:7: undefined variable `m`"]);
+    }
+
+    #[test]
+    fn classes_and_fields_must_exist() {
+        let src = "class Box<T> is\n    item: T\ndone\nclass Hero is\n    hp\ndone\nlocal b = new Box<Hero>\nb.item.hp = 1\nb.item.mp = 2\nlocal g = new Ghost\nlocal w = new Hero[2]\nfor h in w do\n    h.hp = h.hp + 1\ndone\n";
+        assert_eq!(errors(src), ["main.hmr:9: class `Hero` has no field `mp` (in `b.item.mp`)", "main.hmr:10: unknown class `Ghost`"]);
+    }
+
+    #[test]
+    fn call_results_have_their_functions_return_class() {
+        let src = "local e = call make 5\nprint e.dmg\nprint e.mp\nlocal b = call boxed e\nprint b.item.hp\nprint b.item.xp\nclass Box<T> is\n    item: T\ndone\nclass Enemy is\n    hp\n    dmg\ndone\nfunc make n is\n    local e = new Enemy\n    e.dmg = n\n    return e\ndone\nfunc boxed x: Enemy is\n    local b = new Box<Enemy>\n    b.item = x\n    return b\ndone\n";
+        assert_eq!(errors(src), [
+            "main.hmr:3: class `Enemy` has no field `mp` (in `e.mp`)",
+            "main.hmr:6: class `Enemy` has no field `xp` (in `b.item.xp`)",
+        ]);
+    }
+
+    #[test]
+    fn go_only_reaches_states_of_its_machine() {
+        let src = "go home\nmachine m is\n    state a\n        go b\n    state a\n        spawn is\n            go a\n        done\ndone\n";
+        assert_eq!(errors(src), [
+            "main.hmr:1: `go home` is outside any `machine`",
+            "main.hmr:2: machine `m` has two states named `a`",
+            "main.hmr:4: machine `m` has no state `b`",
+            "main.hmr:7: `go a` is outside any `machine`",
+        ]);
+    }
+
+    #[test]
+    fn starting_values_fit_their_fields() {
+        let src = "class A is\n    x 1\n    next: A 3\ndone\nlocal a = new A 0.5\nlocal b = new A 1 a 2\n";
+        assert_eq!(errors(src), [
+            "main.hmr:1: field `next` of `A` holds a `A`, so it can't start at 3",
+            "main.hmr:6: `new A` gives 3 values, but `A` has 2 fields",
+        ]);
+    }
+
+    #[test]
+    fn reserved_names_are_not_variables() {
+        let src = "local std = 1\nfunc f args is\n    return args\ndone\nlocal math = 2\nlocal math = 3\nlocal mathematics = 4\n";
+        assert_eq!(errors(src), [
+            "main.hmr:1: `std` is a reserved name: it names the modules built into the compiler, as in `Get std.ecs`",
+            "main.hmr:2: `args` is a reserved name: it is kept for the program's command-line arguments",
+            "main.hmr:5: `math` is a reserved name: the entropy library (`Get math`) keeps its state in it",
+            "main.hmr:6: `math` is a reserved name: the entropy library (`Get math`) keeps its state in it",
+        ]);
+    }
+
+    #[test]
+    fn text_fields_are_not_classes() {
+        let src = "class Hero is\n    name: text\n    pet: Pet\ndone\nlocal h = new Hero\nh.name = \"Ada\" + \"!\"\n";
+        assert_eq!(errors(src), ["main.hmr:1: unknown class `Pet`"]);
+    }
+
+    #[test]
+    fn fractions_narrowed_to_integers_are_warned_about() {
+        let mut session = Session::default();
+        let src = "local a = 2.5 * 2\nif a > 4.0 then print a 0.5\nprint format \"%d\" 0.25\n";
+        let (tokens, spans) = Lexer::new(src, &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &spans, &mut session);
+        let ast = parser.parse_program();
+        let report = check("main.hmr", &ast, &session);
+        assert_eq!(report.warnings, ["main.hmr:3: `0.25` is narrowed to the integer 0, losing its fraction"]);
+    }
+
+    #[test]
+    fn duplicates_are_reported_in_line_order() {
+        let found = errors("label top\nclass A is\n    x\n    x\ndone\nfunc f a, a is\n    return a\ndone\nclass A is\n    y\ndone\nlabel top\n");
+        assert_eq!(found, [
+            "main.hmr:2: class `A` has two fields named `x`",
+            "main.hmr:6: parameter `a` is named twice",
+            "main.hmr:9: class `A` is defined twice",
+            "main.hmr:12: label `top` is defined twice",
+        ]);
+    }
+
+    #[test]
+    fn handler_kinds_must_exist() {
+        let found = errors("handler irq is
+    print 1
+done
+handler irg is
+    print 2
+done
+");
+        assert_eq!(found, ["main.hmr:4: unknown handler kind `irg`; expected `sync`, `irq`, `fiq` or `serror`"]);
+    }
+}
@@ -0,0 +1,185 @@
+//! State shared by every stage of one compilation.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use crate::ast::{Cond, Expr, Item, Stmt};
+use crate::source::SourceMap;
+
+/// An interned identifier. Equal names always get the same symbol, so
+/// comparing two is an integer comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// Contextual keywords. They are interned first, in this order, so the
+/// parser can compare against constants without a lookup.
+const KEYWORDS: &[&str] = &["map", "text", "input", "get", "put", "append", "format", "hex", "bin", "in", "else", "field", "and", "or", "pub", "cls", "cursor", "color", "every", "ms", "elif", "sort", "find", "by", "array", "after", "machine", "state", "go", "emit", "delete"];
+
+/// The contextual keyword spelled `name`, if it is one.
+pub fn keyword(name: &str) -> Option<Symbol> {
+    KEYWORDS.iter().position(|k| *k == name).map(|i| Symbol(i as u32))
+}
+
+pub mod kw {
+    use super::Symbol;
+    pub const MAP: Symbol = Symbol(0);
+    pub const TEXT: Symbol = Symbol(1);
+    pub const INPUT: Symbol = Symbol(2);
+    pub const GET: Symbol = Symbol(3);
+    pub const PUT: Symbol = Symbol(4);
+    pub const APPEND: Symbol = Symbol(5);
+    pub const FORMAT: Symbol = Symbol(6);
+    pub const HEX: Symbol = Symbol(7);
+    pub const BIN: Symbol = Symbol(8);
+    pub const IN: Symbol = Symbol(9);
+    pub const ELSE: Symbol = Symbol(10);
+    pub const FIELD: Symbol = Symbol(11);
+    pub const AND: Symbol = Symbol(12);
+    pub const OR: Symbol = Symbol(13);
+    pub const PUB: Symbol = Symbol(14);
+    pub const CLS: Symbol = Symbol(15);
+    pub const CURSOR: Symbol = Symbol(16);
+    pub const COLOR: Symbol = Symbol(17);
+    pub const EVERY: Symbol = Symbol(18);
+    pub const MS: Symbol = Symbol(19);
+    pub const ELIF: Symbol = Symbol(20);
+    pub const SORT: Symbol = Symbol(21);
+    pub const FIND: Symbol = Symbol(22);
+    pub const BY: Symbol = Symbol(23);
+    pub const ARRAY: Symbol = Symbol(24);
+    pub const AFTER: Symbol = Symbol(25);
+    pub const MACHINE: Symbol = Symbol(26);
+    pub const STATE: Symbol = Symbol(27);
+    pub const GO: Symbol = Symbol(28);
+    pub const EMIT: Symbol = Symbol(29);
+    pub const DELETE: Symbol = Symbol(30);
+}
+
+pub struct Interner {
+    names: Vec<Box<str>>,
+    ids: HashMap<Box<str>, Symbol>,
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        let mut interner = Self { names: Vec::new(), ids: HashMap::new() };
+        for word in KEYWORDS { interner.intern(word); }
+        interner
+    }
+}
+
+impl Interner {
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&sym) = self.ids.get(name) { return sym; }
+        let sym = Symbol(self.names.len() as u32);
+        self.names.push(name.into());
+        self.ids.insert(name.into(), sym);
+        sym
+    }
+
+    pub fn resolve(&self, sym: Symbol) -> &str {
+        &self.names[sym.0 as usize]
+    }
+
+    /// The symbol of `name` if it has been interned, without interning it.
+    pub fn lookup(&self, name: &str) -> Option<Symbol> {
+        self.ids.get(name).copied()
+    }
+
+    /// The names of `path` with `sep` between them, e.g. `hero.hp`.
+    pub fn join(&self, path: &[Symbol], sep: &str) -> String {
+        path.iter().map(|&s| self.resolve(s)).collect::<Vec<_>>().join(sep)
+    }
+}
+
+/// Index of a node in an `Arena<T>`.
+pub struct Id<T> {
+    index: u32,
+    _node: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool { self.index == other.index }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.index.hash(state) }
+}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.index)
+    }
+}
+
+/// Typed, append-only storage. Nodes refer to each other by `Id`, so a
+/// tree lives in one flat Vec instead of nested allocations.
+pub struct Arena<T> {
+    nodes: Vec<T>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self { Self { nodes: Vec::new() } }
+}
+
+impl<T> Arena<T> {
+    pub fn alloc(&mut self, node: T) -> Id<T> {
+        let id = Id { index: self.nodes.len() as u32, _node: PhantomData };
+        self.nodes.push(node);
+        id
+    }
+
+    /// Every node, in the order they were allocated.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.nodes.iter()
+    }
+}
+
+impl<T> Index<Id<T>> for Arena<T> {
+    type Output = T;
+    fn index(&self, id: Id<T>) -> &T { &self.nodes[id.index as usize] }
+}
+
+impl<T> IndexMut<Id<T>> for Arena<T> {
+    fn index_mut(&mut self, id: Id<T>) -> &mut T { &mut self.nodes[id.index as usize] }
+}
+
+/// Owns what the lexer, parser and generator share for one compilation.
+#[derive(Default)]
+pub struct Session {
+    pub interner: Interner,
+    /// Every statement parsed so far, including those from included files.
+    pub stmts: Arena<Stmt>,
+    /// The declarations `Stmt::Item`s refer to.
+    pub items: Arena<Item>,
+    /// The operands of binary expressions.
+    pub exprs: Arena<Expr>,
+    /// The clauses joined by `and` and `or`.
+    pub conds: Arena<Cond>,
+    /// Line each statement starts on in its own file, indexed like `stmts`.
+    stmt_lines: Vec<usize>,
+    /// Every file read so far, and where they are read from.
+    pub sources: SourceMap,
+}
+
+impl Session {
+    /// Stores a parsed statement along with the line it starts on.
+    pub fn alloc_stmt(&mut self, stmt: Stmt, line: usize) -> Id<Stmt> {
+        self.stmt_lines.push(line);
+        self.stmts.alloc(stmt)
+    }
+
+    pub fn stmt_line(&self, id: Id<Stmt>) -> usize {
+        self.stmt_lines.get(id.index as usize).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,112 @@
+//! Where source files come from. Every stage reads files through the
+//! session's `SourceMap` rather than the filesystem, so a caller holding
+//! unsaved editor buffers or an in-memory project can supply them instead.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use crate::stdlib;
+
+/// Supplies file contents by path.
+pub trait FileLoader {
+    fn read(&self, path: &str) -> io::Result<String>;
+
+    /// A file's identity, so `lib.hmr` and `./lib.hmr` count as one.
+    fn canonical(&self, path: &str) -> PathBuf;
+}
+
+/// Reads from the real filesystem.
+pub struct DiskLoader;
+
+impl FileLoader for DiskLoader {
+    fn read(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn canonical(&self, path: &str) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+    }
+}
+
+/// Files held in memory; any path not added reads as missing. Only tests
+/// use it so far.
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Default)]
+pub struct MemoryLoader {
+    files: HashMap<PathBuf, String>,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl MemoryLoader {
+    pub fn add(&mut self, path: &str, contents: &str) {
+        let key = self.canonical(path);
+        self.files.insert(key, contents.to_string());
+    }
+}
+
+impl FileLoader for MemoryLoader {
+    fn read(&self, path: &str) -> io::Result<String> {
+        self.files.get(&self.canonical(path)).cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such file"))
+    }
+
+    /// Resolves `.` and `..` by the path's text alone, as there is no
+    /// filesystem to ask.
+    fn canonical(&self, path: &str) -> PathBuf {
+        let mut out = PathBuf::new();
+        for part in Path::new(path).components() {
+            match part {
+                Component::CurDir => {}
+                Component::ParentDir if matches!(out.components().next_back(), Some(Component::Normal(_))) => { out.pop(); }
+                other => out.push(other),
+            }
+        }
+        out
+    }
+}
+
+/// The source files of one compilation, read through a `FileLoader` and
+/// kept so later stages (diagnostics, `hamer disasm`) can show their lines.
+pub struct SourceMap {
+    loader: Box<dyn FileLoader>,
+    files: HashMap<String, String>,
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        SourceMap::new(Box::new(DiskLoader))
+    }
+}
+
+impl SourceMap {
+    pub fn new(loader: Box<dyn FileLoader>) -> Self {
+        Self { loader, files: HashMap::new() }
+    }
+
+    /// Reads `path` and keeps its contents. The std modules come from the
+    /// compiler itself rather than the loader.
+    pub fn load(&mut self, path: &str) -> io::Result<String> {
+        let text = match stdlib::module(path) {
+            Some(text) => text.to_string(),
+            None => self.loader.read(path)?,
+        };
+        self.files.insert(path.to_string(), text.clone());
+        Ok(text)
+    }
+
+    /// Keeps `text` as the source named `path` that no file holds, such as
+    /// the output of an `@python emit` block.
+    pub fn add(&mut self, path: &str, text: &str) {
+        self.files.insert(path.to_string(), text.to_string());
+    }
+
+    /// A file loaded earlier, by the path it was loaded with.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.files.get(path).map(String::as_str)
+    }
+
+    pub fn loader(&self) -> &dyn FileLoader {
+        &*self.loader
+    }
+}
@@ -0,0 +1,15 @@
+//! The `std` modules, built into the compiler so `Get std.ecs` works
+//! wherever the program lives and whatever is on disk.
+
+/// Directory std modules appear to live in, in diagnostics and paths.
+pub const DIR: &str = "<std>";
+
+/// Source of the std module at `path` (as `import_path` names it), if
+/// there is one.
+pub fn module(path: &str) -> Option<&'static str> {
+    let name = path.strip_prefix(DIR)?.strip_prefix('/')?.strip_suffix(".hmr")?;
+    match name {
+        "ecs" => Some(include_str!("../std/ecs.hmr")),
+        _ => None,
+    }
+}
@@ -0,0 +1,144 @@
+//! What the generated assembly is aimed at. For now that is always ARM64
+//! Linux; the target only decides which assembler's syntax to write.
+
+use std::env::consts;
+
+/// Whether programs built for the one backend run on this machine as-is.
+pub const HOST_IS_ARM64_LINUX: bool = cfg!(all(target_arch = "aarch64", target_os = "linux"));
+
+/// Triple of the only backend, and the names `--target` accepts for it.
+const TRIPLE: &str = "aarch64-linux-gnu";
+const TRIPLE_ALIASES: &[&str] = &["aarch64-linux-gnu", "aarch64-unknown-linux-gnu", "aarch64-linux", "arm64-linux"];
+
+/// `arch-os` of the machine the compiler is running on, e.g. `x86_64-linux`.
+pub fn host_triple() -> String {
+    format!("{}-{}", consts::ARCH, consts::OS)
+}
+
+/// Assembler the output must be accepted by.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Dialect {
+    /// GNU as.
+    #[default]
+    Gnu,
+    /// clang's integrated assembler on ELF.
+    Llvm,
+    /// Apple's assembler (Mach-O).
+    Apple,
+}
+
+impl Dialect {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gnu" => Some(Dialect::Gnu),
+            "llvm" | "clang" => Some(Dialect::Llvm),
+            "apple" => Some(Dialect::Apple),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Target {
+    pub dialect: Dialect,
+    /// Build a position-independent executable: every data reference is
+    /// PC-relative, so the program runs wherever the loader maps it.
+    pub pie: bool,
+}
+
+impl Target {
+    /// The target for `--target=name`. Only ARM64 Linux has a backend so
+    /// far; other triples are refused rather than miscompiled.
+    pub fn from_triple(name: &str) -> Result<Self, String> {
+        if TRIPLE_ALIASES.contains(&name) { return Ok(Target::default()); }
+        Err(format!("no backend for target `{}` (supported: {})", name, TRIPLE))
+    }
+
+    /// What to build for when no `--target` is given: the host when there
+    /// is a backend for it, otherwise ARM64 Linux.
+    pub fn host() -> Self {
+        Target::from_triple(&host_triple()).unwrap_or_default()
+    }
+
+    pub fn triple(&self) -> &'static str {
+        TRIPLE
+    }
+
+    /// Whether the host can run what this target produces without qemu.
+    pub fn runs_on_host(&self) -> bool {
+        HOST_IS_ARM64_LINUX
+    }
+
+    pub fn text_section(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Gnu => ".section .text",
+            Dialect::Llvm | Dialect::Apple => ".text",
+        }
+    }
+
+    pub fn data_section(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Gnu => ".section .data",
+            Dialect::Llvm | Dialect::Apple => ".data",
+        }
+    }
+
+    /// Section for zero-initialized data, which takes no space in the file.
+    pub fn bss_section(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Gnu => ".section .bss",
+            Dialect::Llvm => ".bss",
+            Dialect::Apple => ".section __DATA,__bss",
+        }
+    }
+
+    /// Prefix that keeps a label out of the object's symbol table.
+    pub fn local_prefix(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Apple => "L",
+            Dialect::Gnu | Dialect::Llvm => ".L",
+        }
+    }
+
+    /// Instructions putting the address of `label` into `reg`. GNU output
+    /// keeps the single `adr` unless building a PIE; the others use a page +
+    /// offset pair, which reaches labels in other sections wherever the
+    /// linker (or loader) puts them.
+    pub fn load_address(&self, reg: &str, label: &str) -> String {
+        match self.dialect {
+            Dialect::Gnu if !self.pie => format!("    adr {}, {}\n", reg, label),
+            Dialect::Gnu | Dialect::Llvm => format!("    adrp {}, {}\n    add {}, {}, :lo12:{}\n", reg, label, reg, reg, label),
+            Dialect::Apple => format!("    adrp {}, {}@PAGE\n    add {}, {}, {}@PAGEOFF\n", reg, label, reg, reg, label),
+        }
+    }
+
+    /// How C code refers to the symbol `name`; Mach-O prefixes an underscore.
+    pub fn c_symbol(&self, name: &str) -> String {
+        match self.dialect {
+            Dialect::Apple => format!("_{}", name),
+            Dialect::Gnu | Dialect::Llvm => name.to_string(),
+        }
+    }
+
+    /// Marks `name` as a function symbol, for object formats that record it.
+    pub fn function_type(&self, name: &str) -> String {
+        match self.dialect {
+            Dialect::Apple => String::new(),
+            Dialect::Gnu | Dialect::Llvm => format!(".type {}, %function\n", name),
+        }
+    }
+
+    /// Records the size of the symbol `name`, which must end here.
+    pub fn symbol_size(&self, name: &str) -> String {
+        match self.dialect {
+            Dialect::Apple => String::new(),
+            Dialect::Gnu | Dialect::Llvm => format!(".size {}, . - {}\n", name, name),
+        }
+    }
+
+    /// Rewrites the `.L` labels in a fixed runtime routine for this dialect.
+    pub fn localize(&self, asm: &str) -> String {
+        if self.local_prefix() == ".L" { return asm.to_string(); }
+        asm.replace(".L", self.local_prefix())
+    }
+}
@@ -0,0 +1,124 @@
+//! The external tools that turn the generated assembly into a running
+//! program: an assembler, a linker and, when the host is not ARM64,
+//! qemu-aarch64.
+//! `hamer dist` can also hand the result to upx.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+use crate::target::HOST_IS_ARM64_LINUX;
+
+/// Looks `name` up on PATH, like a shell would.
+fn find(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path).map(|dir| dir.join(name)).find(|p| p.is_file())
+}
+
+/// First of the `cross` tools found on PATH. The native tool only counts on an ARM64
+/// Linux host; anywhere else it would target the wrong machine.
+fn find_tool(cross: &[&str], native: &str) -> Option<PathBuf> {
+    cross.iter().find_map(|n| find(n)).or_else(|| if HOST_IS_ARM64_LINUX { find(native) } else { None })
+}
+
+/// The `prefix`ed binutils tool `--cross` asks for. Nothing else will do:
+/// falling back to another toolchain would hide a broken cross setup.
+fn find_cross(prefix: &str, tool: &str) -> Result<PathBuf, String> {
+    let name = format!("{}{}", prefix, tool);
+    find(&name).ok_or_else(|| format!("--cross: {} not found on PATH", name))
+}
+
+/// Runs a build tool. What it prints is only shown when it fails, so a
+/// successful `hamer run` writes nothing but the program's own output.
+fn exec(cmd: &mut Command) -> Result<(), String> {
+    let tool = cmd.get_program().to_string_lossy().into_owned();
+    debug!("{} {}", tool, cmd.get_args().map(|a| a.to_string_lossy()).collect::<Vec<_>>().join(" "));
+    let out = cmd.output().map_err(|e| format!("cannot run {}: {}", tool, e))?;
+    if out.status.success() { return Ok(()); }
+    let said = format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+    Err(format!("{} failed ({}):\n{}", tool, out.status, said.trim_end()))
+}
+
+/// Assembles `asm` into `obj`; with `debug`, the object also records which
+/// line of `asm` each instruction came from. `cross` is the `--cross` prefix.
+pub fn assemble(asm: &str, obj: &str, debug: bool, cross: Option<&str>) -> Result<(), String> {
+    let gas = match cross {
+        Some(prefix) => Some(find_cross(prefix, "as")?),
+        None => find_tool(&["aarch64-linux-gnu-as"], "as"),
+    };
+    if let Some(gas) = gas {
+        let mut cmd = Command::new(gas);
+        if debug { cmd.arg("-g"); }
+        return exec(cmd.args([asm, "-o", obj]));
+    }
+    if let Some(mc) = find("llvm-mc") {
+        let mut cmd = Command::new(mc);
+        if debug { cmd.arg("-g"); }
+        return exec(cmd.args(["-triple=aarch64-linux-gnu", "-filetype=obj", asm, "-o", obj]));
+    }
+    Err("no ARM64 assembler found (install binutils-aarch64-linux-gnu or llvm)".to_string())
+}
+
+/// Links `obj` into a static executable. `extra` carries options such as
+/// `-T prog.ld` straight through to the linker.
+pub fn link(obj: &str, exe: &str, extra: &[String], cross: Option<&str>) -> Result<(), String> {
+    let ld = match cross {
+        Some(prefix) => Some(find_cross(prefix, "ld")?),
+        None => find_tool(&["aarch64-linux-gnu-ld", "ld.lld"], "ld"),
+    };
+    let Some(ld) = ld else {
+        return Err("no ARM64 linker found (install binutils-aarch64-linux-gnu or lld)".to_string());
+    };
+    let mut cmd = Command::new(&ld);
+    if ld.ends_with("ld.lld") { cmd.arg("-m").arg("aarch64linux"); }
+    exec(cmd.args(extra).args([obj, "-o", exe]))
+}
+
+/// Compresses `exe` in place with upx.
+pub fn compress(exe: &str) -> Result<(), String> {
+    let Some(upx) = find("upx") else {
+        return Err("--compress needs upx on PATH (install upx-ucl)".to_string());
+    };
+    exec(Command::new(upx).args(["--best", "-q", "-q", exe]))
+}
+
+/// Disassembles `obj`, with the `file:line` each instruction came from.
+pub fn disassemble(obj: &str, cross: Option<&str>) -> Result<String, String> {
+    let objdump = match cross {
+        Some(prefix) => Some(find_cross(prefix, "objdump")?),
+        None => find_tool(&["aarch64-linux-gnu-objdump", "llvm-objdump"], "objdump"),
+    };
+    let Some(objdump) = objdump else {
+        return Err("no ARM64 disassembler found (install binutils-aarch64-linux-gnu or llvm)".to_string());
+    };
+    let out = Command::new(&objdump).args(["-d", "-l", obj]).output()
+        .map_err(|e| format!("cannot run {}: {}", objdump.display(), e))?;
+    if !out.status.success() {
+        return Err(format!("{} failed ({}):\n{}", objdump.display(), out.status, String::from_utf8_lossy(&out.stderr)));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Runs `exe` with the compiler's stdin/stdout, under qemu-aarch64 when
+/// `qemu` is set or the host cannot run ARM64 code itself. Returns the exit
+/// code, or 128 + the signal number if the program was killed.
+pub fn run(exe: &str, qemu: bool) -> Result<i32, String> {
+    let path = PathBuf::from(".").join(exe);
+    let mut cmd = if qemu || !HOST_IS_ARM64_LINUX {
+        let Some(qemu) = find("qemu-aarch64").or_else(|| find("qemu-aarch64-static")) else {
+            return Err("cannot run an ARM64 binary on this host: qemu-aarch64 not found (install qemu-user)".to_string());
+        };
+        let mut cmd = Command::new(qemu);
+        cmd.arg(path);
+        cmd
+    } else {
+        Command::new(path)
+    };
+    let status = cmd.status().map_err(|e| format!("cannot run {}: {}", exe, e))?;
+    if let Some(code) = status.code() { return Ok(code); }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(sig) = status.signal() { return Ok(128 + sig); }
+    }
+    Ok(1)
+}
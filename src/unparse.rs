@@ -0,0 +1,553 @@
+//! Turns a parsed tree back into H@mer source, in one canonical layout:
+//! one statement per line, bodies indented four spaces, every block closed
+//! by its own `done`. Parsing the output gives the same tree back.
+//! `--emit=expanded` uses it to show what the compiler made of a program.
+
+use crate::ast::{Cond, Expr, Field, Item, Operand, PrintItem, Stmt, StmtId, TextSrc};
+use crate::lexer::Token;
+use crate::session::{Session, Symbol};
+
+/// `body` (usually a whole file) as source text.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn unparse(session: &Session, body: &[StmtId]) -> String {
+    let mut printer = Printer { session, out: String::new(), indent: 0, expand: false };
+    printer.stmts(body);
+    printer.out
+}
+
+/// Like `unparse`, but with each file pulled in by `Get` written out in
+/// place of the first `Get` of it, and what each `@python emit` printed in
+/// place of the block, as the generator compiles it.
+pub fn expand(session: &Session, body: &[StmtId]) -> String {
+    let mut printer = Printer { session, out: String::new(), indent: 0, expand: true };
+    printer.stmts(body);
+    printer.out
+}
+
+fn is_decl(session: &Session, id: StmtId) -> bool {
+    matches!(session.stmts[id], Stmt::Item(item) if !matches!(session.items[item], Item::Import { .. } | Item::Emit { .. }))
+}
+
+struct Printer<'s> {
+    session: &'s Session,
+    out: String,
+    indent: usize,
+    /// Write imported and emitted statements instead of `Get`s and
+    /// `@python emit` blocks.
+    expand: bool,
+}
+
+impl Printer<'_> {
+    /// Statements at the current indentation; declarations get a blank
+    /// line on either side.
+    fn stmts(&mut self, body: &[StmtId]) {
+        for (i, &id) in body.iter().enumerate() {
+            if i > 0 && (is_decl(self.session, id) || is_decl(self.session, body[i - 1])) { self.out.push('\n'); }
+            self.stmt(id);
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent { self.out.push_str("    "); }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn body(&mut self, body: &[StmtId]) {
+        self.indent += 1;
+        for &id in body { self.stmt(id); }
+        self.indent -= 1;
+    }
+
+    /// `head`, the indented `body` and the `done` closing it.
+    fn block(&mut self, head: &str, body: &[StmtId]) {
+        self.line(head);
+        self.body(body);
+        self.line("done");
+    }
+
+    fn stmt(&mut self, id: StmtId) {
+        let session = self.session;
+        match &session.stmts[id] {
+            Stmt::Item(item) => self.item(&session.items[*item]),
+            Stmt::LocalAssign { name, value } => self.line(&format!("local {} = {}", self.name(*name), self.expr(value))),
+            Stmt::HeapAlloc { var_name, class_name, args } => {
+                let args: String = args.iter().map(|a| format!(" {}", self.operand(a))).collect();
+                self.line(&format!("local {} = new {}{}", self.name(*var_name), self.name(*class_name), args))
+            }
+            Stmt::ArrayAlloc { var_name, class_name, len } => self.line(&format!("local {} = new {}[{}]", self.name(*var_name), self.name(*class_name), len)),
+            Stmt::NumArrayAlloc { var_name, len } => self.line(&format!("local {} = array {}", self.name(*var_name), len)),
+            Stmt::CopyAlloc { var_name, src } => self.line(&format!("local {} = copy {}", self.name(*var_name), self.name(*src))),
+            Stmt::MapAlloc { var_name } => self.line(&format!("local {} = map", self.name(*var_name))),
+            Stmt::TextAlloc { var_name } => self.line(&format!("local {} = text", self.name(*var_name))),
+            Stmt::Input { var_name } => self.line(&format!("local {} = input", self.name(*var_name))),
+            Stmt::TextAppend { text, src } => {
+                let src = match src {
+                    TextSrc::Lit(s) => quote(s),
+                    TextSrc::Var(v) => self.name(*v).to_string(),
+                };
+                self.line(&format!("{} append {}", self.path(text), src));
+            }
+            Stmt::MapPut { map, key, value } => self.line(&format!("{} put {} {}", self.path(map), quote(key), self.operand(value))),
+            Stmt::MapGet { map, key, dest } => self.line(&format!("local {} = {} get {}", self.name(*dest), self.path(map), quote(key))),
+            Stmt::FieldAssign { path: p, value } => self.line(&format!("{} = {}", self.path(p), self.expr(value))),
+            Stmt::RefAssign { path: p, src } => self.line(&format!("{} = {}", self.path(p), self.path(src))),
+            Stmt::PrintVar(_) | Stmt::PrintString(_) | Stmt::PrintList(_) | Stmt::PrintFormat { .. } | Stmt::PrintBits { .. } => {
+                let args = self.print_args(&session.stmts[id]);
+                self.line(&format!("print {}", args));
+            }
+            Stmt::Eprint(print) => self.line(&format!("eprint {}", self.print_args(&session.stmts[*print]))),
+            Stmt::Cls => self.line("cls"),
+            Stmt::Cursor { x, y } => self.line(&format!("cursor {} {}", self.operand(x), self.operand(y))),
+            Stmt::Color(None) => self.line("color"),
+            Stmt::Color(Some(n)) => self.line(&format!("color {}", self.operand(n))),
+            Stmt::IfStmt { .. } | Stmt::IfSame { .. } | Stmt::ProbIf { .. } => self.if_chain(id, "if"),
+            Stmt::WhileStmt { cond, body } => self.block(&format!("while {} do", self.condition(cond)), body),
+            Stmt::Every { period, body } => self.block(&format!("every {} ms do", self.operand(period)), body),
+            Stmt::After { delay, body } => self.block(&format!("after {} ms do", self.operand(delay)), body),
+            Stmt::ForEach { var, array, body } => self.block(&format!("for {} in {} do", self.name(*var), self.name(*array)), body),
+            Stmt::Sort { array, field } => self.line(&format!("sort {}{}", self.name(*array), self.by(*field))),
+            Stmt::Find { array, value, field, dest } => {
+                self.line(&format!("local {} = find {} {}{}", self.name(*dest), self.name(*array), self.operand(value), self.by(*field)));
+            }
+            Stmt::Match { subject, arms, default } => {
+                self.line(&format!("match {} is", self.name(*subject)));
+                self.indent += 1;
+                for (pattern, body) in arms {
+                    self.line(&format!("{} then", quote(pattern)));
+                    self.body(body);
+                }
+                if !default.is_empty() {
+                    self.line("else");
+                    self.body(default);
+                }
+                self.indent -= 1;
+                self.line("done");
+            }
+            Stmt::Choose { arms } => {
+                self.line("choose is");
+                self.indent += 1;
+                for (weight, body) in arms {
+                    self.line(&format!("{} then", weight));
+                    self.body(body);
+                }
+                self.indent -= 1;
+                self.line("done");
+            }
+            Stmt::Machine { name, states } => {
+                self.line(&format!("machine {} is", self.name(*name)));
+                self.indent += 1;
+                for (state, body) in states {
+                    self.line(&format!("state {}", self.name(*state)));
+                    self.body(body);
+                }
+                self.indent -= 1;
+                self.line("done");
+            }
+            Stmt::Go(state) => self.line(&format!("go {}", self.name(*state))),
+            Stmt::Delete(name) => self.line(&format!("delete {}", self.name(*name))),
+            Stmt::Label(name) => self.line(&format!("label {}", self.name(*name))),
+            Stmt::Goto(name) => self.line(&format!("goto {}", self.name(*name))),
+            Stmt::Assert(cond) => self.line(&format!("assert {}", self.condition(cond))),
+            Stmt::Handler { kind, body } => self.block(&format!("handler {} is", self.name(*kind)), body),
+            Stmt::Spawn(body) => self.block("spawn is", body),
+            Stmt::Join => self.line("join"),
+            Stmt::AtomicAdd { path: p, value } => self.line(&format!("atomic_add {} {}", self.path(p), self.operand(value))),
+            Stmt::Lock(p) => self.line(&format!("lock {}", self.path(p))),
+            Stmt::Unlock(p) => self.line(&format!("unlock {}", self.path(p))),
+            Stmt::OnInterrupt(body) => self.block("on_interrupt is", body),
+            Stmt::Run { argv, dest } => {
+                let argv: Vec<String> = argv.iter().map(|a| quote(a)).collect();
+                self.line(&format!("{}run {}", self.assign_to(*dest), argv.join(" ")));
+            }
+            Stmt::Try { body, err, rescue } => {
+                self.line("try");
+                self.body(body);
+                if err.is_some() || !rescue.is_empty() {
+                    match err {
+                        Some(e) => self.line(&format!("rescue {}", self.name(*e))),
+                        None => self.line("rescue"),
+                    }
+                    self.body(rescue);
+                }
+                self.line("done");
+            }
+            Stmt::Call { name, args, dest, from: Some(inner) } => {
+                self.line(&format!("{}{}", self.assign_to(*dest), self.pipeline(*inner, *name, args)));
+            }
+            Stmt::Call { name, args, dest, from: None } => {
+                let args: String = args.iter().map(|a| format!(" {}", self.operand(a))).collect();
+                self.line(&format!("{}call {}{}", self.assign_to(*dest), self.name(*name), args));
+            }
+            Stmt::Return(None) => self.line("return"),
+            Stmt::Return(Some(v)) => self.line(&format!("return {}", self.operand(v))),
+            Stmt::AsmBlock(code) => self.raw("asm", code),
+            Stmt::IntelBlock(code) => self.raw("intel", code),
+            Stmt::PythonBlock { script, .. } => self.python("@python is", script),
+        }
+    }
+
+    /// An `if` and any `elif`s chained onto it, down to the final `else`.
+    fn if_chain(&mut self, id: StmtId, keyword: &str) {
+        let session = self.session;
+        let (head, body, else_body) = match &session.stmts[id] {
+            Stmt::IfStmt { cond, body, else_body } => (self.condition(cond), body, else_body),
+            Stmt::IfSame { lhs, rhs, body, else_body } => (format!("{} same {}", self.path(lhs), self.path(rhs)), body, else_body),
+            Stmt::ProbIf { chance, body, else_body } => (format!("?<{}%>", self.operand(chance)), body, else_body),
+            _ => unreachable!("if_chain is only called on an if"),
+        };
+        self.line(&format!("{} {} then", keyword, head));
+        self.body(body);
+        match else_body.as_slice() {
+            [] => {}
+            &[nested] if matches!(session.stmts[nested], Stmt::IfStmt { .. } | Stmt::IfSame { .. } | Stmt::ProbIf { .. }) => {
+                return self.if_chain(nested, "elif");
+            }
+            _ => {
+                self.line("else");
+                self.body(else_body);
+            }
+        }
+        self.line("done");
+    }
+
+    /// An `@python` block under `header`. The parser dedents the body
+    /// again, so it can sit indented.
+    fn python(&mut self, header: &str, script: &str) {
+        self.line(header);
+        self.indent += 1;
+        for text in script.lines() {
+            if text.is_empty() { self.out.push('\n'); } else { self.line(text); }
+        }
+        self.indent -= 1;
+        self.line("done");
+    }
+
+    /// An `@asm`/`@intel` body. The parser trims it, so only its first line
+    /// lost its indentation; the rest are kept as written.
+    fn raw(&mut self, kind: &str, code: &str) {
+        self.line(&format!("@{} is", kind));
+        if !code.is_empty() {
+            self.indent += 1;
+            let mut lines = code.lines();
+            if let Some(first) = lines.next() { self.line(first); }
+            for text in lines {
+                self.out.push_str(text);
+                self.out.push('\n');
+            }
+            self.indent -= 1;
+        }
+        self.line("done");
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Class { name, params, fields } => {
+                let params = if params.is_empty() { String::new() } else { format!("<{}>", self.session.interner.join(params, ", ")) };
+                self.line(&format!("class {}{} is", self.name(*name), params));
+                self.indent += 1;
+                for f in fields { self.line(&self.field(f)); }
+                self.indent -= 1;
+                self.line("done");
+            }
+            Item::Func { name, params, body, public } => {
+                let params: String = params.iter().map(|f| self.field(f)).collect::<Vec<_>>().join(", ");
+                let sep = if params.is_empty() { "" } else { " " };
+                let public = if *public { "pub " } else { "" };
+                self.block(&format!("{}func {}{}{} is", public, self.name(*name), sep, params), body);
+            }
+            Item::Import { body, .. } if self.expand => self.stmts(body),
+            Item::Import { name, .. } => self.line(&format!("Get {}", name)),
+            Item::Emit { body, .. } if self.expand => self.stmts(body),
+            Item::Emit { script, .. } => self.python("@python emit is", script),
+        }
+    }
+}
+
+impl Printer<'_> {
+    fn name(&self, sym: Symbol) -> &str {
+        self.session.interner.resolve(sym)
+    }
+
+    fn field(&self, f: &Field) -> String {
+        let field = match f.class {
+            Some(class) => format!("{}: {}", self.name(f.name), self.name(class)),
+            None => self.name(f.name).to_string(),
+        };
+        match f.init {
+            Some(n) => format!("{} {}", field, number(n)),
+            None => field,
+        }
+    }
+
+    fn assign_to(&self, dest: Option<Symbol>) -> String {
+        dest.map_or(String::new(), |d| format!("local {} = ", self.name(d)))
+    }
+
+    fn by(&self, field: Option<Symbol>) -> String {
+        field.map_or(String::new(), |f| format!(" by {}", self.name(f)))
+    }
+
+    /// A pipeline ending in a call to `name` that takes the result of the
+    /// stage `inner` first: `hero -> heal 5 -> show`.
+    fn pipeline(&self, inner: StmtId, name: Symbol, args: &[Operand]) -> String {
+        let args: String = args.iter().map(|a| format!(" {}", self.operand(a))).collect();
+        let before = match &self.session.stmts[inner] {
+            Stmt::Call { name, args, from: Some(inner), .. } => self.pipeline(*inner, *name, args),
+            Stmt::Call { name, args, from: None, .. } => {
+                let rest: String = args.iter().skip(1).map(|a| format!(" {}", self.operand(a))).collect();
+                format!("{} -> {}{}", args.first().map_or(String::new(), |a| self.operand(a)), self.name(*name), rest)
+            }
+            _ => String::new(),
+        };
+        format!("{} -> {}{}", before, self.name(name), args)
+    }
+
+    /// What follows `print` or `eprint` for one of the print statements.
+    fn print_args(&self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::PrintVar(name) => self.name(*name).to_string(),
+            Stmt::PrintString(s) => quote(s),
+            Stmt::PrintList(items) => items.iter().map(|item| match item {
+                PrintItem::Str(s) => quote(s),
+                PrintItem::Value(v) => self.operand(v),
+            }).collect::<Vec<_>>().join(" "),
+            Stmt::PrintFormat { fmt, args } => {
+                let args: Vec<String> = args.iter().map(|a| self.operand(a)).collect();
+                let sep = if args.is_empty() { "" } else { " " };
+                format!("format {}{}{}", quote(fmt), sep, args.join(", "))
+            }
+            Stmt::PrintBits { bits, value } => format!("{} {}", if *bits == 4 { "hex" } else { "bin" }, self.operand(value)),
+            _ => String::new(),
+        }
+    }
+
+    /// `a.b[i].c`: index segments attach to the name before them.
+    fn path(&self, p: &[Symbol]) -> String {
+        let mut out = String::new();
+        for &segment in p {
+            let segment = self.name(segment);
+            if !out.is_empty() && !segment.starts_with('[') { out.push('.'); }
+            out.push_str(segment);
+        }
+        out
+    }
+
+    fn operand(&self, op: &Operand) -> String {
+        match op {
+            Operand::Num(n) => number(*n),
+            Operand::Path(p) => self.path(p),
+        }
+    }
+
+    /// Parenthesizes only where precedence or left associativity needs it.
+    fn expr(&self, e: &Expr) -> String {
+        match e {
+            Expr::Num(n) => number(*n),
+            Expr::Float(n) => format!("{:?}", n),
+            Expr::Str(s) => quote(s),
+            Expr::Path(p) => self.path(p),
+            Expr::Binary { op, lhs, rhs } => {
+                let (symbol, prec) = binary_op(op);
+                let (lhs, rhs) = (&self.session.exprs[*lhs], &self.session.exprs[*rhs]);
+                let wrap = |side: &Expr, paren: bool| if paren { format!("({})", self.expr(side)) } else { self.expr(side) };
+                format!("{} {} {}", wrap(lhs, precedence(lhs) < prec), symbol, wrap(rhs, precedence(rhs) <= prec))
+            }
+        }
+    }
+
+    fn condition(&self, cond: &Cond) -> String {
+        match cond {
+            Cond::Cmp { path: p, op, rhs } => {
+                let op = match op {
+                    Token::Less => "<",
+                    Token::Greater => ">",
+                    _ => "==",
+                };
+                format!("{} {} {}", self.path(p), op, self.expr(rhs))
+            }
+            Cond::And(l, r) => format!("{} and {}", self.condition(&self.session.conds[*l]), self.condition(&self.session.conds[*r])),
+            Cond::Or(l, r) => format!("{} or {}", self.condition(&self.session.conds[*l]), self.condition(&self.session.conds[*r])),
+        }
+    }
+}
+
+fn number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 { format!("{}", n as i64) } else { format!("{}", n) }
+}
+
+fn binary_op(op: &Token) -> (&'static str, u8) {
+    match op {
+        Token::Plus => ("+", 1),
+        Token::Minus => ("-", 1),
+        Token::Star => ("*", 2),
+        Token::Slash => ("/", 2),
+        _ => ("?", 0),
+    }
+}
+
+fn precedence(e: &Expr) -> u8 {
+    match e {
+        Expr::Binary { op, .. } => binary_op(op).1,
+        _ => 3,
+    }
+}
+
+/// A string literal, escaped the way the lexer reads it back.
+fn quote(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '\x1b' => out.push_str("\\e"),
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Parses `src` and prints it back, failing on any syntax error.
+    fn reprint(src: &str) -> String {
+        let mut session = Session::default();
+        let (tokens, spans) = Lexer::new(src, &mut session.interner).tokenize();
+        let mut parser = Parser::new(&tokens, &spans, &mut session);
+        let ast = parser.parse_program();
+        assert!(parser.errors.is_empty(), "{:?} in\n{}", parser.errors, src);
+        unparse(&session, &ast)
+    }
+
+    /// Printing is canonical: what it prints parses back to a tree that
+    /// prints the same.
+    fn assert_round_trip(src: &str) -> String {
+        let printed = reprint(src);
+        assert_eq!(reprint(&printed), printed);
+        printed
+    }
+
+    #[test]
+    fn every_statement_round_trips() {
+        let src = r#"Get lib
+class Hero is
+    hp
+    field target: Enemy
+done
+class Box<T, U> is
+    item: T
+done
+pub func hurt e: Enemy, amount is
+    e.hp = e.hp - amount * 2
+    return e.hp
+done
+local a = (1 + 2) * -3 - (4 - 5) / b.c
+local f = -2.0 * 1e3 + a
+local h = new Hero
+local w = new Enemy[4]
+local c = copy h
+local m = map
+local t = text
+local i = input
+t append "x\"y\\n"
+t append i
+m put "k" 3
+local v = m get "k"
+h.target = w[2]
+w[i].hp = -1.5
+print "hp:" h.hp " of " 10
+print x
+eprint format "%d %x\n" a, b
+print hex a
+print bin 3
+cls
+cursor 1 2
+color 4
+color
+if a == 1 or b < 2 and c > 3 then
+    print "a"
+elif h same c then
+    print "same"
+elif ?<h.hp%> then
+    print "roll"
+else
+    print "none"
+done
+while a > 0 do a = a - 1
+every 16 ms do
+    join
+done
+after t do print "late"
+for e in w do
+    atomic_add e.hp 1
+done
+match i is "y" then print 1
+"n" then
+    print 2
+else print 3
+done
+choose is 2 then print "x"
+1 then print "y"
+done
+label top
+goto top
+assert a < 3
+handler irq is
+    lock h.hp
+    unlock h.hp
+done
+spawn is
+    print 1
+done
+on_interrupt is
+    print 2
+done
+local r = run "ls" "-l"
+run "true"
+try
+    call hurt h 5
+rescue err
+    print err
+done
+local q = call hurt w -2
+delete q
+@asm is
+    mov x0, #1
+    mov x1, #2
+done
+@python is
+    for i in range(3):
+        print(i)
+done
+@python emit is
+    print("local z = 1")
+done
+"#;
+        let printed = assert_round_trip(src);
+        assert!(printed.contains("local a = (1 + 2) * -3 - (4 - 5) / b.c\n"), "{}", printed);
+        assert!(printed.contains("local f = -2.0 * 1000.0 + a\n"), "{}", printed);
+        assert!(printed.contains("elif h same c then\n"), "{}", printed);
+        assert!(printed.contains("after t ms do\n    print \"late\"\ndone\n"), "{}", printed);
+        assert!(printed.contains("    for i in range(3):\n        print(i)\n"), "{}", printed);
+    }
+
+    #[test]
+    fn fixtures_round_trip() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|e| e == "hmr") {
+                assert_round_trip(&std::fs::read_to_string(&path).unwrap());
+            }
+        }
+    }
+}
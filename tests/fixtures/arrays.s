@@ -0,0 +1,231 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    mov x9, #168
+    bl __hamer_heap_reserve
+    mov x9, #10
+    str x9, [x28]
+    add x12, x28, #8
+    ldr x9, =168
+    add x28, x28, x9
+    mov x13, #3
+    mov x1, #50
+    add x9, x12, #32
+    str x1, [x9, #0]
+    mov x10, #16
+    madd x9, x13, x10, x12
+    ldr x0, [x9, #8]
+    add x1, x0, #4
+    mov x10, #16
+    madd x9, x13, x10, x12
+    str x1, [x9, #8]
+    add x9, x12, #32
+    ldr x0, [x9, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr0: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x1, #9
+    ldur x10, [x12, #-8]
+    cmp x13, x10
+    b.lo .Lok2
+    mov x9, #3
+    b .Ltry1_rescue
+.Lok2:
+    mov x10, #16
+    madd x9, x13, x10, x12
+    str x1, [x9, #0]
+    b .Ltry1_end
+.Ltry1_rescue:
+    mov x14, x9
+    mov x0, x14
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr3: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #1
+    mov x8, #64
+    svc #0
+.Ltry1_end:
+    mov x14, x12
+    ldr x9, =160
+    add x15, x12, x9
+.Lfor4:
+    cmp x14, x15
+    b.hs .Lfor4_end
+    ldr x0, [x14, #0]
+    add x1, x0, #1
+    str x1, [x14, #0]
+    ldr x0, [x14, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr5: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    add x14, x14, #16
+    b .Lfor4
+.Lfor4_end:
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr6: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr6
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 8
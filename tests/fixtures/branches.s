@@ -0,0 +1,153 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #0
+.Lwhile0:
+    mov x1, x12
+    cmp x1, #4
+    b.ge .Lwhile0_end
+    mov x1, x12
+    cmp x1, #0
+    b.ne .Lif1_else
+
+.section .data
+.Lstr2: .ascii "zero\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    b .Lif1_end
+.Lif1_else:
+    mov x1, x12
+    cmp x1, #1
+    b.ne .Lif3_else
+
+.section .data
+.Lstr4: .ascii "one\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    b .Lif3_end
+.Lif3_else:
+    mov x1, x12
+    cmp x1, #2
+    b.ne .Lif5_else
+    mov x1, x12
+    cmp x1, #1
+    b.le .Lif5_else
+
+.section .data
+.Lstr6: .ascii "two\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr6
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    b .Lif5_end
+.Lif5_else:
+
+.section .data
+.Lstr7: .ascii "many\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr7
+    mov x2, #5
+    mov x8, #64
+    svc #0
+.Lif5_end:
+.Lif3_end:
+.Lif1_end:
+    add x12, x12, #1
+    b .Lwhile0
+.Lwhile0_end:
+    mov x1, x12
+    cmp x1, #9
+    b.le .Lif8_else
+
+.section .data
+.Lstr9: .ascii "big\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr9
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    b .Lif8_end
+.Lif8_else:
+
+.section .data
+.Lstr10: .ascii "small\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr10
+    mov x2, #6
+    mov x8, #64
+    svc #0
+.Lif8_end:
+    mov x13, #0
+
+    // Chaos Roll odds%
+    adr x3, .Lrng_state
+    ldr x1, [x3]
+    cmp x1, #0
+    b.ne .Lprob11_skip
+    mrs x1, cntvct_el0
+.Lprob11_skip:
+    ldr x2, =0x9E3779B97F4A7C15
+    mul x1, x1, x2
+    eor x1, x1, x1, lsr #33
+    str x1, [x3]
+    and x1, x1, #0x7FFFFFFF
+    mov x2, #100
+    udiv x3, x1, x2
+    msub x1, x3, x2, x1
+    mov x2, x13
+    cmp x1, x2
+    b.ge .Lprob11_else
+
+.section .data
+.Lstr12: .ascii "never\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr12
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    b .Lprob11_end
+.Lprob11_else:
+
+.section .data
+.Lstr13: .ascii "always\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr13
+    mov x2, #7
+    mov x8, #64
+    svc #0
+.Lprob11_end:
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.section .bss
+.balign 8
+.Lrng_state: .zero 8
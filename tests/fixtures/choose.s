@@ -0,0 +1,78 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+
+    // Weighted choice out of 100
+    adr x3, .Lrng_state
+    ldr x1, [x3]
+    cmp x1, #0
+    b.ne .Lchoose0_skip
+    mrs x1, cntvct_el0
+.Lchoose0_skip:
+    ldr x2, =0x9E3779B97F4A7C15
+    mul x1, x1, x2
+    eor x1, x1, x1, lsr #33
+    str x1, [x3]
+    and x1, x1, #0x7FFFFFFF
+    mov x2, #100
+    udiv x3, x1, x2
+    msub x1, x3, x2, x1
+    cmp x1, #50
+    b.lo .Lchoose0_0
+    cmp x1, #80
+    b.lo .Lchoose0_1
+    b .Lchoose0_3
+.Lchoose0_0:
+
+.section .data
+.Lstr1: .ascii "common\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #7
+    mov x8, #64
+    svc #0
+    b .Lchoose0_end
+.Lchoose0_1:
+
+.section .data
+.Lstr2: .ascii "rare\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    b .Lchoose0_end
+.Lchoose0_3:
+
+.section .data
+.Lstr3: .ascii "epic\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    b .Lchoose0_end
+.Lchoose0_end:
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.section .bss
+.balign 8
+.Lrng_state: .zero 8
@@ -0,0 +1,52 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #5
+    mov x13, #0
+.Lwhile0:
+    mov x1, x12
+    cmp x1, #0
+    b.le .Lwhile0_end
+    mov x1, x13
+    cmp x1, #10
+    b.ge .Lwhile0_end
+    sub x12, x12, #1
+    add x13, x13, #1
+    b .Lwhile0
+.Lwhile0_end:
+    mov x1, x12
+    cmp x1, #1
+    b.eq .Lcond2
+    mov x1, x13
+    cmp x1, #2
+    b.ne .Lif1_end
+    mov x1, x12
+    cmp x1, #3
+    b.ge .Lif1_end
+.Lcond2:
+
+.section .data
+.Lstr3: .ascii "hit\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #4
+    mov x8, #64
+    svc #0
+.Lif1_end:
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
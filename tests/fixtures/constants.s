@@ -0,0 +1,183 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    ldr x12, =123456789
+    mov x0, #0
+    ldr x1, =70000
+    sub x13, x0, x1
+    ldr x1, =100000
+    add x14, x12, x1
+    mov x0, x14
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr0: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x13
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr1: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x1, x12
+    ldr x2, =100000
+    cmp x1, x2
+    b.le .Lif2_else
+
+.section .data
+.Lstr3: .ascii "big\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    b .Lif2_end
+.Lif2_else:
+
+.section .data
+.Lstr4: .ascii "small\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #6
+    mov x8, #64
+    svc #0
+.Lif2_end:
+    mov x1, x14
+    ldr x2, =123556789
+    cmp x1, x2
+    b.ne .Lif5_else
+
+.section .data
+.Lstr6: .ascii "same\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr6
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    b .Lif5_end
+.Lif5_else:
+
+.section .data
+.Lstr7: .ascii "differs\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr7
+    mov x2, #8
+    mov x8, #64
+    svc #0
+.Lif5_end:
+    mov x15, #4294967296
+    mov x0, x15
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr8: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr8
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
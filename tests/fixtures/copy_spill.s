@@ -0,0 +1,279 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    sub sp, sp, #32
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    mov x12, #0
+    mov x13, #0
+    mov x14, #0
+    mov x15, #0
+    mov x16, #0
+    mov x17, #0
+    mov x18, #0
+    mov x19, #0
+    mov x20, #0
+    mov x21, #0
+    mov x22, #0
+    mov x23, #0
+    mov x24, #0
+    mov x25, #0
+    mov x26, #0
+    mov x27, #0
+    adr x10, .Lfree0
+    ldr x9, [x10]
+    cbz x9, .Lreuse0
+    ldr x11, [x9]
+    str x11, [x10]
+    str xzr, [x9, #0]
+    str xzr, [x9, #8]
+    b .Lreuse0_done
+.Lreuse0:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x9, x28
+    add x28, x28, #16
+.Lreuse0_done:
+    str x9, [x29, #16]
+    ldr x9, [x29, #16]
+    cbz x9, .Ldelete1
+    adr x10, .Lfree0
+    ldr x11, [x10]
+    str x11, [x9]
+    str x9, [x10]
+.Ldelete1:
+    str xzr, [x29, #16]
+    adr x10, .Lfree0
+    ldr x9, [x10]
+    cbz x9, .Lreuse2
+    ldr x11, [x9]
+    str x11, [x10]
+    str xzr, [x9, #0]
+    str xzr, [x9, #8]
+    b .Lreuse2_done
+.Lreuse2:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x9, x28
+    add x28, x28, #16
+.Lreuse2_done:
+    str x9, [x29, #24]
+    mov x1, #3
+    ldr x9, [x29, #24]
+    str x1, [x9, #0]
+    mov x1, #4
+    ldr x9, [x29, #24]
+    str x1, [x9, #8]
+    adr x10, .Lfree0
+    ldr x9, [x10]
+    cbz x9, .Lreuse3
+    ldr x11, [x9]
+    str x11, [x10]
+    str xzr, [x9, #0]
+    str xzr, [x9, #8]
+    b .Lreuse3_done
+.Lreuse3:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x9, x28
+    add x28, x28, #16
+.Lreuse3_done:
+    str x9, [x29, #32]
+    ldr x10, [x29, #24]
+    ldr x1, [x10, #0]
+    str x1, [x9, #0]
+    ldr x1, [x10, #8]
+    str x1, [x9, #8]
+    ldr x9, [x29, #32]
+    ldr x0, [x9, #0]
+    add x1, x0, #10
+    ldr x9, [x29, #32]
+    str x1, [x9, #0]
+    ldr x9, [x29, #24]
+    ldr x0, [x9, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr4: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    ldr x9, [x29, #32]
+    ldr x0, [x9, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr5: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    ldr x9, [x29, #32]
+    ldr x0, [x9, #8]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr6: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr6
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr7: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr7
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lfree0: .zero 8
+.balign 8
+.Lheap_end: .zero 8
@@ -0,0 +1,498 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    mov x9, #3592
+    bl __hamer_heap_reserve
+    mov x9, #64
+    str x9, [x28]
+    add x12, x28, #8
+    ldr x9, =3592
+    add x28, x28, x9
+    mov x13, #0
+    mov x0, #1
+    bl fn_ecs_spawn
+    mov x22, x0
+    mov x1, #3
+    mov x10, #56
+    madd x9, x22, x10, x12
+    str x1, [x9, #48]
+    mov x1, #2
+    mov x10, #56
+    madd x9, x22, x10, x12
+    str x1, [x9, #32]
+    mov x0, #2
+    bl fn_ecs_spawn
+    mov x23, x0
+    mov x1, #1
+    mov x10, #56
+    madd x9, x23, x10, x12
+    str x1, [x9, #48]
+    mov x1, #-1
+    mov x10, #56
+    madd x9, x23, x10, x12
+    str x1, [x9, #40]
+    bl fn_ecs_move
+    bl fn_ecs_move
+    mov x0, #-1
+    bl fn_ecs_next
+    mov x24, x0
+.Lwhile8:
+    mov x1, x24
+    cmn x1, #1
+    b.le .Lwhile8_end
+    mov x10, #56
+    madd x9, x24, x10, x12
+    ldr x0, [x9, #8]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr9: .ascii " at "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr9
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    mov x10, #56
+    madd x9, x24, x10, x12
+    ldr x0, [x9, #16]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr10: .ascii ","
+.section .text
+    mov x0, #1
+    adr x1, .Lstr10
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x10, #56
+    madd x9, x24, x10, x12
+    ldr x0, [x9, #24]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr11: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr11
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x24
+    bl fn_ecs_next
+    mov x24, x0
+    b .Lwhile8
+.Lwhile8_end:
+    mov x1, #0
+    mov x10, #56
+    madd x9, x23, x10, x12
+    str x1, [x9, #48]
+    bl fn_ecs_reap
+    mov x0, x13
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr12: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr12
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, #3
+    bl fn_ecs_spawn
+    mov x25, x0
+    mov x0, x25
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr13: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr13
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x10, #56
+    madd x9, x25, x10, x12
+    ldr x0, [x9, #8]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr14: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr14
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x10, #56
+    madd x9, x25, x10, x12
+    ldr x0, [x9, #24]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr15: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr15
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.type fn_ecs_spawn, %function
+fn_ecs_spawn:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_ecs_spawn_body:
+    mov x14, x0
+    mov x0, x12
+    mov x1, #64
+    mov x2, #56
+    mov x3, #0
+    mov x4, #0
+    bl __hamer_find
+    mov x15, x0
+    mov x1, x15
+    cmp x1, #0
+    b.ge .Lif0_end
+    mov x0, x15
+    b .Lfn_ecs_spawn_ret
+.Lif0_end:
+    mov x1, #1
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #0]
+    mov x1, x14
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #8]
+    mov x1, #0
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #16]
+    mov x1, #0
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #24]
+    mov x1, #0
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #32]
+    mov x1, #0
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #40]
+    mov x1, #0
+    mov x10, #56
+    madd x9, x15, x10, x12
+    str x1, [x9, #48]
+    add x13, x13, #1
+    mov x0, x15
+    b .Lfn_ecs_spawn_ret
+.Lfn_ecs_spawn_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_ecs_spawn, . - fn_ecs_spawn
+
+.type fn_ecs_kill, %function
+fn_ecs_kill:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_ecs_kill_body:
+    mov x16, x0
+    mov x10, #56
+    madd x9, x16, x10, x12
+    ldr x1, [x9, #0]
+    cmp x1, #1
+    b.ne .Lif1_end
+    mov x1, #0
+    mov x10, #56
+    madd x9, x16, x10, x12
+    str x1, [x9, #0]
+    sub x13, x13, #1
+.Lif1_end:
+.Lfn_ecs_kill_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_ecs_kill, . - fn_ecs_kill
+
+.type fn_ecs_next, %function
+fn_ecs_next:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_ecs_next_body:
+    mov x17, x0
+    add x18, x17, #1
+.Lwhile2:
+    mov x1, x18
+    cmp x1, #64
+    b.ge .Lwhile2_end
+    mov x10, #56
+    madd x9, x18, x10, x12
+    ldr x1, [x9, #0]
+    cmp x1, #1
+    b.ne .Lif3_end
+    mov x0, x18
+    b .Lfn_ecs_next_ret
+.Lif3_end:
+    add x18, x18, #1
+    b .Lwhile2
+.Lwhile2_end:
+    mov x0, #-1
+    b .Lfn_ecs_next_ret
+.Lfn_ecs_next_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_ecs_next, . - fn_ecs_next
+
+.type fn_ecs_move, %function
+fn_ecs_move:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_ecs_move_body:
+    mov x19, x12
+    ldr x9, =3584
+    add x20, x12, x9
+.Lfor4:
+    cmp x19, x20
+    b.hs .Lfor4_end
+    ldr x1, [x19, #0]
+    cmp x1, #1
+    b.ne .Lif5_end
+    ldr x0, [x19, #16]
+    ldr x1, [x19, #32]
+    add x1, x0, x1
+    str x1, [x19, #16]
+    ldr x0, [x19, #24]
+    ldr x1, [x19, #40]
+    add x1, x0, x1
+    str x1, [x19, #24]
+.Lif5_end:
+    add x19, x19, #56
+    b .Lfor4
+.Lfor4_end:
+.Lfn_ecs_move_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_ecs_move, . - fn_ecs_move
+
+.type fn_ecs_reap, %function
+fn_ecs_reap:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_ecs_reap_body:
+    mov x0, #-1
+    bl fn_ecs_next
+    mov x21, x0
+.Lwhile6:
+    mov x1, x21
+    cmn x1, #1
+    b.le .Lwhile6_end
+    mov x10, #56
+    madd x9, x21, x10, x12
+    ldr x1, [x9, #48]
+    cmp x1, #1
+    b.ge .Lif7_end
+    mov x0, x21
+    bl fn_ecs_kill
+.Lif7_end:
+    mov x0, x21
+    bl fn_ecs_next
+    mov x21, x0
+    b .Lwhile6
+.Lwhile6_end:
+.Lfn_ecs_reap_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_ecs_reap, . - fn_ecs_reap
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr16: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr16
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_find, %function
+
+__hamer_find:
+    add x5, x0, x3
+    mov x0, #0
+.Lrt_fi_loop:
+    cmp x0, x1
+    b.hs .Lrt_fi_miss
+    ldr x6, [x5]
+    cmp x6, x4
+    b.eq .Lrt_fi_done
+    add x5, x5, x2
+    add x0, x0, #1
+    b .Lrt_fi_loop
+.Lrt_fi_miss:
+    mov x0, #-1
+.Lrt_fi_done:
+    ret
+.size __hamer_find, . - __hamer_find
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 8
@@ -0,0 +1,128 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #0
+    mov x0, #1
+    adr x1, .Levery0_deadline
+    mov x8, #113
+    svc #0
+.Levery0:
+    add x12, x12, #1
+    mov x0, x12
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr1: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, #16
+    mov x9, #0x4240
+    movk x9, #0xf, lsl #16
+    mul x0, x0, x9
+    adr x2, .Levery0_deadline
+    ldp x9, x10, [x2]
+    add x10, x10, x0
+    mov x11, #0xca00
+    movk x11, #0x3b9a, lsl #16
+.Levery0_carry:
+    cmp x10, x11
+    b.lo .Levery0_sleep
+    sub x10, x10, x11
+    add x9, x9, #1
+    b .Levery0_carry
+.Levery0_sleep:
+    stp x9, x10, [x2]
+    mov x0, #1
+    mov x1, #1
+    mov x3, #0
+    mov x8, #115
+    svc #0
+    b .Levery0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Levery0_deadline: .zero 16
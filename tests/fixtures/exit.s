@@ -0,0 +1,49 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #3
+.Lwhile0:
+    mov x1, x12
+    cmp x1, #0
+    b.le .Lwhile0_end
+    sub x12, x12, #1
+    b .Lwhile0
+.Lwhile0_end:
+
+.section .data
+.Lstr1: .ascii "giving up\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #10
+    mov x8, #64
+    svc #0
+    add x13, x12, #2
+    mov x0, x13
+    b .Lexit2
+
+.section .data
+.Lstr3: .ascii "not reached\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #12
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+.Lexit2:
+    mov x8, #93
+    svc #0
+.size _start, . - _start
@@ -0,0 +1,449 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    ldr x9, =4614256650576692846
+    fmov d0, x9
+    fmov x12, d0
+    mov x13, #2
+    fmov d0, x12
+    scvtf d1, x13
+    fmul d0, d0, d1
+    scvtf d1, x13
+    fmul d0, d0, d1
+    fmov x14, d0
+    mov x0, x14
+    mov x8, #1
+    bl __hamer_print_float
+
+.section .data
+.Lstr0: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x9, #4607182418800017408
+    fmov d0, x9
+    mov x9, #4611686018427387904
+    fmov d1, x9
+    fdiv d0, d0, d1
+    fmov x15, d0
+    mov x0, x15
+    mov x8, #1
+    bl __hamer_print_float
+
+.section .data
+.Lstr1: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x9, #24
+    bl __hamer_heap_reserve
+    mov x16, x28
+    add x28, x28, #24
+    mov x9, #4609434218613702656
+    fmov d0, x9
+    fmov x1, d0
+    str x1, [x16, #8]
+    ldr x9, [x16, #8]
+    fmov d0, x9
+    mov x9, #4611686018427387904
+    fmov d1, x9
+    fmul d0, d0, d1
+    fmov d1, x15
+    fadd d0, d0, d1
+    fmov x1, d0
+    str x1, [x16, #8]
+
+.section .data
+.Lstr2: .ascii "speed "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    ldr x0, [x16, #8]
+    mov x8, #1
+    bl __hamer_print_float
+
+.section .data
+.Lstr3: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    ldr x9, =4622241330054037504
+    fmov d0, x9
+    fcvtzs x1, d0
+    str x1, [x16, #16]
+    ldr x0, [x16, #16]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr4: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x17, #7
+    scvtf d0, x17
+    mov x9, #4611686018427387904
+    fmov d1, x9
+    fdiv d0, d0, d1
+    fcvtzs x17, d0
+    mov x0, x17
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr5: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    fmov d0, x14
+    mov x9, #4623226492472524800
+    fmov d1, x9
+    fcmp d0, d1
+    b.le .Lif6_end
+
+.section .data
+.Lstr7: .ascii "big\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr7
+    mov x2, #4
+    mov x8, #64
+    svc #0
+.Lif6_end:
+    ldr x9, =4591870180066957722
+    fmov d0, x9
+    ldr x9, =4596373779694328218
+    fmov d1, x9
+    fadd d0, d0, d1
+    fmov x18, d0
+    fmov d0, x18
+    ldr x9, =4599075939470750515
+    fmov d1, x9
+    fcmp d0, d1
+    b.ne .Lif8_else
+
+.section .data
+.Lstr9: .ascii "exact\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr9
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    b .Lif8_end
+.Lif8_else:
+
+.section .data
+.Lstr10: .ascii "close\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr10
+    mov x2, #6
+    mov x8, #64
+    svc #0
+.Lif8_end:
+    ldr x9, =4652007308841189376
+    fmov d0, x9
+    mov x9, #4598175219545276416
+    fmov d1, x9
+    fadd d0, d0, d1
+    fmov x19, d0
+    mov x0, x19
+    mov x8, #1
+    bl __hamer_print_float
+
+.section .data
+.Lstr11: .ascii " 2.5\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr11
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    mov x9, #-4611686018427387904
+    fmov d0, x9
+    fmov x20, d0
+    mov x0, x20
+    mov x8, #1
+    bl __hamer_print_float
+
+.section .data
+.Lstr12: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr12
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x19
+    fmov d0, x0
+    fcvtzs x0, d0
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr13: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr13
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x9, #0
+    fmov d0, x9
+    fmov x21, d0
+.Lwhile14:
+    fmov d0, x21
+    mov x9, #4607182418800017408
+    fmov d1, x9
+    fcmp d0, d1
+    b.ge .Lwhile14_end
+    fmov d0, x21
+    mov x9, #4598175219545276416
+    fmov d1, x9
+    fadd d0, d0, d1
+    fmov x21, d0
+    b .Lwhile14
+.Lwhile14_end:
+    mov x0, x21
+    mov x8, #1
+    bl __hamer_print_float
+
+.section .data
+.Lstr15: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr15
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr16: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr16
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_print_float, %function
+
+__hamer_print_float:
+    sub sp, sp, #64
+    fmov d0, x0
+    mov x9, #0
+    fcmp d0, #0.0
+    b.pl .Lrt_pf_scale
+    fneg d0, d0
+    mov x9, #1
+.Lrt_pf_scale:
+    mov x1, #0x4240
+    movk x1, #0xf, lsl #16
+    ucvtf d1, x1
+    fmul d0, d0, d1
+    fcvtau x0, d0
+    cmp x0, #0
+    csel x9, xzr, x9, eq
+    udiv x2, x0, x1
+    msub x3, x2, x1, x0
+    add x5, sp, #64
+    mov x4, #6
+    mov x7, #0
+    mov x1, #10
+.Lrt_pf_frac:
+    udiv x6, x3, x1
+    msub x0, x6, x1, x3
+    mov x3, x6
+    cbnz x7, .Lrt_pf_keep
+    cbnz x0, .Lrt_pf_keep
+    cmp x4, #1
+    b.ne .Lrt_pf_next
+.Lrt_pf_keep:
+    mov x7, #1
+    add x0, x0, #48
+    sub x5, x5, #1
+    strb w0, [x5]
+.Lrt_pf_next:
+    subs x4, x4, #1
+    b.ne .Lrt_pf_frac
+    mov w0, #46
+    sub x5, x5, #1
+    strb w0, [x5]
+.Lrt_pf_whole:
+    udiv x6, x2, x1
+    msub x0, x6, x1, x2
+    add x0, x0, #48
+    sub x5, x5, #1
+    strb w0, [x5]
+    mov x2, x6
+    cbnz x2, .Lrt_pf_whole
+    cbz x9, .Lrt_pf_write
+    mov w0, #45
+    sub x5, x5, #1
+    strb w0, [x5]
+.Lrt_pf_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #64
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #64
+    ret
+.size __hamer_print_float, . - __hamer_print_float
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 8
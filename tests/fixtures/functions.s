@@ -0,0 +1,229 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x0, #1
+    mov x1, #2
+    bl fn_add
+    mov x14, x0
+    mov x0, x14
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr0: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    adr x9, .Lsigint2
+    sub sp, sp, #32
+    stp x9, xzr, [sp]
+    stp xzr, xzr, [sp, #16]
+    mov x0, #2
+    mov x1, sp
+    mov x2, #0
+    mov x3, #8
+    mov x8, #134
+    svc #0
+    add sp, sp, #32
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.type fn_add, %function
+fn_add:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_add_body:
+    mov x12, x0
+    mov x13, x1
+    add x12, x12, #1
+    mov x0, x12
+    b .Lfn_add_ret
+.Lfn_add_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_add, . - fn_add
+
+.Lvec_irq:
+    sub sp, sp, #272
+    stp x0, x1, [sp, #0]
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    stp x6, x7, [sp, #48]
+    stp x8, x9, [sp, #64]
+    stp x10, x11, [sp, #80]
+    stp x12, x13, [sp, #96]
+    stp x14, x15, [sp, #112]
+    stp x16, x17, [sp, #128]
+    stp x18, x19, [sp, #144]
+    stp x20, x21, [sp, #160]
+    stp x22, x23, [sp, #176]
+    stp x24, x25, [sp, #192]
+    stp x26, x27, [sp, #208]
+    stp x28, x29, [sp, #224]
+    mrs x0, elr_el1
+    mrs x1, spsr_el1
+    stp x30, x0, [sp, #240]
+    str x1, [sp, #256]
+
+.section .data
+.Lstr1: .ascii "irq\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    ldr x1, [sp, #256]
+    ldp x30, x0, [sp, #240]
+    msr elr_el1, x0
+    msr spsr_el1, x1
+    ldp x28, x29, [sp, #224]
+    ldp x26, x27, [sp, #208]
+    ldp x24, x25, [sp, #192]
+    ldp x22, x23, [sp, #176]
+    ldp x20, x21, [sp, #160]
+    ldp x18, x19, [sp, #144]
+    ldp x16, x17, [sp, #128]
+    ldp x14, x15, [sp, #112]
+    ldp x12, x13, [sp, #96]
+    ldp x10, x11, [sp, #80]
+    ldp x8, x9, [sp, #64]
+    ldp x6, x7, [sp, #48]
+    ldp x4, x5, [sp, #32]
+    ldp x2, x3, [sp, #16]
+    ldp x0, x1, [sp, #0]
+    add sp, sp, #272
+    eret
+
+.Lsigint2:
+
+.section .data
+.Lstr3: .ascii "bye\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #4
+    mov x8, #64
+    svc #0
+    mov x0, #130
+    mov x8, #94
+    svc #0
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.balign 2048
+.global hamer_vectors
+.type hamer_vectors, %function
+hamer_vectors:
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .Lvec_irq
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+    b .
+    .balign 128
+.size hamer_vectors, . - hamer_vectors
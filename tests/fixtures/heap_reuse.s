@@ -0,0 +1,322 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    mov x12, #0
+    mov x13, #0
+.Lwhile0:
+    mov x1, x13
+    ldr x2, =200000
+    cmp x1, x2
+    b.ge .Lwhile0_end
+    adr x10, .Lfree0
+    ldr x14, [x10]
+    cbz x14, .Lreuse1
+    ldr x11, [x14]
+    str x11, [x10]
+    str xzr, [x14, #0]
+    str xzr, [x14, #8]
+    b .Lreuse1_done
+.Lreuse1:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x14, x28
+    add x28, x28, #16
+.Lreuse1_done:
+    mov x10, #3
+    str x10, [x14, #8]
+    mov x1, x13
+    str x1, [x14, #0]
+    mov x1, x13
+    ldr x2, =199999
+    cmp x1, x2
+    b.ne .Lif2_end
+    ldr x0, [x14, #0]
+    ldr x1, [x14, #8]
+    add x12, x0, x1
+.Lif2_end:
+    cbz x14, .Ldelete3
+    adr x10, .Lfree0
+    ldr x11, [x10]
+    str x11, [x14]
+    str x14, [x10]
+.Ldelete3:
+    mov x14, xzr
+    add x13, x13, #1
+    b .Lwhile0
+.Lwhile0_end:
+    mov x0, x12
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr4: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    adr x10, .Lfree0
+    ldr x15, [x10]
+    cbz x15, .Lreuse5
+    ldr x11, [x15]
+    str x11, [x10]
+    str xzr, [x15, #0]
+    str xzr, [x15, #8]
+    b .Lreuse5_done
+.Lreuse5:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x15, x28
+    add x28, x28, #16
+.Lreuse5_done:
+    mov x10, #3
+    str x10, [x15, #8]
+    adr x10, .Lfree0
+    ldr x16, [x10]
+    cbz x16, .Lreuse6
+    ldr x11, [x16]
+    str x11, [x10]
+    str xzr, [x16, #0]
+    str xzr, [x16, #8]
+    b .Lreuse6_done
+.Lreuse6:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x16, x28
+    add x28, x28, #16
+.Lreuse6_done:
+    mov x10, #3
+    str x10, [x16, #8]
+    cbz x15, .Ldelete7
+    adr x10, .Lfree0
+    ldr x11, [x10]
+    str x11, [x15]
+    str x15, [x10]
+.Ldelete7:
+    mov x15, xzr
+    adr x10, .Lfree0
+    ldr x17, [x10]
+    cbz x17, .Lreuse8
+    ldr x11, [x17]
+    str x11, [x10]
+    str xzr, [x17, #0]
+    str xzr, [x17, #8]
+    b .Lreuse8_done
+.Lreuse8:
+    mov x9, #16
+    bl __hamer_heap_reserve
+    mov x17, x28
+    add x28, x28, #16
+.Lreuse8_done:
+    mov x10, #3
+    str x10, [x17, #8]
+    ldr x0, [x17, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr9: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr9
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    ldr x0, [x17, #8]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr10: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr10
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    cbz x15, .Ldelete11
+    adr x10, .Lfree0
+    ldr x11, [x10]
+    str x11, [x15]
+    str x15, [x10]
+.Ldelete11:
+    mov x15, xzr
+    ldr x9, =2400008
+    bl __hamer_heap_reserve
+    ldr x9, =300000
+    str x9, [x28]
+    add x18, x28, #8
+    ldr x9, =2400008
+    add x28, x28, x9
+    mov x1, #7
+    ldr x10, =2399992
+    add x9, x18, x10
+    str x1, [x9, #0]
+    ldr x10, =2399992
+    add x9, x18, x10
+    ldr x0, [x9, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr12: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr12
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr13: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr13
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lfree0: .zero 8
+.balign 8
+.Lheap_end: .zero 8
@@ -0,0 +1,34 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.type fn_triple, %function
+fn_triple:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_triple_body:
+    mov x12, x0
+    mov x1, #3
+    mul x12, x12, x1
+    mov x0, x12
+    b .Lfn_triple_ret
+.Lfn_triple_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_triple, . - fn_triple
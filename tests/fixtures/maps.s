@@ -0,0 +1,802 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    mov x9, #544
+    bl __hamer_heap_reserve
+    mov x12, x28
+    add x28, x28, #544
+    add x10, x12, #32
+    mov x11, #31
+    stp x10, x11, [x12]
+    mov x0, x12
+    mov x2, #1
+
+.section .data
+.balign 8
+.Lkey0: .quad 0x8be0e07b562230e
+.section .text
+    adr x1, .Lkey0
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #4
+
+.section .data
+.balign 8
+.Lkey1: .quad 0x8be0f07b56224c1
+.section .text
+    adr x1, .Lkey1
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #7
+
+.section .data
+.balign 8
+.Lkey2: .quad 0x8be0c07b5621fa8
+.section .text
+    adr x1, .Lkey2
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #10
+
+.section .data
+.balign 8
+.Lkey3: .quad 0x8be0d07b562215b
+.section .text
+    adr x1, .Lkey3
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #13
+
+.section .data
+.balign 8
+.Lkey4: .quad 0x8be1207b56229da
+.section .text
+    adr x1, .Lkey4
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #16
+
+.section .data
+.balign 8
+.Lkey5: .quad 0x8be1307b5622b8d
+.section .text
+    adr x1, .Lkey5
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #19
+
+.section .data
+.balign 8
+.Lkey6: .quad 0x8be1007b5622674
+.section .text
+    adr x1, .Lkey6
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #22
+
+.section .data
+.balign 8
+.Lkey7: .quad 0x8be1107b5622827
+.section .text
+    adr x1, .Lkey7
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #25
+
+.section .data
+.balign 8
+.Lkey8: .quad 0x8be0607b5621576
+.section .text
+    adr x1, .Lkey8
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #28
+
+.section .data
+.balign 8
+.Lkey9: .quad 0x8be0707b5621729
+.section .text
+    adr x1, .Lkey9
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #31
+
+.section .data
+.balign 8
+.Lkey10: .quad 0x3d187b1935c4c583
+.section .text
+    adr x1, .Lkey10
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #34
+
+.section .data
+.balign 8
+.Lkey11: .quad 0x3d187a1935c4c3d0
+.section .text
+    adr x1, .Lkey11
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #37
+
+.section .data
+.balign 8
+.Lkey12: .quad 0x3d187d1935c4c8e9
+.section .text
+    adr x1, .Lkey12
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #40
+
+.section .data
+.balign 8
+.Lkey13: .quad 0x3d187c1935c4c736
+.section .text
+    adr x1, .Lkey13
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #43
+
+.section .data
+.balign 8
+.Lkey14: .quad 0x3d187f1935c4cc4f
+.section .text
+    adr x1, .Lkey14
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #46
+
+.section .data
+.balign 8
+.Lkey15: .quad 0x3d187e1935c4ca9c
+.section .text
+    adr x1, .Lkey15
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #49
+
+.section .data
+.balign 8
+.Lkey16: .quad 0x3d18811935c4cfb5
+.section .text
+    adr x1, .Lkey16
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #52
+
+.section .data
+.balign 8
+.Lkey17: .quad 0x3d18801935c4ce02
+.section .text
+    adr x1, .Lkey17
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #55
+
+.section .data
+.balign 8
+.Lkey18: .quad 0x3d18831935c4d31b
+.section .text
+    adr x1, .Lkey18
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #58
+
+.section .data
+.balign 8
+.Lkey19: .quad 0x3d18821935c4d168
+.section .text
+    adr x1, .Lkey19
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #61
+
+.section .data
+.balign 8
+.Lkey20: .quad 0x3d0e091935bbaf48
+.section .text
+    adr x1, .Lkey20
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #64
+
+.section .data
+.balign 8
+.Lkey21: .quad 0x3d0e0a1935bbb0fb
+.section .text
+    adr x1, .Lkey21
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #67
+
+.section .data
+.balign 8
+.Lkey22: .quad 0x3d0e0b1935bbb2ae
+.section .text
+    adr x1, .Lkey22
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #70
+
+.section .data
+.balign 8
+.Lkey23: .quad 0x3d0e0c1935bbb461
+.section .text
+    adr x1, .Lkey23
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #73
+
+.section .data
+.balign 8
+.Lkey24: .quad 0x3d0e0d1935bbb614
+.section .text
+    adr x1, .Lkey24
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #76
+
+.section .data
+.balign 8
+.Lkey25: .quad 0x3d0e0e1935bbb7c7
+.section .text
+    adr x1, .Lkey25
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #79
+
+.section .data
+.balign 8
+.Lkey26: .quad 0x3d0e0f1935bbb97a
+.section .text
+    adr x1, .Lkey26
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #82
+
+.section .data
+.balign 8
+.Lkey27: .quad 0x3d0e101935bbbb2d
+.section .text
+    adr x1, .Lkey27
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #85
+
+.section .data
+.balign 8
+.Lkey28: .quad 0x3d0e011935bba1b0
+.section .text
+    adr x1, .Lkey28
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #88
+
+.section .data
+.balign 8
+.Lkey29: .quad 0x3d0e021935bba363
+.section .text
+    adr x1, .Lkey29
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #91
+
+.section .data
+.balign 8
+.Lkey30: .quad 0x3d118f1935bec8d1
+.section .text
+    adr x1, .Lkey30
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #94
+
+.section .data
+.balign 8
+.Lkey31: .quad 0x3d118e1935bec71e
+.section .text
+    adr x1, .Lkey31
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #97
+
+.section .data
+.balign 8
+.Lkey32: .quad 0x3d118d1935bec56b
+.section .text
+    adr x1, .Lkey32
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #100
+
+.section .data
+.balign 8
+.Lkey33: .quad 0x3d118c1935bec3b8
+.section .text
+    adr x1, .Lkey33
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #103
+
+.section .data
+.balign 8
+.Lkey34: .quad 0x3d11931935becf9d
+.section .text
+    adr x1, .Lkey34
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #106
+
+.section .data
+.balign 8
+.Lkey35: .quad 0x3d11921935becdea
+.section .text
+    adr x1, .Lkey35
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #109
+
+.section .data
+.balign 8
+.Lkey36: .quad 0x3d11911935becc37
+.section .text
+    adr x1, .Lkey36
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #112
+
+.section .data
+.balign 8
+.Lkey37: .quad 0x3d11901935beca84
+.section .text
+    adr x1, .Lkey37
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #115
+
+.section .data
+.balign 8
+.Lkey38: .quad 0x3d11871935bebb39
+.section .text
+    adr x1, .Lkey38
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #118
+
+.section .data
+.balign 8
+.Lkey39: .quad 0x3d11861935beb986
+.section .text
+    adr x1, .Lkey39
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #121
+
+.section .data
+.balign 8
+.Lkey40: .quad 0x3d228d1935cd389e
+.section .text
+    adr x1, .Lkey40
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #124
+
+.section .data
+.balign 8
+.Lkey41: .quad 0x3d228e1935cd3a51
+.section .text
+    adr x1, .Lkey41
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #127
+
+.section .data
+.balign 8
+.Lkey42: .quad 0x3d228b1935cd3538
+.section .text
+    adr x1, .Lkey42
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #130
+
+.section .data
+.balign 8
+.Lkey43: .quad 0x3d228c1935cd36eb
+.section .text
+    adr x1, .Lkey43
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #133
+
+.section .data
+.balign 8
+.Lkey44: .quad 0x3d22911935cd3f6a
+.section .text
+    adr x1, .Lkey44
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #136
+
+.section .data
+.balign 8
+.Lkey45: .quad 0x3d22921935cd411d
+.section .text
+    adr x1, .Lkey45
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #139
+
+.section .data
+.balign 8
+.Lkey46: .quad 0x3d228f1935cd3c04
+.section .text
+    adr x1, .Lkey46
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #142
+
+.section .data
+.balign 8
+.Lkey47: .quad 0x3d22901935cd3db7
+.section .text
+    adr x1, .Lkey47
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #145
+
+.section .data
+.balign 8
+.Lkey48: .quad 0x3d22851935cd2b06
+.section .text
+    adr x1, .Lkey48
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #148
+
+.section .data
+.balign 8
+.Lkey49: .quad 0x3d22861935cd2cb9
+.section .text
+    adr x1, .Lkey49
+    bl __hamer_map_put
+    mov x0, x12
+    mov x2, #1000
+    adr x1, .Lkey7
+    bl __hamer_map_put
+    mov x0, x12
+    adr x1, .Lkey0
+    bl __hamer_map_get
+    mov x13, x0
+    mov x0, x12
+    adr x1, .Lkey7
+    bl __hamer_map_get
+    mov x14, x0
+    mov x0, x12
+    adr x1, .Lkey24
+    bl __hamer_map_get
+    mov x15, x0
+    mov x0, x12
+    adr x1, .Lkey25
+    bl __hamer_map_get
+    mov x16, x0
+    mov x0, x12
+    adr x1, .Lkey49
+    bl __hamer_map_get
+    mov x17, x0
+    mov x0, x12
+
+.section .data
+.balign 8
+.Lkey50: .quad 0x3beb1bbad14c75e1
+.section .text
+    adr x1, .Lkey50
+    bl __hamer_map_get
+    mov x18, x0
+    mov x0, x13
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr0: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x14
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr1: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x15
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr2: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x16
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr3: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x17
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr4: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x18
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr5: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr6: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr6
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_map_put, %function
+
+__hamer_map_put:
+    ldp x3, x4, [x0]
+    ldr x5, [x1]
+.Lrt_mp_probe:
+    and x5, x5, x4
+    add x6, x3, x5, lsl #4
+    ldr x7, [x6]
+    cmp x7, x1
+    b.eq .Lrt_mp_store
+    cbz x7, .Lrt_mp_new
+    add x5, x5, #1
+    b .Lrt_mp_probe
+.Lrt_mp_new:
+    ldr x7, [x0, #16]
+    add x7, x7, #1
+    add x8, x4, #1
+    add x8, x8, x8, lsl #1
+    cmp x8, x7, lsl #2
+    b.lo .Lrt_mp_grow
+    str x7, [x0, #16]
+.Lrt_mp_store:
+    stp x1, x2, [x6]
+    ret
+.Lrt_mp_grow:
+    stp x30, x2, [sp, #-16]!
+    add x9, x4, #1
+    lsl x9, x9, #5
+    bl __hamer_heap_reserve
+    add x7, x4, #1
+    lsl x7, x7, #5
+    mov x8, x28
+    add x28, x28, x7
+    lsl x7, x4, #1
+    add x7, x7, #1
+    stp x8, x7, [x0]
+    mov x5, #0
+.Lrt_mp_move:
+    add x6, x3, x5, lsl #4
+    ldp x9, x10, [x6]
+    cbz x9, .Lrt_mp_moved
+    ldr x11, [x9]
+.Lrt_mp_slot:
+    and x11, x11, x7
+    add x6, x8, x11, lsl #4
+    ldr x2, [x6]
+    add x11, x11, #1
+    cbnz x2, .Lrt_mp_slot
+    stp x9, x10, [x6]
+.Lrt_mp_moved:
+    add x5, x5, #1
+    cmp x5, x4
+    b.ls .Lrt_mp_move
+    ldp x30, x2, [sp], #16
+    b __hamer_map_put
+.size __hamer_map_put, . - __hamer_map_put
+.type __hamer_map_get, %function
+
+__hamer_map_get:
+    ldp x3, x4, [x0]
+    ldr x5, [x1]
+.Lrt_mg_probe:
+    and x5, x5, x4
+    add x6, x3, x5, lsl #4
+    ldp x7, x8, [x6]
+    cbz x7, .Lrt_mg_miss
+    cmp x7, x1
+    b.eq .Lrt_mg_hit
+    add x5, x5, #1
+    b .Lrt_mg_probe
+.Lrt_mg_miss:
+    mov x0, #0
+    ret
+.Lrt_mg_hit:
+    mov x0, x8
+    ret
+.size __hamer_map_get, . - __hamer_map_get
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 8
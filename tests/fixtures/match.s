@@ -0,0 +1,264 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+
+.section .data
+.Lstr0: .ascii "Open the door?\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #15
+    mov x8, #64
+    svc #0
+    mov x9, #288
+    bl __hamer_heap_reserve
+    mov x12, x28
+    add x1, x28, #32
+    str x1, [x12]
+    mov x1, #256
+    str x1, [x12, #16]
+    add x28, x28, #288
+    ldr x0, [x12]
+    mov x1, #256
+    bl __hamer_read_line
+    str x0, [x12, #8]
+
+.section .data
+.Lstr2: .ascii "yes"
+.section .text
+    mov x0, x12
+    adr x1, .Lstr2
+    mov x2, #3
+    bl __hamer_text_eq
+    cbz x0, .Lmatch1_0
+
+.section .data
+.Lstr3: .ascii "It creaks open.\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #16
+    mov x8, #64
+    svc #0
+    b .Lmatch1_end
+.Lmatch1_0:
+
+.section .data
+.Lstr4: .ascii "no"
+.section .text
+    mov x0, x12
+    adr x1, .Lstr4
+    mov x2, #2
+    bl __hamer_text_eq
+    cbz x0, .Lmatch1_1
+
+.section .data
+.Lstr5: .ascii "You wait.\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #10
+    mov x8, #64
+    svc #0
+    b .Lmatch1_end
+.Lmatch1_1:
+
+.section .data
+.Lstr6: .ascii "Eh?\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr6
+    mov x2, #4
+    mov x8, #64
+    svc #0
+.Lmatch1_end:
+
+.section .data
+.Lstr7: .ascii "!"
+.section .text
+    adr x1, .Lstr7
+    mov x2, #1
+    mov x0, x12
+    bl __hamer_text_append
+    ldp x1, x2, [x12]
+    mov x0, #1
+    mov x8, #64
+    svc #0
+
+.section .data
+.Lstr8: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr8
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr9: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr9
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_read_line, %function
+
+__hamer_read_line:
+    sub sp, sp, #16
+    mov x4, x0
+    mov x5, x0
+    add x6, x0, x1
+.Lrt_rl_next:
+    mov x0, #0
+    mov x1, sp
+    mov x2, #1
+    mov x8, #63
+    svc #0
+    cmp x0, #1
+    b.ne .Lrt_rl_done
+    ldrb w7, [sp]
+    cmp w7, #10
+    b.eq .Lrt_rl_done
+    cmp x4, x6
+    b.hs .Lrt_rl_next
+    strb w7, [x4], #1
+    b .Lrt_rl_next
+.Lrt_rl_done:
+    sub x0, x4, x5
+    add sp, sp, #16
+    ret
+.size __hamer_read_line, . - __hamer_read_line
+.type __hamer_text_eq, %function
+
+__hamer_text_eq:
+    ldp x3, x4, [x0]
+    mov x0, #0
+    cmp x4, x2
+    b.ne .Lrt_te_done
+.Lrt_te_loop:
+    cbz x2, .Lrt_te_same
+    ldrb w5, [x3], #1
+    ldrb w6, [x1], #1
+    cmp w5, w6
+    b.ne .Lrt_te_done
+    sub x2, x2, #1
+    b .Lrt_te_loop
+.Lrt_te_same:
+    mov x0, #1
+.Lrt_te_done:
+    ret
+.size __hamer_text_eq, . - __hamer_text_eq
+.type __hamer_text_append, %function
+
+__hamer_text_append:
+    ldp x3, x4, [x0]
+    ldr x5, [x0, #16]
+    add x6, x4, x2
+    cmp x6, x5
+    b.ls .Lrt_ta_copy
+.Lrt_ta_grow:
+    lsl x5, x5, #1
+    cmp x6, x5
+    b.hi .Lrt_ta_grow
+    str x30, [sp, #-16]!
+    add x9, x5, #15
+    bl __hamer_heap_reserve
+    ldr x30, [sp], #16
+    mov x7, x28
+    add x28, x28, x5
+    add x28, x28, #15
+    and x28, x28, #0xfffffffffffffff0
+    mov x8, #0
+.Lrt_ta_move:
+    cmp x8, x4
+    b.hs .Lrt_ta_moved
+    ldrb w9, [x3, x8]
+    strb w9, [x7, x8]
+    add x8, x8, #1
+    b .Lrt_ta_move
+.Lrt_ta_moved:
+    mov x3, x7
+    str x3, [x0]
+    str x5, [x0, #16]
+.Lrt_ta_copy:
+    add x7, x3, x4
+    mov x8, #0
+.Lrt_ta_append:
+    cmp x8, x2
+    b.hs .Lrt_ta_done
+    ldrb w9, [x1, x8]
+    strb w9, [x7, x8]
+    add x8, x8, #1
+    b .Lrt_ta_append
+.Lrt_ta_done:
+    str x6, [x0, #8]
+    ret
+.size __hamer_text_append, . - __hamer_text_append
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 8
@@ -0,0 +1,190 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x0, #5
+    bl fn_fact
+    mov x22, x0
+    mov x0, #10
+    bl fn_fib
+    mov x23, x0
+    mov x0, x22
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr2: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x23
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr3: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.type fn_fact, %function
+fn_fact:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_fact_body:
+    mov x12, x0
+    mov x1, x12
+    cmp x1, #2
+    b.ge .Lif0_end
+    mov x0, #1
+    b .Lfn_fact_ret
+.Lif0_end:
+    sub x13, x12, #1
+    mov x0, x13
+    sub sp, sp, #32
+    stp x12, x13, [sp, #0]
+    stp x14, x15, [sp, #16]
+    bl fn_fact
+    ldp x12, x13, [sp, #0]
+    ldp x14, x15, [sp, #16]
+    add sp, sp, #32
+    mov x14, x0
+    mul x15, x12, x14
+    mov x0, x15
+    b .Lfn_fact_ret
+.Lfn_fact_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_fact, . - fn_fact
+
+.type fn_fib, %function
+fn_fib:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_fib_body:
+    mov x16, x0
+    mov x1, x16
+    cmp x1, #2
+    b.ge .Lif1_end
+    mov x0, x16
+    b .Lfn_fib_ret
+.Lif1_end:
+    sub x17, x16, #1
+    sub x18, x16, #2
+    mov x0, x17
+    sub sp, sp, #48
+    stp x16, x17, [sp, #0]
+    stp x18, x19, [sp, #16]
+    stp x20, x21, [sp, #32]
+    bl fn_fib
+    ldp x16, x17, [sp, #0]
+    ldp x18, x19, [sp, #16]
+    ldp x20, x21, [sp, #32]
+    add sp, sp, #48
+    mov x19, x0
+    mov x0, x18
+    sub sp, sp, #48
+    stp x16, x17, [sp, #0]
+    stp x18, x19, [sp, #16]
+    stp x20, x21, [sp, #32]
+    bl fn_fib
+    ldp x16, x17, [sp, #0]
+    ldp x18, x19, [sp, #16]
+    ldp x20, x21, [sp, #32]
+    add sp, sp, #48
+    mov x20, x0
+    add x21, x19, x20
+    mov x0, x21
+    b .Lfn_fib_ret
+.Lfn_fib_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_fib, . - fn_fib
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
@@ -0,0 +1,364 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    mov x9, #88
+    bl __hamer_heap_reserve
+    mov x9, #5
+    str x9, [x28]
+    add x12, x28, #8
+    ldr x9, =88
+    add x28, x28, x9
+    mov x1, #40
+    add x9, x12, #0
+    str x1, [x9, #0]
+    mov x1, #1
+    add x9, x12, #0
+    str x1, [x9, #8]
+    mov x1, #75
+    add x9, x12, #16
+    str x1, [x9, #0]
+    mov x1, #2
+    add x9, x12, #16
+    str x1, [x9, #8]
+    mov x1, #10
+    add x9, x12, #32
+    str x1, [x9, #0]
+    mov x1, #3
+    add x9, x12, #32
+    str x1, [x9, #8]
+    mov x1, #75
+    add x9, x12, #48
+    str x1, [x9, #0]
+    mov x1, #4
+    add x9, x12, #48
+    str x1, [x9, #8]
+    mov x1, #-5
+    add x9, x12, #64
+    str x1, [x9, #0]
+    mov x1, #5
+    add x9, x12, #64
+    str x1, [x9, #8]
+    mov x0, x12
+    mov x1, #5
+    mov x2, #16
+    mov x3, #0
+    bl __hamer_sort
+    mov x13, x12
+    ldr x9, =80
+    add x14, x12, x9
+.Lfor0:
+    cmp x13, x14
+    b.hs .Lfor0_end
+    ldr x0, [x13, #8]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr1: .ascii " "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    ldr x0, [x13, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr2: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    add x13, x13, #16
+    b .Lfor0
+.Lfor0_end:
+    mov x0, x12
+    mov x1, #5
+    mov x2, #16
+    mov x3, #0
+    mov x4, #75
+    bl __hamer_find
+    mov x15, x0
+    mov x0, x15
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr3: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x12
+    mov x1, #5
+    mov x2, #16
+    mov x3, #8
+    mov x4, #3
+    bl __hamer_find
+    mov x16, x0
+    mov x0, x16
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr4: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    mov x0, x12
+    mov x1, #5
+    mov x2, #16
+    mov x3, #8
+    bl __hamer_sort
+    mov x0, x12
+    mov x1, #5
+    mov x2, #16
+    mov x3, #0
+    mov x4, #12
+    bl __hamer_find
+    mov x17, x0
+    mov x0, x17
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr5: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    add x9, x12, #32
+    ldr x0, [x9, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr6: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr6
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    adr x10, .Lheap_end
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr7: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr7
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_sort, %function
+
+__hamer_sort:
+    mov x4, #1
+.Lrt_so_outer:
+    cmp x4, x1
+    b.hs .Lrt_so_done
+    madd x5, x4, x2, x0
+.Lrt_so_inner:
+    cmp x5, x0
+    b.ls .Lrt_so_next
+    sub x6, x5, x2
+    ldr x7, [x6, x3]
+    ldr x8, [x5, x3]
+    cmp x7, x8
+    b.le .Lrt_so_next
+    mov x7, #0
+.Lrt_so_swap:
+    ldr x8, [x6, x7]
+    ldr x9, [x5, x7]
+    str x9, [x6, x7]
+    str x8, [x5, x7]
+    add x7, x7, #8
+    cmp x7, x2
+    b.lo .Lrt_so_swap
+    mov x5, x6
+    b .Lrt_so_inner
+.Lrt_so_next:
+    add x4, x4, #1
+    b .Lrt_so_outer
+.Lrt_so_done:
+    ret
+.size __hamer_sort, . - __hamer_sort
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+.type __hamer_find, %function
+
+__hamer_find:
+    add x5, x0, x3
+    mov x0, #0
+.Lrt_fi_loop:
+    cmp x0, x1
+    b.hs .Lrt_fi_miss
+    ldr x6, [x5]
+    cmp x6, x4
+    b.eq .Lrt_fi_done
+    add x5, x5, x2
+    add x0, x0, #1
+    b .Lrt_fi_loop
+.Lrt_fi_miss:
+    mov x0, #-1
+.Lrt_fi_done:
+    ret
+.size __hamer_find, . - __hamer_find
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 8
@@ -0,0 +1,198 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #5
+    mov x13, #0
+.Lwhile0:
+    mov x1, x13
+    cmp x1, #6
+    b.ge .Lwhile0_end
+    add x13, x13, #1
+.Lmachine1:
+    adr x9, .Lmachine1_state
+    ldr x9, [x9]
+    cmp x9, #1
+    b.eq .Lmachine1_1
+    cmp x9, #2
+    b.eq .Lmachine1_2
+.Lmachine1_0:
+
+.section .data
+.Lstr2: .ascii "idle "
+.section .text
+    mov x0, #1
+    adr x1, .Lstr2
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    mov x0, x12
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr3: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #1
+    mov x8, #64
+    svc #0
+    sub x12, x12, #2
+    mov x1, x12
+    cmp x1, #2
+    b.ge .Lif4_end
+    mov x10, #1
+    adr x9, .Lmachine1_state
+    str x10, [x9]
+    b .Lmachine1
+.Lif4_end:
+    b .Lmachine1_end
+.Lmachine1_1:
+
+.section .data
+.Lstr5: .ascii "chase\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    mov x1, x12
+    cmp x1, #1
+    b.ge .Lif6_end
+    mov x10, #2
+    adr x9, .Lmachine1_state
+    str x10, [x9]
+    b .Lmachine1
+.Lif6_end:
+    sub x12, x12, #1
+    b .Lmachine1_end
+.Lmachine1_2:
+
+.section .data
+.Lstr7: .ascii "attack!\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr7
+    mov x2, #8
+    mov x8, #64
+    svc #0
+.Lmachine1_end:
+    b .Lwhile0
+.Lwhile0_end:
+.Lmachine8:
+    adr x9, .Lmachine8_state
+    ldr x9, [x9]
+    cmp x9, #1
+    b.eq .Lmachine8_1
+.Lmachine8_0:
+
+.section .data
+.Lstr9: .ascii "knock\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr9
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    mov x10, #1
+    adr x9, .Lmachine8_state
+    str x10, [x9]
+    b .Lmachine8
+    b .Lmachine8_end
+.Lmachine8_1:
+
+.section .data
+.Lstr10: .ascii "come in\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr10
+    mov x2, #8
+    mov x8, #64
+    svc #0
+.Lmachine8_end:
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lmachine1_state: .zero 8
+.balign 8
+.Lmachine8_state: .zero 8
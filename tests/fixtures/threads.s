@@ -0,0 +1,229 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x9, #1048576
+    add x9, x28, x9
+    adr x10, .Lheap_end
+    str x9, [x10]
+    adr x10, .Lheap_end
+    msr tpidr_el0, x10
+    mov x9, #8
+    bl __hamer_heap_reserve
+    mov x12, x28
+    add x28, x28, #8
+    mov x13, #0
+.Lwhile0:
+    mov x1, x13
+    cmp x1, #4
+    b.ge .Lwhile0_end
+    mov x9, #16384
+    bl __hamer_heap_reserve
+    mov x3, x28
+    add x28, x28, #16384
+    stp x28, xzr, [x3]
+    adr x10, .Lthreads
+.Lspawn1_push:
+    ldaxr x9, [x10]
+    stp xzr, x9, [x3, #16]
+    stlxr w11, x3, [x10]
+    cbnz w11, .Lspawn1_push
+    ldr x0, =0x3d0f00
+    mov x1, x28
+    add x2, x3, #16
+    mov x4, x2
+    mov x8, #220
+    svc #0
+    cbnz x0, .Lspawn1
+    mov x9, #8
+    bl __hamer_heap_reserve
+    mov x14, x28
+    add x28, x28, #8
+    mov x1, #10
+    str x1, [x14, #0]
+    ldr x2, [x14, #0]
+    add x10, x12, #0
+.Latomic2:
+    ldaxr x1, [x10]
+    add x1, x1, x2
+    stlxr w3, x1, [x10]
+    cbnz w3, .Latomic2
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.Lspawn1:
+    add x13, x13, #1
+    b .Lwhile0
+.Lwhile0_end:
+    adr x9, .Lthreads
+    ldar x9, [x9]
+.Ljoin3:
+    cbz x9, .Ljoin3_done
+.Ljoin3_wait:
+    ldr w2, [x9, #16]
+    cbz w2, .Ljoin3_next
+    add x0, x9, #16
+    mov x1, #0
+    mov x3, #0
+    mov x8, #98
+    svc #0
+    b .Ljoin3_wait
+.Ljoin3_next:
+    ldr x9, [x9, #24]
+    b .Ljoin3
+.Ljoin3_done:
+    mov x9, #8
+    bl __hamer_heap_reserve
+    mov x15, x28
+    add x28, x28, #8
+    ldr x0, [x12, #0]
+    add x1, x0, #2
+    str x1, [x15, #0]
+    ldr x0, [x15, #0]
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr4: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr4
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+.type __hamer_heap_reserve, %function
+__hamer_heap_reserve:
+    mrs x10, tpidr_el0
+    ldr x11, [x10]
+    cbz x11, .Lheap_grow_done
+    sub x11, x11, x28
+    cmp x11, x9
+    b.lo .Lheap_grow
+.Lheap_grow_done:
+    ret
+.Lheap_grow:
+    stp x0, x1, [sp, #-64]!
+    stp x2, x3, [sp, #16]
+    stp x4, x5, [sp, #32]
+    str x8, [sp, #48]
+    add x9, x9, #15
+    and x9, x9, #-16
+    mov x1, #1048576
+    cmp x9, x1
+    csel x11, x9, x1, hi
+    mov x0, #0
+    mov x1, x11
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    cmn x0, #4095
+    b.hs .Lheap_grow_full
+    mov x28, x0
+    add x11, x0, x11
+    str x11, [x10]
+    ldp x2, x3, [sp, #16]
+    ldp x4, x5, [sp, #32]
+    ldr x8, [sp, #48]
+    ldp x0, x1, [sp], #64
+    ret
+.Lheap_grow_full:
+
+.section .data
+.Lstr5: .ascii "error: out of memory\n"
+.section .text
+    mov x0, #2
+    adr x1, .Lstr5
+    mov x2, #21
+    mov x8, #64
+    svc #0
+    mov x0, #1
+    mov x8, #94
+    svc #0
+.size __hamer_heap_reserve, . - __hamer_heap_reserve
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Lheap_end: .zero 16
+.balign 8
+.Lthreads: .zero 8
@@ -0,0 +1,295 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #0
+    mov x0, #30
+    mrs x9, cntfrq_el0
+    mul x0, x0, x9
+    mov x9, #1000
+    sdiv x0, x0, x9
+    mrs x9, cntvct_el0
+    add x0, x0, x9
+    adr x9, .Ltimers_at
+    str x0, [x9, #0]
+    mov x0, #10
+    mrs x9, cntfrq_el0
+    mul x0, x0, x9
+    mov x9, #1000
+    sdiv x0, x0, x9
+    mrs x9, cntvct_el0
+    add x0, x0, x9
+    adr x9, .Ltimers_at
+    str x0, [x9, #8]
+
+.section .data
+.Lstr6: .ascii "start\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr6
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    mov x13, #0
+    mov x0, #1
+    adr x1, .Levery7_deadline
+    mov x8, #113
+    svc #0
+.Levery7:
+    add x13, x13, #1
+    mov x1, x13
+    cmp x1, #20
+    b.ne .Lif8_end
+    b .Llabel0_out
+.Lif8_end:
+    bl .Ltimers_run
+    mov x0, #5
+    mov x9, #0x4240
+    movk x9, #0xf, lsl #16
+    mul x0, x0, x9
+    adr x2, .Levery7_deadline
+    ldp x9, x10, [x2]
+    add x10, x10, x0
+    mov x11, #0xca00
+    movk x11, #0x3b9a, lsl #16
+.Levery7_carry:
+    cmp x10, x11
+    b.lo .Levery7_sleep
+    sub x10, x10, x11
+    add x9, x9, #1
+    b .Levery7_carry
+.Levery7_sleep:
+    stp x9, x10, [x2]
+    mov x0, #1
+    mov x1, #1
+    mov x3, #0
+    mov x8, #115
+    svc #0
+    b .Levery7
+.Llabel0_out:
+    mov x0, x12
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr9: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr9
+    mov x2, #1
+    mov x8, #64
+    svc #0
+.Lwait10:
+    bl .Ltimers_run
+    cbz x0, .Lwait10_done
+    mrs x9, cntvct_el0
+    subs x0, x0, x9
+    b.ls .Lwait10
+    mrs x9, cntfrq_el0
+    udiv x10, x0, x9
+    msub x0, x10, x9, x0
+    mov x11, #0xca00
+    movk x11, #0x3b9a, lsl #16
+    mul x0, x0, x11
+    udiv x11, x0, x9
+    adr x0, .Ltimers_sleep
+    stp x10, x11, [x0]
+    mov x1, #0
+    mov x8, #101
+    svc #0
+    b .Lwait10
+.Lwait10_done:
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.Lafter0:
+    str x30, [sp, #-16]!
+
+.section .data
+.Lstr1: .ascii "second\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #7
+    mov x8, #64
+    svc #0
+    add x12, x12, #2
+    ldr x30, [sp], #16
+    ret
+
+.Lafter4:
+    str x30, [sp, #-16]!
+
+.section .data
+.Lstr5: .ascii "last\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr5
+    mov x2, #5
+    mov x8, #64
+    svc #0
+    ldr x30, [sp], #16
+    ret
+
+.Lafter2:
+    str x30, [sp, #-16]!
+
+.section .data
+.Lstr3: .ascii "first\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr3
+    mov x2, #6
+    mov x8, #64
+    svc #0
+    add x12, x12, #1
+    mov x0, #200
+    mrs x9, cntfrq_el0
+    mul x0, x0, x9
+    mov x9, #1000
+    sdiv x0, x0, x9
+    mrs x9, cntvct_el0
+    add x0, x0, x9
+    adr x9, .Ltimers_at
+    str x0, [x9, #16]
+    ldr x30, [sp], #16
+    ret
+
+.Ltimers_run:
+    str x30, [sp, #-16]!
+.Ltimers_run_scan:
+    mov x10, #0
+    adr x9, .Ltimers_at
+    ldr x0, [x9, #0]
+    cbz x0, .Ltimers_run_0
+    cbz x10, .Ltimers_run_0_take
+    cmp x0, x10
+    b.hs .Ltimers_run_0
+.Ltimers_run_0_take:
+    mov x10, x0
+    mov x11, #0
+.Ltimers_run_0:
+    ldr x0, [x9, #8]
+    cbz x0, .Ltimers_run_1
+    cbz x10, .Ltimers_run_1_take
+    cmp x0, x10
+    b.hs .Ltimers_run_1
+.Ltimers_run_1_take:
+    mov x10, x0
+    mov x11, #1
+.Ltimers_run_1:
+    ldr x0, [x9, #16]
+    cbz x0, .Ltimers_run_2
+    cbz x10, .Ltimers_run_2_take
+    cmp x0, x10
+    b.hs .Ltimers_run_2
+.Ltimers_run_2_take:
+    mov x10, x0
+    mov x11, #2
+.Ltimers_run_2:
+    mov x0, x10
+    cbz x0, .Ltimers_run_done
+    mrs x1, cntvct_el0
+    cmp x1, x0
+    b.lo .Ltimers_run_done
+    str xzr, [x9, x11, lsl #3]
+    cmp x11, #1
+    b.eq .Ltimers_run_1_fire
+    cmp x11, #2
+    b.eq .Ltimers_run_2_fire
+.Ltimers_run_0_fire:
+    bl .Lafter0
+    b .Ltimers_run_scan
+.Ltimers_run_1_fire:
+    bl .Lafter2
+    b .Ltimers_run_scan
+.Ltimers_run_2_fire:
+    bl .Lafter4
+    b .Ltimers_run_scan
+.Ltimers_run_done:
+    ldr x30, [sp], #16
+    ret
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
+
+.section .bss
+.balign 8
+.Levery7_deadline: .zero 16
+.balign 8
+.Ltimers_sleep: .zero 16
+.balign 8
+.Ltimers_at: .zero 24
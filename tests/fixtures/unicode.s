@@ -0,0 +1,39 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+
+.section .data
+.Lstr0: .ascii "héllo → wörld\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #18
+    mov x8, #64
+    svc #0
+    // ünïcode
+    nop
+
+.section .data
+.Lstr1: .ascii "ok\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr1
+    mov x2, #3
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
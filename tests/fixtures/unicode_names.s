@@ -0,0 +1,157 @@
+.global _start
+.type _start, %function
+.section .text
+
+_start:
+    mov x0, #0
+    mov x1, #1048576
+    mov x2, #3
+    mov x3, #34
+    mov x4, #-1
+    mov x5, #0
+    mov x8, #222
+    svc #0
+    mov x28, x0
+    mov x12, #3
+    add x13, x12, #2
+    mov x0, x13
+    bl fn_verdoppeln
+    mov x16, x0
+".Llabel0_schleife_ü":
+    mov x0, x16
+    mov x1, #10
+    mov x2, #0
+    mov x3, #32
+    mov x4, #1
+    mov x8, #1
+    bl __hamer_print_num
+
+.section .data
+.Lstr0: .ascii "\n"
+.section .text
+    mov x0, #1
+    adr x1, .Lstr0
+    mov x2, #1
+    mov x8, #64
+    svc #0
+
+    mov x0, #0
+    mov x8, #93
+    svc #0
+.size _start, . - _start
+
+.type fn_verdoppeln, %function
+fn_verdoppeln:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lfn_verdoppeln_body:
+    mov x14, x0
+    mov x0, x14
+    b .Lfn_verdoppeln_ret
+.Lfn_verdoppeln_ret:
+    ldp x29, x30, [sp], #16
+    ret
+.size fn_verdoppeln, . - fn_verdoppeln
+
+.type "fn_doppelt_ä", %function
+"fn_doppelt_ä":
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+".Lfn_doppelt_ä_body":
+    mov x15, x0
+    mov x0, x15
+    b ".Lfn_doppelt_ä_ret"
+".Lfn_doppelt_ä_ret":
+    ldp x29, x30, [sp], #16
+    ret
+.size "fn_doppelt_ä", . - "fn_doppelt_ä"
+
+.section .data
+.balign 8
+.global hamer_heap
+hamer_heap: .quad 0
+.size hamer_heap, . - hamer_heap
+.section .text
+
+.global "doppelt_ä"
+.type "doppelt_ä", %function
+"doppelt_ä":
+    stp x29, x30, [sp, #-96]!
+    mov x29, sp
+    stp x19, x20, [sp, #16]
+    stp x21, x22, [sp, #32]
+    stp x23, x24, [sp, #48]
+    stp x25, x26, [sp, #64]
+    stp x27, x28, [sp, #80]
+    adr x9, hamer_heap
+    ldr x28, [x9]
+    bl "fn_doppelt_ä"
+    adr x9, hamer_heap
+    str x28, [x9]
+    ldp x27, x28, [sp, #80]
+    ldp x25, x26, [sp, #64]
+    ldp x23, x24, [sp, #48]
+    ldp x21, x22, [sp, #32]
+    ldp x19, x20, [sp, #16]
+    ldp x29, x30, [sp], #96
+    ret
+.size "doppelt_ä", . - "doppelt_ä"
+.type __hamer_print_num, %function
+
+__hamer_print_num:
+    sub sp, sp, #80
+    add x5, sp, #80
+    mov x9, #0
+    cbz x4, .Lrt_pn_digit
+    tbz x0, #63, .Lrt_pn_digit
+    neg x0, x0
+    mov x9, #1
+.Lrt_pn_digit:
+    udiv x6, x0, x1
+    msub x7, x6, x1, x0
+    add x7, x7, #48
+    cmp x7, #57
+    b.ls .Lrt_pn_store
+    add x7, x7, #39
+.Lrt_pn_store:
+    sub x5, x5, #1
+    strb w7, [x5]
+    mov x0, x6
+    cbnz x0, .Lrt_pn_digit
+    cbz x9, .Lrt_pn_pad
+    cmp w3, #48
+    b.eq .Lrt_pn_zpad
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_zpad:
+    add x6, sp, #81
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_sign
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_zpad
+.Lrt_pn_sign:
+    mov w7, #45
+    sub x5, x5, #1
+    strb w7, [x5]
+.Lrt_pn_pad:
+    add x6, sp, #80
+    sub x6, x6, x5
+    cmp x6, x2
+    b.hs .Lrt_pn_write
+    sub x5, x5, #1
+    strb w3, [x5]
+    b .Lrt_pn_pad
+.Lrt_pn_write:
+    mov x0, x8
+    mov x1, x5
+    add x2, sp, #80
+    sub x2, x2, x5
+    mov x8, #64
+    svc #0
+    add sp, sp, #80
+    ret
+.size __hamer_print_num, . - __hamer_print_num
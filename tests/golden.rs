@@ -0,0 +1,163 @@
+//! Golden tests for code generation. Every `tests/fixtures/*.hmr` is
+//! compiled and the assembly compared against the `.s` file next to it.
+//! On hosts that can run ARM64 Linux programs (natively, or through
+//! qemu-aarch64), fixtures that have a `.stdout` file are also run with
+//! `hamer run` and their stdout compared against it (stdin comes from
+//! `.stdin` if present). What the program writes to stderr is compared
+//! against `.stderr` the same way, so diagnostics can't pass for output,
+//! and a nonzero exit status against `.status`.
+//!
+//! A fixture with no `.s` file is one the compiler must reject: what it
+//! reports (progress lines and the fixtures directory left out) is
+//! compared against `.stderr` instead.
+//!
+//! Set `UPDATE_SNAPSHOTS=1` to rewrite the golden files from the current
+//! output instead of comparing; where programs can run, that also writes
+//! every missing `.stdout`, and a `.stderr` or `.status` for programs
+//! that print to stderr or exit with a nonzero status.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+fn can_run_programs() -> bool {
+    if cfg!(all(target_arch = "aarch64", target_os = "linux")) { return true; }
+    let Some(path) = std::env::var_os("PATH") else { return false };
+    std::env::split_paths(&path).any(|dir| dir.join("qemu-aarch64").is_file() || dir.join("qemu-aarch64-static").is_file())
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut found: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("tests/fixtures is missing")
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "hmr"))
+        .collect();
+    found.sort();
+    found
+}
+
+/// Why a fixture didn't compile.
+enum Failure {
+    /// The compiler reported errors, as written to stderr.
+    Rejected(String),
+    /// The compiler couldn't be started.
+    Broken(String),
+}
+
+/// Compiles `source` in a scratch directory of its own (the compiler
+/// writes `<name>.s` to the working directory) and returns that directory.
+fn compile(source: &Path) -> Result<PathBuf, Failure> {
+    let name = source.file_stem().unwrap().to_string_lossy();
+    let work = std::env::temp_dir().join(format!("hamer-golden-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&work).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_hamer"))
+        .arg(source)
+        .current_dir(&work)
+        .output()
+        .map_err(|e| Failure::Broken(format!("cannot start hamer: {}", e)))?;
+    if !out.status.success() {
+        let _ = fs::remove_dir_all(&work);
+        let dir = format!("{}/", source.parent().unwrap().display());
+        let stderr = String::from_utf8_lossy(&out.stderr).lines()
+            .filter(|l| !l.starts_with("[H@mer]"))
+            .map(|l| format!("{}\n", l.replace(&dir, "")))
+            .collect();
+        return Err(Failure::Rejected(stderr));
+    }
+    Ok(work)
+}
+
+/// What a program run with `hamer run` wrote, and the status it exited with.
+struct Outcome { stdout: String, stderr: String, status: i32 }
+
+/// Builds and runs `source` with `hamer run` in `work`.
+fn run(source: &Path, work: &Path, stdin: Option<Vec<u8>>) -> Result<Outcome, String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hamer"))
+        .arg("run")
+        .arg(source)
+        .current_dir(work)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot start hamer: {}", e))?;
+    if let Some(bytes) = stdin {
+        // Dropping the handle afterwards closes the pipe, so reads see EOF
+        child.stdin.take().unwrap().write_all(&bytes).map_err(|e| e.to_string())?;
+    }
+    let out = child.wait_with_output().map_err(|e| e.to_string())?;
+    Ok(Outcome {
+        stdout: String::from_utf8_lossy(&out.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&out.stderr).into_owned(),
+        // `hamer run` exits with the program's own status
+        status: out.status.code().unwrap_or(-1),
+    })
+}
+
+/// Compares `actual` against the golden file at `path`, or rewrites it.
+fn check(path: &Path, actual: &str, update: bool, failures: &mut Vec<String>) {
+    if update {
+        fs::write(path, actual).unwrap();
+        return;
+    }
+    match fs::read_to_string(path) {
+        Ok(expected) if expected == actual => {}
+        Ok(expected) => {
+            let line = expected.lines().zip(actual.lines()).position(|(e, a)| e != a)
+                .unwrap_or(expected.lines().count().min(actual.lines().count()));
+            failures.push(format!(
+                "{}: output differs from line {}\n  expected: {:?}\n  actual:   {:?}",
+                path.display(), line + 1,
+                expected.lines().nth(line).unwrap_or("<end of file>"),
+                actual.lines().nth(line).unwrap_or("<end of file>"),
+            ));
+        }
+        Err(_) => failures.push(format!("{}: no golden file (run with UPDATE_SNAPSHOTS=1)", path.display())),
+    }
+}
+
+#[test]
+fn codegen_matches_golden_files() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let run_programs = can_run_programs();
+    let mut failures = Vec::new();
+    for source in fixtures() {
+        let work = match compile(&source) {
+            Ok(work) => work,
+            Err(Failure::Rejected(stderr)) if !source.with_extension("s").exists() => {
+                check(&source.with_extension("stderr"), &stderr, update, &mut failures);
+                continue;
+            }
+            Err(Failure::Rejected(stderr)) => { failures.push(format!("{}: hamer failed:\n{}", source.display(), stderr)); continue; }
+            Err(Failure::Broken(e)) => { failures.push(format!("{}: {}", source.display(), e)); continue; }
+        };
+        let name = source.file_stem().unwrap().to_string_lossy();
+        let asm = fs::read_to_string(work.join(format!("{}.s", name))).unwrap();
+        check(&source.with_extension("s"), &asm, update, &mut failures);
+
+        let stdout_golden = source.with_extension("stdout");
+        let stderr_golden = source.with_extension("stderr");
+        let status_golden = source.with_extension("status");
+        if run_programs && (update || stdout_golden.exists() || stderr_golden.exists() || status_golden.exists()) {
+            let stdin = fs::read(source.with_extension("stdin")).ok();
+            match run(&source, &work, stdin) {
+                Ok(outcome) => {
+                    check(&stdout_golden, &outcome.stdout, update, &mut failures);
+                    if stderr_golden.exists() || (update && !outcome.stderr.is_empty()) {
+                        check(&stderr_golden, &outcome.stderr, update, &mut failures);
+                    }
+                    if status_golden.exists() || (update && outcome.status != 0) {
+                        check(&status_golden, &format!("{}\n", outcome.status), update, &mut failures);
+                    } else if outcome.status != 0 {
+                        failures.push(format!("{}: exited with status {} (no .status file)", source.display(), outcome.status));
+                    }
+                }
+                Err(e) => failures.push(format!("{}: {}", source.display(), e)),
+            }
+        }
+        let _ = fs::remove_dir_all(&work);
+    }
+    assert!(failures.is_empty(), "{} golden mismatch(es):\n{}", failures.len(), failures.join("\n"));
+}